@@ -208,6 +208,22 @@ export class Api {
     });
   }
 
+  /**
+   * Edit the restrictions of an alias.
+   *
+   * @param {object} key key of the alias to edit
+   * @param {object} restrictions the new cooldown, user_cooldown, and scope of the alias
+   */
+  aliasesEditRestrictions(key, restrictions) {
+    return this.fetch(["aliases", key.channel, key.name, "restrictions"], {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(restrictions),
+    });
+  }
+
   /**
    * List all commands from a channel.
    */
@@ -231,6 +247,39 @@ export class Api {
     });
   }
 
+  /**
+   * Edit the template and the named arguments of a command.
+   *
+   * @param {object} key the key of the command to edit.
+   * @param {string} template the template of the command.
+   * @param {string} args the named arguments of the command, like `{user} {message?}`, or empty to remove them.
+   */
+  commandsEdit(key, template, args) {
+    return this.fetch(["commands", key.channel, key.name], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({template, args}),
+    });
+  }
+
+  /**
+   * Edit the restrictions of a command.
+   *
+   * @param {object} key key of the command to edit
+   * @param {object} restrictions the new cooldown, user_cooldown, and scope of the command
+   */
+  commandsEditRestrictions(key, restrictions) {
+    return this.fetch(["commands", key.channel, key.name, "restrictions"], {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(restrictions),
+    });
+  }
+
   promotions(channel) {
     return this.fetch(["promotions", channel]);
   }
@@ -314,6 +363,83 @@ export class Api {
     });
   }
 
+  /**
+   * Get a list of all temporary grants.
+   */
+  authTemporaryGrants() {
+    return this.fetch(["auth", "temporary-grants"]);
+  }
+
+  /**
+   * Revoke a temporary grant.
+   *
+   * @param {number} id the id of the temporary grant to revoke.
+   */
+  authDeleteTemporaryGrant(id) {
+    return this.fetch(`auth/temporary-grants/${id}`, {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * List moderation actions taken in a channel.
+   *
+   * @param {string} channel the channel to list moderation actions for.
+   * @param {object} filter filter to apply, like `{user: "setbac"}`.
+   */
+  moderationLog(channel, filter = {}) {
+    let query = new URLSearchParams();
+
+    for (let key in filter) {
+      if (!!filter[key]) {
+        query.set(key, filter[key]);
+      }
+    }
+
+    return this.fetch(`${encodePath(["moderation", channel])}?${query}`);
+  }
+
+  /**
+   * Undo the timeout or ban of a moderation action.
+   *
+   * @param {string} channel the channel the action was taken in.
+   * @param {number} id the id of the moderation action to undo.
+   */
+  moderationUndo(channel, id) {
+    return this.fetch(["moderation", channel, id, "undo"], {
+      method: "POST",
+    });
+  }
+
+  /**
+   * List redemptions from the shop.
+   *
+   * @param {string} channel the channel to list redemptions for.
+   * @param {string | null} state only list redemptions in the given state, like `"pending"`.
+   */
+  shopRedemptions(channel, state = null) {
+    let query = new URLSearchParams();
+
+    if (!!state) {
+      query.set("state", state);
+    }
+
+    return this.fetch(`${encodePath(["shop", channel, "redemptions"])}?${query}`);
+  }
+
+  /**
+   * Fulfil or reject a pending shop redemption.
+   *
+   * @param {string} channel the channel the redemption belongs to.
+   * @param {number} id the id of the redemption.
+   * @param {string} action either `"fulfil"` or `"reject"`.
+   */
+  shopResolve(channel, id, action) {
+    return this.fetch(["shop", channel, "redemptions", id, action], {
+      method: "POST",
+    });
+  }
+
   /**
    * Get all existing chat messages.
    */
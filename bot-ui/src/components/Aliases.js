@@ -62,6 +62,37 @@ export default class Aliases extends React.Component {
     return this.list();
   }
 
+  /**
+   * Prompt for a new value of one of the restrictions and save it.
+   */
+  async editRestriction(c, field, label) {
+    let value = window.prompt(`${label} for ${c.key.name} (leave empty for none)`, c.restrictions[field] || "");
+
+    if (value === null) {
+      return;
+    }
+
+    let restrictions = Object.assign({}, c.restrictions, {[field]: value.trim() || null});
+
+    this.setState({
+      loading: true,
+      error: null,
+    });
+
+    try {
+      await this.api.aliasesEditRestrictions(c.key, restrictions);
+    } catch (e) {
+      this.setState({
+        loading: false,
+        error: `Failed to edit restrictions: ${e}`,
+      });
+
+      return;
+    }
+
+    await this.list();
+  }
+
   render() {
     let error = null;
 
@@ -86,6 +117,9 @@ export default class Aliases extends React.Component {
                 <th>Name</th>
                 <th>Group</th>
                 <th className="table-fill">Text</th>
+                <th>Cooldown</th>
+                <th>User Cooldown</th>
+                <th>Scope</th>
                 <th></th>
               </tr>
             </thead>
@@ -110,6 +144,15 @@ export default class Aliases extends React.Component {
                     <td className="alias-name">{c.key.name}</td>
                     <td className="alias-group"><b>{c.group}</b></td>
                     <td className="alias-template">{c.template}</td>
+                    <td className="alias-cooldown">
+                      <Button size="sm" variant="light" onClick={_ => this.editRestriction(c, "cooldown", "Cooldown")}>{c.restrictions.cooldown || "none"}</Button>
+                    </td>
+                    <td className="alias-user-cooldown">
+                      <Button size="sm" variant="light" onClick={_ => this.editRestriction(c, "user_cooldown", "User cooldown")}>{c.restrictions.user_cooldown || "none"}</Button>
+                    </td>
+                    <td className="alias-scope">
+                      <Button size="sm" variant="light" onClick={_ => this.editRestriction(c, "scope", "Scope")}>{c.restrictions.scope || "none"}</Button>
+                    </td>
                     <td>{disabled}</td>
                   </tr>
                 );
@@ -81,10 +81,11 @@ export default class Authorization extends React.Component {
       this.api.authRoles(this.props.current.channel),
       this.api.authScopes(this.props.current.channel),
       this.api.authGrants(this.props.current.channel),
+      this.api.authTemporaryGrants(),
     ];
 
     try {
-      let [roles, scopes, grants] = await Promise.all(requests);
+      let [roles, scopes, grants, temporary] = await Promise.all(requests);
       let allowsObject = {};
 
       for (let [scope, role] of grants) {
@@ -94,7 +95,7 @@ export default class Authorization extends React.Component {
       this.setState({
         loading: false,
         error: null,
-        data: {roles, scopes, grants: allowsObject},
+        data: {roles, scopes, grants: allowsObject, temporary},
       });
     } catch (e) {
       this.setState({
@@ -133,6 +134,66 @@ export default class Authorization extends React.Component {
     }
   }
 
+  async revoke(id) {
+    this.setState({loading: true});
+
+    try {
+      await this.api.authDeleteTemporaryGrant(id);
+      await this.list();
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to revoke temporary grant: ${e}`,
+      });
+    }
+  }
+
+  /**
+   * Render the list of temporary grants.
+   */
+  renderTemporary(temporary) {
+    if (temporary.length === 0) {
+      return null;
+    }
+
+    return (
+      <div className="mt-4">
+        <h4>Temporary Grants</h4>
+
+        <Table className="mb-0">
+          <thead>
+            <tr>
+              <th>Scope</th>
+              <th>Principal</th>
+              <th>Expires At</th>
+              <th>Uses Left</th>
+              <th></th>
+            </tr>
+          </thead>
+          <tbody>
+            {temporary.map(grant => {
+              let revoke = () => this.revoke(grant.id);
+
+              return (
+                <tr key={grant.id}>
+                  <td>{grant.scope}</td>
+                  <td>{grant.principal}</td>
+                  <td>{grant.expires_at}</td>
+                  <td>{grant.uses === null ? <em>unlimited</em> : grant.uses}</td>
+                  <td align="right">
+                    <Button size="sm" variant="danger" title="Revoke" onClick={revoke}>
+                      <FontAwesomeIcon icon="trash" />
+                    </Button>
+                  </td>
+                </tr>
+              );
+            })}
+          </tbody>
+        </Table>
+      </div>
+    );
+  }
+
   filtered(data) {
     if (!this.state.filter) {
       return data;
@@ -395,6 +456,7 @@ export default class Authorization extends React.Component {
 
         {filter}
         {content}
+        {this.state.data ? this.renderTemporary(this.state.data.temporary) : null}
         {modal}
       </div>
     );
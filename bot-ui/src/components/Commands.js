@@ -69,6 +69,66 @@ export default class Commands extends React.Component {
     }
   }
 
+  /**
+   * Prompt for a new value of one of the restrictions and save it.
+   */
+  async editRestriction(c, field, label) {
+    let value = window.prompt(`${label} for ${c.key.name} (leave empty for none)`, c.restrictions[field] || "");
+
+    if (value === null) {
+      return;
+    }
+
+    let restrictions = Object.assign({}, c.restrictions, {[field]: value.trim() || null});
+
+    this.setState({
+      loading: true,
+      error: null,
+    });
+
+    try {
+      await this.api.commandsEditRestrictions(c.key, restrictions);
+    } catch (e) {
+      this.setState({
+        loading: false,
+        error: `Failed to edit restrictions: ${e}`,
+      });
+
+      return;
+    }
+
+    await this.list();
+  }
+
+  /**
+   * Prompt for new named arguments of a command and save them.
+   */
+  async editArgs(c) {
+    let value = window.prompt(`Arguments for ${c.key.name}, like {user} {message?} (leave empty for none)`, c.args || "");
+
+    if (value === null) {
+      return;
+    }
+
+    this.setState({
+      loading: true,
+      error: null,
+    });
+
+    try {
+      await this.api.commandsEdit(c.key, c.template, value.trim());
+    } catch (e) {
+      this.setState({
+        loading: false,
+        error: `Failed to edit arguments: ${e}`,
+      });
+
+      return;
+    }
+
+    await this.list();
+  }
+
   render() {
     let content = null;
 
@@ -87,6 +147,10 @@ export default class Commands extends React.Component {
                 <th>Name</th>
                 <th>Group</th>
                 <th className="table-fill">Text</th>
+                <th>Arguments</th>
+                <th>Cooldown</th>
+                <th>User Cooldown</th>
+                <th>Scope</th>
                 <th></th>
               </tr>
             </thead>
@@ -111,6 +175,18 @@ export default class Commands extends React.Component {
                     <td className="command-name">{c.key.name}</td>
                     <td className="command-group"><b>{c.group}</b></td>
                     <td className="command-template">{c.template}</td>
+                    <td className="command-args">
+                      <Button size="sm" variant="light" onClick={_ => this.editArgs(c)}>{c.args || "none"}</Button>
+                    </td>
+                    <td className="command-cooldown">
+                      <Button size="sm" variant="light" onClick={_ => this.editRestriction(c, "cooldown", "Cooldown")}>{c.restrictions.cooldown || "none"}</Button>
+                    </td>
+                    <td className="command-user-cooldown">
+                      <Button size="sm" variant="light" onClick={_ => this.editRestriction(c, "user_cooldown", "User cooldown")}>{c.restrictions.user_cooldown || "none"}</Button>
+                    </td>
+                    <td className="command-scope">
+                      <Button size="sm" variant="light" onClick={_ => this.editRestriction(c, "scope", "Scope")}>{c.restrictions.scope || "none"}</Button>
+                    </td>
                     <td>{disabled}</td>
                   </tr>
                 );
@@ -0,0 +1,171 @@
+import React from "react";
+import {Button, Alert, Table, Form} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Test if the given action can be undone.
+ */
+function canUndo(action) {
+  return action.startsWith("timeout") || action === "ban";
+}
+
+export default class Moderation extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      data: null,
+      filter: {
+        user: "",
+        action: "",
+        rule: "",
+        actor: "",
+      },
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the moderation log.
+   */
+  async list() {
+    this.setState({
+      loading: true,
+    });
+
+    try {
+      let data = await this.api.moderationLog(this.props.current.channel, this.state.filter);
+
+      this.setState({
+        loading: false,
+        error: null,
+        data,
+      });
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to request moderation log: ${e}`,
+        data: null,
+      });
+    }
+  }
+
+  /**
+   * Undo the given moderation action.
+   */
+  async undo(id) {
+    this.setState({
+      loading: true,
+      error: null,
+    });
+
+    try {
+      await this.api.moderationUndo(this.props.current.channel, id);
+      await this.list();
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `Failed to undo moderation action: ${e}`,
+      });
+    }
+  }
+
+  /**
+   * Update a single filter field.
+   */
+  setFilter(key, value) {
+    this.setState({
+      filter: Object.assign({}, this.state.filter, {[key]: value}),
+    });
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.data) {
+      if (this.state.data.length === 0) {
+        content = (
+          <Alert variant="info">
+            No moderation actions!
+          </Alert>
+        );
+      } else {
+        content = (
+          <Table responsive="sm">
+            <thead>
+              <tr>
+                <th>When</th>
+                <th>User</th>
+                <th>Action</th>
+                <th>Rule</th>
+                <th className="table-fill">Message</th>
+                <th>By</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.data.map(e => {
+                let undo = null;
+
+                if (canUndo(e.action)) {
+                  let onClick = _ => {
+                    this.undo(e.id);
+                  };
+
+                  undo = <Button size="sm" variant="danger" onClick={onClick}>Undo</Button>;
+                }
+
+                return (
+                  <tr key={e.id}>
+                    <td className="moderation-created-at">{e.created_at}</td>
+                    <td className="moderation-user"><b>{e.user}</b></td>
+                    <td className="moderation-action">{e.action}</td>
+                    <td className="moderation-rule">{e.rule}</td>
+                    <td className="moderation-message">{e.message}</td>
+                    <td className="moderation-actor">{e.actor || <em>automated</em>}</td>
+                    <td>{undo}</td>
+                  </tr>
+                );
+              })}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    let onSubmit = e => {
+      e.preventDefault();
+      this.list();
+    };
+
+    let filter = ["user", "action", "rule", "actor"].map(key => (
+      <Form.Control
+        key={key}
+        size="sm"
+        className="mr-2"
+        placeholder={key}
+        value={this.state.filter[key]}
+        onChange={e => this.setFilter(key, e.target.value)} />
+    ));
+
+    return (
+      <div>
+        <h1 className="oxi-page-title">Moderation</h1>
+        <Form inline className="mb-3" onSubmit={onSubmit}>
+          {filter}
+          <Button size="sm" type="submit">Filter</Button>
+        </Form>
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </div>
+    );
+  }
+}
@@ -0,0 +1,152 @@
+import React from "react";
+import {Button, ButtonGroup, Alert, Table, Form} from "react-bootstrap";
+import {Loading, Error} from 'shared-ui/components';
+
+export default class Shop extends React.Component {
+  constructor(props) {
+    super(props);
+
+    this.api = this.props.api;
+
+    this.state = {
+      loading: true,
+      error: null,
+      data: null,
+      filter: "pending",
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of redemptions.
+   */
+  async list(filter = this.state.filter) {
+    this.setState({
+      loading: true,
+    });
+
+    try {
+      let data = await this.api.shopRedemptions(this.props.current.channel, filter);
+
+      this.setState({
+        loading: false,
+        error: null,
+        data,
+      });
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to request shop redemptions: ${e}`,
+        data: null,
+      });
+    }
+  }
+
+  /**
+   * Fulfil or reject the given redemption.
+   */
+  async resolve(id, action) {
+    this.setState({
+      loading: true,
+      error: null,
+    });
+
+    try {
+      await this.api.shopResolve(this.props.current.channel, id, action);
+      await this.list();
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `Failed to ${action} redemption: ${e}`,
+      });
+    }
+  }
+
+  /**
+   * Change which redemptions are shown.
+   */
+  setFilter(filter) {
+    this.setState({filter});
+    this.list(filter);
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.data) {
+      if (this.state.data.length === 0) {
+        content = (
+          <Alert variant="info">
+            No redemptions!
+          </Alert>
+        );
+      } else {
+        content = (
+          <Table responsive="sm">
+            <thead>
+              <tr>
+                <th>When</th>
+                <th>User</th>
+                <th>Item</th>
+                <th>Cost</th>
+                <th className="table-fill">Message</th>
+                <th>State</th>
+                <th></th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.data.map(r => {
+                let actions = null;
+
+                if (r.state === "pending") {
+                  actions = (
+                    <ButtonGroup>
+                      <Button size="sm" variant="primary" onClick={() => this.resolve(r.id, "fulfil")}>Fulfil</Button>
+                      <Button size="sm" variant="danger" onClick={() => this.resolve(r.id, "reject")}>Reject</Button>
+                    </ButtonGroup>
+                  );
+                }
+
+                return (
+                  <tr key={r.id}>
+                    <td className="shop-created-at">{r.created_at}</td>
+                    <td className="shop-user"><b>{r.user}</b></td>
+                    <td className="shop-item">{r.item}</td>
+                    <td className="shop-cost">{r.cost}</td>
+                    <td className="shop-message">{r.message}</td>
+                    <td className="shop-state">{r.state}</td>
+                    <td>{actions}</td>
+                  </tr>
+                );
+              })}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    return (
+      <div>
+        <h1 className="oxi-page-title">Shop</h1>
+        <Form inline className="mb-3">
+          <Form.Control
+            as="select"
+            size="sm"
+            value={this.state.filter}
+            onChange={e => this.setFilter(e.target.value)}>
+            <option value="pending">Pending</option>
+            <option value="fulfilled">Fulfilled</option>
+            <option value="rejected">Rejected</option>
+            <option value="">All</option>
+          </Form.Control>
+        </Form>
+        <Loading isLoading={this.state.loading} />
+        <Error error={this.state.error} />
+        {content}
+      </div>
+    );
+  }
+}
@@ -21,6 +21,8 @@ import Themes from "./components/Themes";
 import YouTube from "./components/YouTube";
 import Chat from "./components/Chat";
 import Authorization from "./components/Authorization";
+import Moderation from "./components/Moderation";
+import Shop from "./components/Shop";
 import ConfigurationPrompt from "./components/ConfigurationPrompt";
 import * as semver from "semver";
 import logo from "./logo.png";
@@ -319,9 +321,15 @@ class Layout extends React.Component {
                 <NavDropdown.Item as={Link} active={path === "/commands"} to="/commands">
                   Commands
                 </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/moderation"} to="/moderation">
+                  Moderation
+                </NavDropdown.Item>
                 <NavDropdown.Item as={Link} active={path === "/promotions"} to="/promotions">
                   Promotions
                 </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/shop"} to="/shop">
+                  Shop
+                </NavDropdown.Item>
                 <NavDropdown.Item as={Link} active={path === "/themes"} to="/themes">
                   Themes
                 </NavDropdown.Item>
@@ -393,9 +401,15 @@ function AppRouter() {
       <Route path="/commands" exact render={props => (
         <AuthorizedPage><Commands {...props} /></AuthorizedPage>
       )} />
+      <Route path="/moderation" exact render={props => (
+        <AuthorizedPage><Moderation {...props} /></AuthorizedPage>
+      )} />
       <Route path="/promotions" exact render={props => (
         <AuthorizedPage><Promotions {...props} /></AuthorizedPage>
       )} />
+      <Route path="/shop" exact render={props => (
+        <AuthorizedPage><Shop {...props} /></AuthorizedPage>
+      )} />
       <Route path="/themes" exact render={props => (
         <AuthorizedPage><Themes {...props} /></AuthorizedPage>
       )} />
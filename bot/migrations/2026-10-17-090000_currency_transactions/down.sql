@@ -0,0 +1 @@
+DROP TABLE currency_transactions;
\ No newline at end of file
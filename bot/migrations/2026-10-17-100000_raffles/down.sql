@@ -0,0 +1,2 @@
+DROP TABLE raffle_entries;
+DROP TABLE raffles;
@@ -0,0 +1,19 @@
+CREATE TABLE raffles (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    keyword VARCHAR NOT NULL,
+    ticket_cost BIGINT NOT NULL DEFAULT 0,
+    max_tickets INTEGER NOT NULL DEFAULT 1,
+    open BOOLEAN NOT NULL DEFAULT TRUE,
+    ended BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE raffle_entries (
+    raffle_id INTEGER NOT NULL,
+    user VARCHAR NOT NULL,
+    tickets INTEGER NOT NULL,
+    subscriber BOOLEAN NOT NULL DEFAULT FALSE,
+    won BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (raffle_id, user)
+);
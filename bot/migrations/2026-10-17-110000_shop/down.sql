@@ -0,0 +1,2 @@
+DROP TABLE shop_redemptions;
+DROP TABLE shop_items;
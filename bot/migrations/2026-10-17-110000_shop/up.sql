@@ -0,0 +1,26 @@
+CREATE TABLE shop_items (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    cost BIGINT NOT NULL,
+    cooldown INTEGER,
+    stock INTEGER,
+    scope VARCHAR,
+    response VARCHAR,
+    manual BOOLEAN NOT NULL DEFAULT FALSE,
+    disabled BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE TABLE shop_redemptions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    item VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    cost BIGINT NOT NULL,
+    message VARCHAR,
+    state VARCHAR NOT NULL DEFAULT 'pending',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP
+);
+
+CREATE INDEX shop_redemptions_state ON shop_redemptions (channel, state);
@@ -0,0 +1,18 @@
+CREATE TABLE polls (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    question VARCHAR NOT NULL,
+    options VARCHAR NOT NULL,
+    weighted BOOLEAN NOT NULL DEFAULT FALSE,
+    closes_at TIMESTAMP,
+    closed BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE poll_votes (
+    poll_id INTEGER NOT NULL,
+    user VARCHAR NOT NULL,
+    option VARCHAR NOT NULL,
+    weight BIGINT NOT NULL,
+    PRIMARY KEY (poll_id, user)
+);
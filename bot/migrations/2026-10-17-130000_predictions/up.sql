@@ -0,0 +1,18 @@
+CREATE TABLE predictions (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    question VARCHAR NOT NULL,
+    outcomes VARCHAR NOT NULL,
+    locks_at TIMESTAMP NOT NULL,
+    state VARCHAR NOT NULL DEFAULT 'open',
+    winner VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE prediction_stakes (
+    prediction_id INTEGER NOT NULL,
+    user VARCHAR NOT NULL,
+    outcome VARCHAR NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (prediction_id, user)
+);
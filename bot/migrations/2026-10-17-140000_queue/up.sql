@@ -0,0 +1,9 @@
+CREATE TABLE queue_entries (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    priority BOOLEAN NOT NULL DEFAULT FALSE,
+    skipped_at TIMESTAMP,
+    joined_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (channel, user)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE quotes (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    quote VARCHAR NOT NULL,
+    added_by VARCHAR NOT NULL,
+    game VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX quotes_channel ON quotes (channel);
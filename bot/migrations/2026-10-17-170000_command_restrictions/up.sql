@@ -0,0 +1,7 @@
+ALTER TABLE commands ADD COLUMN cooldown INTEGER;
+ALTER TABLE commands ADD COLUMN user_cooldown INTEGER;
+ALTER TABLE commands ADD COLUMN scope VARCHAR;
+
+ALTER TABLE aliases ADD COLUMN cooldown INTEGER;
+ALTER TABLE aliases ADD COLUMN user_cooldown INTEGER;
+ALTER TABLE aliases ADD COLUMN scope VARCHAR;
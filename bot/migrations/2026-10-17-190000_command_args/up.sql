@@ -0,0 +1 @@
+ALTER TABLE commands ADD COLUMN args VARCHAR;
@@ -0,0 +1,8 @@
+CREATE TABLE offences (
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    filter VARCHAR NOT NULL,
+    count INTEGER NOT NULL DEFAULT 0,
+    last_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, user, filter)
+);
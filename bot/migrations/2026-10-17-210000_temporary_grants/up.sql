@@ -0,0 +1,8 @@
+-- Grants which have been temporarily given to a user or a role.
+CREATE TABLE temporary_grants (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    scope VARCHAR NOT NULL,
+    principal VARCHAR NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    uses INTEGER
+);
@@ -0,0 +1 @@
+ALTER TABLE bad_words ADD COLUMN kind VARCHAR;
@@ -0,0 +1,12 @@
+CREATE TABLE moderation_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    action VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    message VARCHAR,
+    rule VARCHAR,
+    actor VARCHAR,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX moderation_log_channel ON moderation_log (channel);
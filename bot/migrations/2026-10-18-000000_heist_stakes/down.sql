@@ -0,0 +1 @@
+DROP TABLE heist_stakes;
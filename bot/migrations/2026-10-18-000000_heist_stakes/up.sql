@@ -0,0 +1,6 @@
+CREATE TABLE heist_stakes (
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (channel, user)
+);
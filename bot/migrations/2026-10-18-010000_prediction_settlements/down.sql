@@ -0,0 +1,21 @@
+CREATE TEMPORARY TABLE tmp_prediction_stakes (
+    prediction_id INTEGER NOT NULL,
+    user VARCHAR NOT NULL,
+    outcome VARCHAR NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (prediction_id, user)
+);
+
+INSERT INTO tmp_prediction_stakes SELECT prediction_id, user, outcome, amount FROM prediction_stakes;
+DROP TABLE prediction_stakes;
+
+CREATE TABLE prediction_stakes (
+    prediction_id INTEGER NOT NULL,
+    user VARCHAR NOT NULL,
+    outcome VARCHAR NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (prediction_id, user)
+);
+
+INSERT INTO prediction_stakes SELECT prediction_id, user, outcome, amount FROM tmp_prediction_stakes;
+DROP TABLE tmp_prediction_stakes;
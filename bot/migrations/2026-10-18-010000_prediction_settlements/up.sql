@@ -0,0 +1 @@
+ALTER TABLE prediction_stakes ADD COLUMN settled BOOLEAN NOT NULL DEFAULT FALSE;
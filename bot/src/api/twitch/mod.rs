@@ -397,6 +397,33 @@ pub struct User {
     pub ty: String,
 }
 
+impl User {
+    /// Construct the public parts of a user from their channel.
+    ///
+    /// This is used for channels we don't have the credentials of, so private
+    /// information like the email is left empty.
+    pub fn from_channel(channel: &Channel) -> Self {
+        Self {
+            id: channel.id.clone(),
+            name: channel.name.clone(),
+            display_name: channel
+                .display_name
+                .clone()
+                .unwrap_or_else(|| channel.name.clone()),
+            created_at: channel.created_at,
+            updated_at: channel.updated_at,
+            bio: None,
+            email: String::new(),
+            email_verified: false,
+            logo: channel.logo.clone(),
+            notifications: HashMap::new(),
+            partnered: channel.partner,
+            twitter_connected: false,
+            ty: String::from("user"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct Subscription {
     pub broadcaster_id: String,
@@ -1,7 +1,7 @@
 use crate::db;
 use crate::utils::{Cooldown, Duration};
 use anyhow::{Context as _, Error};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::prelude::*;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
@@ -11,6 +11,12 @@ use tokio::sync::RwLock;
 
 const SCHEMA: &[u8] = include_bytes!("auth.yaml");
 
+no_arg_sql_function!(
+    last_insert_rowid,
+    diesel::sql_types::Integer,
+    "The id of the last row inserted on the connection."
+);
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Schema {
     roles: HashMap<Role, RoleData>,
@@ -45,7 +51,7 @@ impl std::str::FromStr for RoleOrUser {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         if s.starts_with('@') {
-            let role = Role::from_str(&s[1..])?;
+            let role = Role::from_str(s)?;
             return Ok(RoleOrUser::Role(role));
         }
 
@@ -53,14 +59,40 @@ impl std::str::FromStr for RoleOrUser {
     }
 }
 
+impl serde::Serialize for RoleOrUser {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
 /// A grant that has been temporarily given.
-struct TemporaryGrant {
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct TemporaryGrant {
+    pub id: i32,
     pub scope: Scope,
     pub principal: RoleOrUser,
     pub expires_at: DateTime<Utc>,
+    /// The number of times the grant can be used, if limited.
+    pub uses: Option<u32>,
 }
 
 impl TemporaryGrant {
+    /// Convert database columns into a temporary grant.
+    fn from_db(
+        (id, scope, principal, expires_at, uses): (i32, Scope, String, NaiveDateTime, Option<i32>),
+    ) -> Result<Self, Error> {
+        Ok(Self {
+            id,
+            scope,
+            principal: str::parse(&principal)?,
+            expires_at: DateTime::from_utc(expires_at, Utc),
+            uses: uses.map(|uses| uses as u32),
+        })
+    }
+
     /// Test if the grant is expired.
     pub fn is_expired(&self, now: &DateTime<Utc>) -> bool {
         *now >= self.expires_at
@@ -82,23 +114,38 @@ pub struct Auth {
 impl Auth {
     pub async fn new(db: db::Database, schema: Schema) -> Result<Self, Error> {
         use db::schema::grants::dsl;
+        use db::schema::temporary_grants::dsl as t;
 
-        let grants = db
+        let now = Utc::now().naive_utc();
+
+        let (grants, temporary_grants) = db
             .asyncify(move |c| {
                 let grants = dsl::grants
                     .select((dsl::scope, dsl::role))
                     .load::<(Scope, Role)>(c)?
                     .into_iter()
                     .collect::<HashSet<_>>();
-                Ok::<_, Error>(grants)
+
+                diesel::delete(t::temporary_grants.filter(t::expires_at.le(now))).execute(c)?;
+
+                let temporary_grants = t::temporary_grants
+                    .select((t::id, t::scope, t::principal, t::expires_at, t::uses))
+                    .load::<(i32, Scope, String, NaiveDateTime, Option<i32>)>(c)?;
+
+                Ok::<_, Error>((grants, temporary_grants))
             })
             .await?;
 
+        let temporary_grants = temporary_grants
+            .into_iter()
+            .map(TemporaryGrant::from_db)
+            .collect::<Result<Vec<_>, Error>>()?;
+
         let auth = Self {
             db,
             schema: Arc::new(schema),
             grants: Arc::new(RwLock::new(grants)),
-            temporary_grants: Default::default(),
+            temporary_grants: Arc::new(RwLock::new(temporary_grants)),
         };
 
         // perform default initialization based on auth.yaml
@@ -209,18 +256,85 @@ impl Auth {
         Ok(())
     }
 
-    /// Insert a temporary grant.
+    /// Insert a temporary grant, optionally limited to the given number of
+    /// uses.
     pub async fn insert_temporary(
         &self,
         scope: Scope,
         principal: RoleOrUser,
         expires_at: DateTime<Utc>,
-    ) {
-        self.temporary_grants.write().await.push(TemporaryGrant {
+        uses: Option<u32>,
+    ) -> Result<TemporaryGrant, Error> {
+        use db::schema::temporary_grants::dsl;
+
+        let principal_string = principal.to_string();
+        let naive_expires_at = expires_at.naive_utc();
+
+        let id = self
+            .db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    diesel::insert_into(dsl::temporary_grants)
+                        .values((
+                            dsl::scope.eq(scope),
+                            dsl::principal.eq(principal_string),
+                            dsl::expires_at.eq(naive_expires_at),
+                            dsl::uses.eq(uses.map(|uses| uses as i32)),
+                        ))
+                        .execute(c)?;
+
+                    let id = diesel::select(last_insert_rowid).get_result::<i32>(c)?;
+                    Ok::<_, Error>(id)
+                })
+            })
+            .await?;
+
+        let grant = TemporaryGrant {
+            id,
             scope,
             principal,
             expires_at,
-        })
+            uses,
+        };
+
+        self.temporary_grants.write().await.push(grant.clone());
+        Ok(grant)
+    }
+
+    /// List all temporary grants which haven't expired.
+    pub async fn list_temporary(&self) -> Vec<TemporaryGrant> {
+        let now = Utc::now();
+
+        self.temporary_grants
+            .read()
+            .await
+            .iter()
+            .filter(|g| !g.is_expired(&now))
+            .cloned()
+            .collect()
+    }
+
+    /// Revoke the temporary grant with the given id.
+    pub async fn delete_temporary(&self, id: i32) -> Result<bool, Error> {
+        use db::schema::temporary_grants::dsl;
+
+        let mut temporary = self.temporary_grants.write().await;
+
+        let index = match temporary.iter().position(|g| g.id == id) {
+            Some(index) => index,
+            None => return Ok(false),
+        };
+
+        temporary.remove(index);
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(dsl::temporary_grants.filter(dsl::id.eq(id))).execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await?;
+
+        Ok(true)
     }
 
     /// Insert an assignment.
@@ -259,24 +373,20 @@ impl Auth {
         Ok(())
     }
 
-    /// Test if there are any temporary grants matching the given user or role.
-    async fn test_temporary(
-        &self,
-        now: &DateTime<Utc>,
+    /// Find the first temporary grant of the given scope which matches the
+    /// given user or role.
+    ///
+    /// Also returns if any matching grant has expired.
+    fn find_temporary(
+        temporary: &[TemporaryGrant],
         scope: Scope,
         against: impl IntoIterator<Item = RoleOrUser>,
-    ) -> (bool, bool) {
-        let temporary = self.temporary_grants.read().await;
-
-        if temporary.is_empty() {
-            return (false, false);
-        }
-
-        let mut granted = false;
+        now: &DateTime<Utc>,
+    ) -> (Option<usize>, bool) {
         let mut expired = false;
 
-        'outer: for against in against.into_iter() {
-            for t in temporary.iter() {
+        for against in against.into_iter() {
+            for (index, t) in temporary.iter().enumerate() {
                 if t.principal != against || t.scope != scope {
                     continue;
                 }
@@ -286,26 +396,27 @@ impl Auth {
                     continue;
                 }
 
-                granted = true;
-                break 'outer;
+                return (Some(index), expired);
             }
         }
 
-        (granted, expired)
+        (None, expired)
     }
 
-    /// Test if the given assignment exists.
-    pub async fn test(&self, scope: Scope, user: &str, role: Role) -> bool {
-        if self.grants.read().await.contains(&(scope, role)) {
-            return true;
-        }
-
+    /// Test if there are any temporary grants matching the given user or role.
+    ///
+    /// This doesn't consume any uses, see [Auth::consume_any].
+    async fn test_temporary(
+        &self,
+        scope: Scope,
+        against: impl IntoIterator<Item = RoleOrUser>,
+    ) -> bool {
         let now = Utc::now();
 
-        let against = iter::once(RoleOrUser::User(user.to_string()))
-            .chain(iter::once(RoleOrUser::Role(role)));
-
-        let (granted, expired) = self.test_temporary(&now, scope, against).await;
+        let (found, expired) = {
+            let temporary = self.temporary_grants.read().await;
+            Self::find_temporary(&temporary, scope, against, &now)
+        };
 
         // Delete temporary grants that has expired.
         if expired {
@@ -313,12 +424,89 @@ impl Auth {
                 .write()
                 .await
                 .retain(|g| !g.is_expired(&now));
+
+            if let Err(e) = self.delete_expired_temporary(&now).await {
+                log_error!(e, "failed to delete expired temporary grants");
+            }
+        }
+
+        found.is_some()
+    }
+
+    /// Consume one use of the temporary grant at the given index, removing it
+    /// once it has been used up.
+    ///
+    /// Returns the id of the grant and its remaining uses if it is limited in
+    /// its number of uses.
+    fn consume_temporary(temporary: &mut Vec<TemporaryGrant>, index: usize) -> Option<(i32, u32)> {
+        let grant = &mut temporary[index];
+
+        let uses = grant.uses.as_mut()?;
+        *uses = uses.saturating_sub(1);
+        let uses = *uses;
+        let id = grant.id;
+
+        if uses == 0 {
+            temporary.remove(index);
         }
 
-        granted
+        Some((id, uses))
+    }
+
+    /// Store the remaining uses of a temporary grant, deleting it once it has
+    /// been used up.
+    async fn store_temporary_uses(&self, id: i32, uses: u32) -> Result<(), Error> {
+        use db::schema::temporary_grants::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let grant = dsl::temporary_grants.filter(dsl::id.eq(id));
+
+                if uses == 0 {
+                    diesel::delete(grant).execute(c)?;
+                } else {
+                    diesel::update(grant)
+                        .set(dsl::uses.eq(uses as i32))
+                        .execute(c)?;
+                }
+
+                Ok::<_, Error>(())
+            })
+            .await
+    }
+
+    /// Delete temporary grants which have expired from the database.
+    async fn delete_expired_temporary(&self, now: &DateTime<Utc>) -> Result<(), Error> {
+        use db::schema::temporary_grants::dsl;
+
+        let now = now.naive_utc();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(dsl::temporary_grants.filter(dsl::expires_at.le(now))).execute(c)?;
+                Ok::<_, Error>(())
+            })
+            .await
     }
 
     /// Test if the given assignment exists.
+    pub async fn test(&self, scope: Scope, user: &str, role: Role) -> bool {
+        if self.grants.read().await.contains(&(scope, role)) {
+            return true;
+        }
+
+        let against = iter::once(RoleOrUser::User(user.to_string()))
+            .chain(iter::once(RoleOrUser::Role(role)));
+
+        self.test_temporary(scope, against).await
+    }
+
+    /// Test if the given assignment exists for the given user or any of the
+    /// given roles.
+    ///
+    /// Testing never consumes uses of temporary grants, that only happens
+    /// once the action gated by the scope has been taken through
+    /// [Auth::consume_any].
     pub async fn test_any(
         &self,
         scope: Scope,
@@ -335,22 +523,52 @@ impl Auth {
             }
         }
 
-        let now = Utc::now();
-
         let against = iter::once(RoleOrUser::User(user.to_string()))
             .chain(roles.into_iter().map(RoleOrUser::Role));
 
-        let (granted, expired) = self.test_temporary(&now, scope, against).await;
+        self.test_temporary(scope, against).await
+    }
 
-        // Delete temporary grants that has expired.
-        if expired {
-            self.temporary_grants
-                .write()
-                .await
-                .retain(|g| !g.is_expired(&now));
+    /// Consume one use of the temporary grant which gives the user the given
+    /// scope.
+    ///
+    /// Nothing is consumed if one of the roles has the scope permanently, or
+    /// if the matching grant isn't limited in its number of uses.
+    pub async fn consume_any(
+        &self,
+        scope: Scope,
+        user: &str,
+        roles: impl IntoIterator<Item = Role>,
+    ) {
+        let roles = roles.into_iter().collect::<HashSet<_>>();
+
+        {
+            let grants = self.grants.read().await;
+
+            if roles.iter().any(|r| grants.contains(&(scope, *r))) {
+                return;
+            }
         }
 
-        granted
+        let against = iter::once(RoleOrUser::User(user.to_string()))
+            .chain(roles.into_iter().map(RoleOrUser::Role));
+
+        let now = Utc::now();
+        let mut temporary = self.temporary_grants.write().await;
+
+        let consumed = match Self::find_temporary(&temporary, scope, against, &now) {
+            (Some(index), _) => Self::consume_temporary(&mut temporary, index),
+            (None, _) => None,
+        };
+
+        // NB: don't hold on to the lock while the database is being updated.
+        drop(temporary);
+
+        if let Some((id, uses)) = consumed {
+            if let Err(e) = self.store_temporary_uses(id, uses).await {
+                log_error!(e, "failed to consume temporary grant");
+            }
+        }
     }
 
     /// Get a list of scopes and extra information associated with them.
@@ -603,9 +821,32 @@ scopes! {
     (WaterUndo, "water/undo"),
     (AuthPermit, "auth/permit"),
     (ChatBypassUrlWhitelist, "chat/bypass-url-whitelist"),
+    (ChatBypassCaps, "chat/bypass-caps"),
+    (ChatBypassSymbols, "chat/bypass-symbols"),
+    (ChatBypassRepetition, "chat/bypass-repetition"),
+    (ChatBypassDuplicates, "chat/bypass-duplicates"),
+    (ChatBypassLength, "chat/bypass-length"),
+    (ChatBypassEmotes, "chat/bypass-emotes"),
     (Time, "time"),
     (Poll, "poll"),
     (Weather, "weather"),
+    (GambleRoulette, "gamble/roulette"),
+    (GambleSlots, "gamble/slots"),
+    (GambleDuel, "gamble/duel"),
+    (Heist, "heist"),
+    (Raffle, "raffle"),
+    (ShopRedeem, "shop/redeem"),
+    (ShopEdit, "shop/edit"),
+    (ShopFulfil, "shop/fulfil"),
+    (Bet, "bet"),
+    (BetAdmin, "bet/admin"),
+    (Queue, "queue"),
+    (QueueAdmin, "queue/admin"),
+    (Quote, "quote"),
+    (QuoteEdit, "quote/edit"),
+    (Counter, "counter"),
+    (CounterEdit, "counter/edit"),
+    (BadWordEdit, "bad-word/edit"),
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -652,3 +893,15 @@ pub struct RoleData {
     /// Documentation for this role.
     pub doc: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Schema, Scope};
+
+    #[test]
+    fn test_load_static() {
+        let schema = Schema::load_static().expect("auth.yaml to load");
+        assert!(schema.scopes.contains_key(&Scope::Weather));
+        assert!(schema.scopes.contains_key(&Scope::GambleRoulette));
+    }
+}
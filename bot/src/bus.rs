@@ -1,5 +1,8 @@
+use crate::db;
+use crate::irc;
 use crate::player;
 use crate::track_id::TrackId;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::broadcast;
@@ -10,14 +13,19 @@ pub trait Message: 'static + Clone + Send + Sync + serde::Serialize {
     fn id(&self) -> Option<&'static str> {
         None
     }
+
+    /// The chat channel a bussed message belongs to, like `#setbac`.
+    fn channel(&self) -> Option<&str> {
+        None
+    }
 }
 
 pub type Reader<T> = broadcast::Receiver<T>;
 
 struct Inner<T> {
     subs: broadcast::Sender<T>,
-    /// Latest instances of all messages.
-    latest: RwLock<HashMap<&'static str, T>>,
+    /// Latest instances of all messages, by ID and channel.
+    latest: RwLock<HashMap<(&'static str, Option<String>), T>>,
 }
 
 /// Bus system.
@@ -43,6 +51,7 @@ impl<T> Bus<T> {
         T: Message,
     {
         if let Some(key) = m.id() {
+            let key = (key, m.channel().map(String::from));
             let mut latest = self.inner.latest.write().await;
             latest.insert(key, m.clone());
         }
@@ -119,6 +128,30 @@ impl Message for YouTube {
     }
 }
 
+/// Events for overlays showing the viewer queue.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type")]
+pub enum Queue {
+    /// The current state of the queue.
+    #[serde(rename = "queue/update")]
+    QueueUpdate {
+        open: bool,
+        max_size: Option<u32>,
+        entries: Vec<db::QueueEntry>,
+    },
+}
+
+impl Message for Queue {
+    /// Whether a message should be cached or not and under what key.
+    fn id(&self) -> Option<&'static str> {
+        use self::Queue::*;
+
+        match *self {
+            QueueUpdate { .. } => Some("queue/update"),
+        }
+    }
+}
+
 /// Messages that go on the global bus.
 #[derive(Debug, Clone, serde::Serialize)]
 #[serde(tag = "type")]
@@ -145,6 +178,63 @@ pub enum Global {
     },
     #[serde(rename = "song/modified")]
     SongModified,
+    /// A subscription, raid, or other notice from chat.
+    #[serde(rename = "chat/user-notice")]
+    UserNotice {
+        channel: String,
+        notice: irc::UserNotice,
+    },
+    /// A user cheered with bits in chat.
+    #[serde(rename = "chat/cheer")]
+    Cheer {
+        channel: String,
+        user: String,
+        display_name: Option<String>,
+        bits: u32,
+        message: String,
+    },
+    /// An item was redeemed from the shop, or a redemption was resolved.
+    #[serde(rename = "shop/redemption")]
+    ShopRedemption { redemption: db::ShopRedemption },
+    /// The current state of a poll.
+    #[serde(rename = "poll")]
+    Poll {
+        channel: String,
+        id: i32,
+        question: String,
+        options: Vec<PollOption>,
+        weighted: bool,
+        closes_at: Option<DateTime<Utc>>,
+        closed: bool,
+    },
+    /// The current state of a prediction made with `!bet`.
+    #[serde(rename = "prediction")]
+    Prediction {
+        channel: String,
+        id: i32,
+        question: String,
+        outcomes: Vec<PredictionOutcome>,
+        locks_at: DateTime<Utc>,
+        locked: bool,
+        state: db::PredictionState,
+        winner: Option<String>,
+    },
+}
+
+/// A single option in a poll, with the votes it has received so far.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PollOption {
+    pub key: String,
+    pub description: Option<String>,
+    pub votes: i64,
+}
+
+/// A single outcome in a prediction, with the stakes placed on it so far.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PredictionOutcome {
+    pub key: String,
+    pub total: i64,
+    pub stakers: usize,
 }
 
 impl Message for Global {
@@ -155,6 +245,20 @@ impl Message for Global {
         match *self {
             SongProgress { .. } => Some("song/progress"),
             SongCurrent { .. } => Some("song/current"),
+            Poll { .. } => Some("poll"),
+            Prediction { .. } => Some("prediction"),
+            _ => None,
+        }
+    }
+
+    fn channel(&self) -> Option<&str> {
+        use self::Global::*;
+
+        match self {
+            UserNotice { channel, .. }
+            | Cheer { channel, .. }
+            | Poll { channel, .. }
+            | Prediction { channel, .. } => Some(channel),
             _ => None,
         }
     }
@@ -215,6 +319,12 @@ pub enum Command {
     /// Run a raw command.
     #[serde(rename = "raw")]
     Raw { command: String },
+    /// Remove a timeout from a user.
+    #[serde(rename = "untimeout")]
+    Untimeout { channel: String, user: String },
+    /// Unban a user.
+    #[serde(rename = "unban")]
+    Unban { channel: String, user: String },
 }
 
 impl Message for Command {
@@ -19,6 +19,9 @@ use tokio::sync;
 #[error("Command failed with: {0}")]
 pub struct Respond(pub(crate) Cow<'static, str>);
 
+/// Message hooks installed in a channel.
+pub type MessageHooks = slab::Slab<Box<dyn MessageHook>>;
+
 /// An opaque identifier for a hook that has been inserted.
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct HookId(usize);
@@ -65,7 +68,7 @@ pub(crate) struct ContextInner {
     /// Active scope cooldowns.
     pub(crate) scope_cooldowns: sync::Mutex<HashMap<Scope, utils::Cooldown>>,
     /// A hook that can be installed to peek at all incoming messages.
-    pub(crate) message_hooks: sync::RwLock<slab::Slab<Box<dyn MessageHook>>>,
+    pub(crate) message_hooks: sync::RwLock<MessageHooks>,
     /// Shutdown handler.
     pub(crate) restart: utils::Restart,
 }
@@ -77,6 +80,8 @@ pub struct Context {
     pub(crate) user: irc::User,
     pub(crate) it: utils::Words,
     pub(crate) inner: Arc<ContextInner>,
+    /// Scopes which have been checked while handling the command.
+    pub(crate) checked_scopes: Arc<parking_lot::Mutex<Vec<Scope>>>,
 }
 
 impl Context {
@@ -120,6 +125,8 @@ impl Context {
             respond_bail!("Do you think this is a democracy? LUL");
         }
 
+        self.checked_scopes.lock().push(scope);
+
         if self.user.has_scope(Scope::BypassCooldowns).await {
             return Ok(());
         }
@@ -142,6 +149,22 @@ impl Context {
         Ok(())
     }
 
+    /// Consume one use of any temporary grants which gave the user the
+    /// scope required by the command, or the scopes checked while it was
+    /// handled.
+    ///
+    /// Called once the command has been handled successfully.
+    pub(crate) async fn consume_scopes(&self, scope: Option<Scope>) {
+        let mut scopes = std::mem::take(&mut *self.checked_scopes.lock());
+        scopes.extend(scope);
+        scopes.sort();
+        scopes.dedup();
+
+        for scope in scopes {
+            self.user.consume_scope(scope).await;
+        }
+    }
+
     /// Respond to the user with a message.
     pub async fn respond(&self, m: impl fmt::Display) {
         self.user.respond(m).await;
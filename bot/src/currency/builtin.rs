@@ -1,6 +1,6 @@
 //! Module for the built-in currency which uses the regular databse support.
 
-use crate::currency::{BalanceOf, BalanceTransferError};
+use crate::currency::{BalanceOf, BalanceTransferError, Reason, TopOrder};
 use crate::db::{models, schema, user_id, Database};
 
 use anyhow::Result;
@@ -24,6 +24,7 @@ impl Backend {
         taker: &str,
         amount: i64,
         override_balance: bool,
+        reason: Reason,
     ) -> Result<(), BalanceTransferError> {
         use self::schema::balances::dsl;
 
@@ -49,8 +50,8 @@ impl Backend {
                         return Err(BalanceTransferError::NoBalance);
                     }
 
-                    modify_balance(c, &channel, &taker, amount)?;
-                    modify_balance(c, &channel, &giver, -amount)?;
+                    modify_balance(c, &channel, &taker, amount, reason)?;
+                    modify_balance(c, &channel, &giver, -amount, reason)?;
                     Ok(())
                 })
             })
@@ -138,12 +139,20 @@ impl Backend {
     }
 
     /// Add (or subtract) from the balance for a single user.
-    pub async fn balance_add(&self, channel: &str, user: &str, amount: i64) -> Result<()> {
+    pub async fn balance_add(
+        &self,
+        channel: &str,
+        user: &str,
+        amount: i64,
+        reason: Reason,
+    ) -> Result<()> {
         let channel = channel_id(channel);
         let user = user_id(user);
 
         self.db
-            .asyncify(move |c| modify_balance(&*c, &channel, &user, amount))
+            .asyncify(move |c| {
+                c.transaction(|| modify_balance(&*c, &channel, &user, amount, reason))
+            })
             .await
     }
 
@@ -154,6 +163,7 @@ impl Backend {
         users: impl IntoIterator<Item = String> + Send + 'static,
         amount: i64,
         watch_time: i64,
+        reason: Reason,
     ) -> Result<()> {
         use self::schema::balances::dsl;
 
@@ -162,51 +172,130 @@ impl Backend {
 
         self.db
             .asyncify(move |c| {
-                for user in users {
-                    let user = user_id(&user);
-
-                    let filter = dsl::balances
-                        .filter(dsl::channel.eq(channel.as_str()).and(dsl::user.eq(&user)));
+                c.transaction(|| {
+                    for user in users {
+                        let user = user_id(&user);
+
+                        let filter = dsl::balances
+                            .filter(dsl::channel.eq(channel.as_str()).and(dsl::user.eq(&user)));
+
+                        let b = filter.clone().first::<models::Balance>(&*c).optional()?;
+
+                        let balance = match b {
+                            None => {
+                                let balance = models::Balance {
+                                    channel: channel.to_string(),
+                                    user: user.clone(),
+                                    amount,
+                                    watch_time,
+                                };
+
+                                diesel::insert_into(dsl::balances)
+                                    .values(&balance)
+                                    .execute(&*c)?;
+
+                                amount
+                            }
+                            Some(b) => {
+                                let value = b.amount.saturating_add(amount);
+                                let watch_time = b.watch_time.saturating_add(watch_time);
+
+                                diesel::update(filter)
+                                    .set((dsl::amount.eq(value), dsl::watch_time.eq(watch_time)))
+                                    .execute(&*c)?;
+
+                                value
+                            }
+                        };
+
+                        record_transaction(&*c, &channel, &user, amount, reason, balance)?;
+                    }
 
-                    let b = filter.clone().first::<models::Balance>(&*c).optional()?;
+                    Ok(())
+                })
+            })
+            .await
+    }
 
-                    match b {
-                        None => {
-                            let balance = models::Balance {
-                                channel: channel.to_string(),
-                                user: user.clone(),
-                                amount,
-                                watch_time,
-                            };
+    /// Get the top balances in the given order.
+    pub async fn top(
+        &self,
+        channel: &str,
+        order: TopOrder,
+        limit: i64,
+        ignore: Vec<String>,
+    ) -> Result<Vec<models::Balance>> {
+        use self::schema::balances::dsl;
 
-                            diesel::insert_into(dsl::balances)
-                                .values(&balance)
-                                .execute(&*c)?;
-                        }
-                        Some(b) => {
-                            let value = b.amount.saturating_add(amount);
-                            let watch_time = b.watch_time.saturating_add(watch_time);
+        let channel = channel_id(channel);
 
-                            diesel::update(filter)
-                                .set((dsl::amount.eq(value), dsl::watch_time.eq(watch_time)))
-                                .execute(&*c)?;
-                        }
+        self.db
+            .asyncify(move |c| {
+                let query = dsl::balances
+                    .filter(dsl::channel.eq(channel).and(dsl::user.ne_all(ignore)))
+                    .into_boxed();
+
+                let query = match order {
+                    TopOrder::Amount => query.order((dsl::amount.desc(), dsl::watch_time.desc())),
+                    TopOrder::WatchTime => {
+                        query.order((dsl::watch_time.desc(), dsl::amount.desc()))
                     }
+                };
+
+                let balances = query.limit(limit).load::<models::Balance>(&*c)?;
+                Ok(balances)
+            })
+            .await
+    }
+
+    /// Get the most recent transactions, newest first.
+    pub async fn transactions(
+        &self,
+        channel: &str,
+        user: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<models::CurrencyTransaction>> {
+        use self::schema::currency_transactions::dsl;
+
+        let channel = channel_id(channel);
+        let user = user.map(user_id);
+
+        self.db
+            .asyncify(move |c| {
+                let mut query = dsl::currency_transactions
+                    .filter(dsl::channel.eq(channel))
+                    .into_boxed();
+
+                if let Some(user) = user {
+                    query = query.filter(dsl::user.eq(user));
                 }
 
-                Ok(())
+                let transactions = query
+                    .order(dsl::id.desc())
+                    .offset(offset)
+                    .limit(limit)
+                    .load::<models::CurrencyTransaction>(&*c)?;
+
+                Ok(transactions)
             })
             .await
     }
 }
 
 /// Common function to modify the balance for the given user.
-fn modify_balance(c: &SqliteConnection, channel: &str, user: &str, amount: i64) -> Result<()> {
+fn modify_balance(
+    c: &SqliteConnection,
+    channel: &str,
+    user: &str,
+    amount: i64,
+    reason: Reason,
+) -> Result<()> {
     use self::schema::balances::dsl;
 
     let filter = dsl::balances.filter(dsl::channel.eq(channel).and(dsl::user.eq(user)));
 
-    match filter.clone().first::<models::Balance>(&*c).optional()? {
+    let balance = match filter.clone().first::<models::Balance>(&*c).optional()? {
         None => {
             let balance = models::Balance {
                 channel: channel.to_string(),
@@ -218,6 +307,8 @@ fn modify_balance(c: &SqliteConnection, channel: &str, user: &str, amount: i64)
             diesel::insert_into(dsl::balances)
                 .values(&balance)
                 .execute(c)?;
+
+            amount
         }
         Some(b) => {
             let amount = b.amount.saturating_add(amount);
@@ -225,8 +316,37 @@ fn modify_balance(c: &SqliteConnection, channel: &str, user: &str, amount: i64)
             diesel::update(filter)
                 .set(dsl::amount.eq(amount))
                 .execute(c)?;
+
+            amount
         }
-    }
+    };
+
+    record_transaction(c, channel, user, amount, reason, balance)?;
+    Ok(())
+}
+
+/// Append a transaction to the ledger.
+fn record_transaction(
+    c: &SqliteConnection,
+    channel: &str,
+    user: &str,
+    amount: i64,
+    reason: Reason,
+    balance: i64,
+) -> Result<()> {
+    use self::schema::currency_transactions::dsl;
+
+    let transaction = models::InsertCurrencyTransaction {
+        channel,
+        user,
+        amount,
+        reason: reason.as_str(),
+        balance,
+    };
+
+    diesel::insert_into(dsl::currency_transactions)
+        .values(&transaction)
+        .execute(c)?;
 
     Ok(())
 }
@@ -1,17 +1,102 @@
 //! Stream currency configuration.
 use crate::api;
-pub use crate::db::models::Balance;
-use crate::db::Database;
+pub use crate::db::models::{Balance, CurrencyTransaction as Transaction};
+use crate::db::{self, Database};
 pub use crate::injector;
 pub use crate::utils::Duration;
-use anyhow::{Error, Result};
-use std::collections::HashSet;
+use anyhow::{bail, Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::sync::Arc;
 use thiserror::Error;
 
 mod builtin;
 mod mysql;
 
+/// The account which holds the stakes of an ongoing heist.
+///
+/// Not a valid twitch username, so it can't collide with a real user.
+pub const HEIST_ESCROW: &str = "$heist";
+
+/// The account which holds the stakes of a duel while it's being fought.
+pub const DUEL_ESCROW: &str = "$duel";
+
+/// The account which collects the currency spent on custom commands.
+pub const COMMAND_COSTS: &str = "$commands";
+
+/// The reason a balance was modified, as recorded in the transaction ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    /// Reward for watching the stream.
+    WatchReward,
+    /// Transfer from one user to another.
+    Transfer,
+    /// Currency given to or taken from a user by an administrator.
+    Admin,
+    /// Currency given to everyone in chat by an administrator.
+    Windfall,
+    /// Reward for a water reminder, or the undoing of one.
+    Water,
+    /// The cost of a ChaosMod effect.
+    Gtav,
+    /// Reward for requesting a song.
+    SongRequest,
+    /// Payout from the swear jar.
+    Swearjar,
+    /// Reward for a chat event, like a subscription or a raid.
+    Event,
+    /// Winnings or losses from gambling.
+    Gamble,
+    /// Tickets bought for a raffle.
+    Raffle,
+    /// An item redeemed from the shop, or a refund for one.
+    Shop,
+    /// A stake placed on a prediction, or a payout or refund for one.
+    Bet,
+    /// Paying to skip ahead in the viewer queue.
+    Queue,
+    /// The cost of invoking a custom command, or a payout from one.
+    Command,
+}
+
+impl Reason {
+    /// Get the reason as it is stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Reason::WatchReward => "watch-reward",
+            Reason::Transfer => "transfer",
+            Reason::Admin => "admin",
+            Reason::Windfall => "windfall",
+            Reason::Water => "water",
+            Reason::Gtav => "gtav",
+            Reason::SongRequest => "song-request",
+            Reason::Swearjar => "swearjar",
+            Reason::Event => "event",
+            Reason::Gamble => "gamble",
+            Reason::Raffle => "raffle",
+            Reason::Shop => "shop",
+            Reason::Bet => "bet",
+            Reason::Queue => "queue",
+            Reason::Command => "command",
+        }
+    }
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+/// What to rank balances by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub enum TopOrder {
+    #[serde(rename = "amount")]
+    Amount,
+    #[serde(rename = "watch_time")]
+    WatchTime,
+}
+
 /// Balance of a single user.
 #[derive(Default)]
 pub struct BalanceOf {
@@ -41,6 +126,9 @@ pub struct CurrencyBuilder {
     pub name: Option<Arc<String>>,
     pub db: Option<Database>,
     pub mysql_url: Option<String>,
+    pub top_ignore: HashSet<String>,
+    /// Name of the bot, which is never included in leaderboards.
+    pub bot: Option<String>,
 }
 
 impl CurrencyBuilder {
@@ -60,6 +148,8 @@ impl CurrencyBuilder {
             name: Default::default(),
             db: None,
             mysql_url: None,
+            top_ignore: Default::default(),
+            bot: None,
         }
     }
 
@@ -130,11 +220,21 @@ impl CurrencyBuilder {
         let name = Arc::new(self.name.as_ref()?.to_string());
         let twitch = self.twitch.clone();
         let command_enabled = self.command_enabled;
+        let mut top_ignore = self
+            .top_ignore
+            .iter()
+            .map(|u| db::user_id(u))
+            .collect::<Vec<_>>();
+        top_ignore.extend(self.bot.as_deref().map(db::user_id));
 
         Some(Currency {
             name,
             command_enabled,
-            inner: Arc::new(Inner { backend, twitch }),
+            inner: Arc::new(Inner {
+                backend,
+                twitch,
+                top_ignore,
+            }),
         })
     }
 }
@@ -169,13 +269,14 @@ impl Backend {
         taker: &str,
         amount: i64,
         override_balance: bool,
+        reason: Reason,
     ) -> Result<(), BalanceTransferError> {
         use self::Backend::*;
 
         match *self {
             BuiltIn(ref backend) => {
                 backend
-                    .balance_transfer(channel, giver, taker, amount, override_balance)
+                    .balance_transfer(channel, giver, taker, amount, override_balance, reason)
                     .await
             }
             MySql(ref backend) => {
@@ -217,11 +318,17 @@ impl Backend {
     }
 
     /// Add (or subtract) from the balance for a single user.
-    pub async fn balance_add(&self, channel: &str, user: &str, amount: i64) -> Result<()> {
+    pub async fn balance_add(
+        &self,
+        channel: &str,
+        user: &str,
+        amount: i64,
+        reason: Reason,
+    ) -> Result<()> {
         use self::Backend::*;
 
         match *self {
-            BuiltIn(ref backend) => backend.balance_add(channel, user, amount).await,
+            BuiltIn(ref backend) => backend.balance_add(channel, user, amount, reason).await,
             MySql(ref backend) => backend.balance_add(channel, user, amount).await,
         }
     }
@@ -233,6 +340,7 @@ impl Backend {
         users: I,
         amount: i64,
         watch_time: i64,
+        reason: Reason,
     ) -> Result<()>
     where
         I: IntoIterator<Item = String> + Send + 'static,
@@ -243,17 +351,65 @@ impl Backend {
         match *self {
             BuiltIn(ref backend) => {
                 backend
-                    .balances_increment(channel, users, amount, watch_time)
+                    .balances_increment(channel, users, amount, watch_time, reason)
                     .await
             }
             MySql(ref backend) => backend.balances_increment(channel, users, amount).await,
         }
     }
+
+    /// Get the most recent transactions for the given user, newest first.
+    ///
+    /// The ledger is only kept by the built-in backend, so this errors for the
+    /// MySQL backends.
+    pub async fn transactions(
+        &self,
+        channel: &str,
+        user: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Transaction>> {
+        use self::Backend::*;
+
+        match *self {
+            BuiltIn(ref backend) => backend.transactions(channel, user, offset, limit).await,
+            MySql(..) => bail!("transaction history is not supported for this backend"),
+        }
+    }
+
+    /// Get the top balances, excluding the ignored users.
+    pub async fn top(
+        &self,
+        channel: &str,
+        order: TopOrder,
+        limit: i64,
+        ignore: Vec<String>,
+    ) -> Result<Vec<Balance>> {
+        use self::Backend::*;
+
+        match *self {
+            BuiltIn(ref backend) => backend.top(channel, order, limit, ignore).await,
+            MySql(ref backend) => match order {
+                TopOrder::Amount => backend.top(limit, ignore).await,
+                TopOrder::WatchTime => bail!("watch time is not tracked by the mysql backend"),
+            },
+        }
+    }
+
+    /// Test if the backend keeps track of watch time.
+    pub fn has_watch_time(&self) -> bool {
+        match *self {
+            Backend::BuiltIn(..) => true,
+            Backend::MySql(..) => false,
+        }
+    }
 }
 
 struct Inner {
     backend: Backend,
     twitch: api::Twitch,
+    /// Users to exclude from leaderboards, including the bot.
+    top_ignore: Vec<String>,
 }
 
 /// The currency being used.
@@ -271,6 +427,7 @@ impl Currency {
         channel: &str,
         reward: i64,
         watch_time: i64,
+        reason: Reason,
     ) -> Result<usize, anyhow::Error> {
         let chatters = self.inner.twitch.chatters(channel).await?;
 
@@ -283,12 +440,56 @@ impl Currency {
 
         self.inner
             .backend
-            .balances_increment(channel, users, reward, watch_time)
+            .balances_increment(channel, users, reward, watch_time, reason)
             .await?;
 
         Ok(len)
     }
 
+    /// Reward all users, scaling the reward of each user by the percentage
+    /// returned by `scale`.
+    ///
+    /// The scale function is called with the name of the user and whether or
+    /// not they are a VIP.
+    pub async fn add_channel_all_scaled<F>(
+        &self,
+        channel: &str,
+        reward: i64,
+        watch_time: i64,
+        reason: Reason,
+        scale: F,
+    ) -> Result<usize, anyhow::Error>
+    where
+        F: Fn(&str, bool) -> i64,
+    {
+        let chatters = self.inner.twitch.chatters(channel).await?;
+        let vips = chatters.vips.into_iter().collect::<HashSet<_>>();
+
+        let mut users = HashSet::new();
+        users.extend(chatters.viewers);
+        users.extend(chatters.moderators);
+        users.extend(chatters.broadcaster);
+        users.extend(vips.iter().cloned());
+
+        let len = users.len();
+
+        let mut groups = HashMap::<i64, Vec<String>>::new();
+
+        for user in users {
+            let amount = (reward * scale(&user, vips.contains(&user))) / 100;
+            groups.entry(amount).or_default().push(user);
+        }
+
+        for (amount, users) in groups {
+            self.inner
+                .backend
+                .balances_increment(channel, users, amount, watch_time, reason)
+                .await?;
+        }
+
+        Ok(len)
+    }
+
     /// Add (or subtract) from the balance for a single user.
     pub async fn balance_transfer(
         &self,
@@ -297,10 +498,11 @@ impl Currency {
         taker: &str,
         amount: i64,
         override_balance: bool,
+        reason: Reason,
     ) -> Result<(), BalanceTransferError> {
         self.inner
             .backend
-            .balance_transfer(channel, giver, taker, amount, override_balance)
+            .balance_transfer(channel, giver, taker, amount, override_balance, reason)
             .await
     }
 
@@ -320,8 +522,17 @@ impl Currency {
     }
 
     /// Add (or subtract) from the balance for a single user.
-    pub async fn balance_add(&self, channel: &str, user: &str, amount: i64) -> Result<()> {
-        self.inner.backend.balance_add(channel, user, amount).await
+    pub async fn balance_add(
+        &self,
+        channel: &str,
+        user: &str,
+        amount: i64,
+        reason: Reason,
+    ) -> Result<()> {
+        self.inner
+            .backend
+            .balance_add(channel, user, amount, reason)
+            .await
     }
 
     /// Add balance to users.
@@ -331,6 +542,7 @@ impl Currency {
         users: I,
         amount: i64,
         watch_time: i64,
+        reason: Reason,
     ) -> Result<()>
     where
         I: IntoIterator<Item = String> + Send + 'static,
@@ -338,9 +550,46 @@ impl Currency {
     {
         self.inner
             .backend
-            .balances_increment(channel, users, amount, watch_time)
+            .balances_increment(channel, users, amount, watch_time, reason)
+            .await
+    }
+
+    /// Get the most recent transactions in the ledger, newest first.
+    ///
+    /// If `user` is specified, only transactions for that user are returned.
+    /// The ledger is only kept by the built-in backend, so this errors for the
+    /// MySQL backends.
+    pub async fn transactions(
+        &self,
+        channel: &str,
+        user: Option<&str>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<Transaction>> {
+        self.inner
+            .backend
+            .transactions(channel, user, offset, limit)
             .await
     }
+
+    /// Get the top balances ranked by the given order.
+    ///
+    /// The bot, the owner of the channel, internal accounts, and users in the
+    /// configured ignore list are never included.
+    pub async fn top(&self, channel: &str, order: TopOrder, limit: i64) -> Result<Vec<Balance>> {
+        let mut ignore = self.inner.top_ignore.clone();
+        ignore.push(channel.trim_start_matches('#').to_lowercase());
+        ignore.push(HEIST_ESCROW.to_string());
+        ignore.push(DUEL_ESCROW.to_string());
+        ignore.push(COMMAND_COSTS.to_string());
+
+        self.inner.backend.top(channel, order, limit, ignore).await
+    }
+
+    /// Test if the currency keeps track of watch time.
+    pub fn has_watch_time(&self) -> bool {
+        self.inner.backend.has_watch_time()
+    }
 }
 
 #[derive(Debug, Error)]
@@ -58,6 +58,32 @@ impl Queries {
         Ok(results)
     }
 
+    /// Select the top balances.
+    async fn select_top_balances<Tx>(&self, tx: &mut Tx, limit: i64) -> Result<Vec<(String, i32)>>
+    where
+        Tx: Queryable,
+    {
+        let query = format!(
+            "SELECT `{user_column}`, `{balance_column}` \
+             FROM `{table}` \
+             ORDER BY `{balance_column}` DESC \
+             LIMIT :limit",
+            table = self.schema.table,
+            balance_column = self.schema.balance_column,
+            user_column = self.schema.user_column,
+        );
+
+        let params = params! {
+            "limit" => limit,
+        };
+
+        log::trace!("select_top_balances: {} {:?}", query, params);
+        let results = tx
+            .exec_map(query.as_str(), params, mysql::from_row::<(String, i32)>)
+            .await?;
+        Ok(results)
+    }
+
     /// Select the given balance.
     async fn select_balance<Tx>(&self, tx: &mut Tx, user: &str) -> Result<Option<i32>>
     where
@@ -281,6 +307,34 @@ impl Backend {
         }))
     }
 
+    /// Get the top balances.
+    pub async fn top(&self, limit: i64, ignore: Vec<String>) -> Result<Vec<Balance>> {
+        let channel = self.channel.to_string();
+
+        let opts = mysql::TxOpts::new();
+        let mut tx = self.pool.start_transaction(opts).await?;
+
+        // NB: fetch enough rows to fill the limit after ignored users are removed.
+        let balances = self
+            .queries
+            .select_top_balances(&mut tx, limit + ignore.len() as i64)
+            .await?;
+
+        let output = balances
+            .into_iter()
+            .filter(|(user, _)| !ignore.contains(user))
+            .take(limit as usize)
+            .map(|(user, balance)| Balance {
+                channel: channel.clone(),
+                user,
+                amount: balance as i64,
+                watch_time: 0,
+            })
+            .collect();
+
+        Ok(output)
+    }
+
     /// Add (or subtract) from the balance for a single user.
     pub async fn balance_add(&self, _channel: &str, user: &str, amount: i64) -> Result<()> {
         let user = user_id(&user);
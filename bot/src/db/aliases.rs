@@ -35,6 +35,9 @@ impl Database {
                             text: text.to_string(),
                             group: None,
                             disabled: false,
+                            cooldown: None,
+                            user_cooldown: None,
+                            scope: None,
                         };
 
                         diesel::insert_into(dsl::aliases)
@@ -78,6 +81,34 @@ impl Database {
             })
             .await
     }
+
+    /// Edit the restrictions of an alias.
+    async fn edit_restrictions(
+        &self,
+        key: &db::Key,
+        restrictions: &db::Restrictions,
+    ) -> Result<bool, anyhow::Error> {
+        use db::schema::aliases::dsl;
+
+        let key = key.clone();
+        let (cooldown, user_cooldown, scope) = restrictions.to_db();
+
+        self.0
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::aliases.filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .set((
+                    dsl::cooldown.eq(cooldown),
+                    dsl::user_cooldown.eq(user_cooldown),
+                    dsl::scope.eq(scope),
+                ))
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
 }
 
 #[derive(Clone)]
@@ -107,7 +138,11 @@ impl Aliases {
     }
 
     /// Resolve the given command.
-    pub async fn resolve(&self, channel: &str, message: Arc<String>) -> Option<(db::Key, String)> {
+    pub async fn resolve(
+        &self,
+        channel: &str,
+        message: Arc<String>,
+    ) -> Option<(Arc<Alias>, String)> {
         let mut it = utils::Words::new(message);
         let first = it.next();
 
@@ -117,10 +152,8 @@ impl Aliases {
                 .await
                 .resolve(channel, first.as_deref(), &it)
         {
-            let key = alias.key.clone();
-
             match alias.template.render_to_string(&captures) {
-                Ok(s) => return Some((key, s)),
+                Ok(s) => return Some((alias.clone(), s)),
                 Err(e) => {
                     log::error!("failed to render alias: {}", e);
                 }
@@ -146,10 +179,17 @@ impl Aliases {
         } else {
             let pattern = db::Pattern::from_db(alias.pattern.as_ref())?;
 
+            let restrictions = db::Restrictions::from_db(
+                alias.cooldown,
+                alias.user_cooldown,
+                alias.scope.as_deref(),
+            )?;
+
             let alias = Alias {
                 key: key.clone(),
                 pattern,
                 template,
+                restrictions,
                 group: alias.group,
                 disabled: alias.disabled,
             };
@@ -174,6 +214,26 @@ impl Aliases {
             alias.pattern = pattern.map(db::Pattern::regex).unwrap_or_default();
         }))
     }
+
+    /// Edit the restrictions for the given alias.
+    pub async fn edit_restrictions(
+        &self,
+        channel: &str,
+        name: &str,
+        restrictions: db::Restrictions,
+    ) -> Result<bool, anyhow::Error> {
+        let key = db::Key::new(channel, name);
+
+        if !self.db.edit_restrictions(&key, &restrictions).await? {
+            return Ok(false);
+        }
+
+        self.inner.write().await.modify(key, |alias| {
+            alias.restrictions = restrictions;
+        });
+
+        Ok(true)
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -181,6 +241,8 @@ pub struct Alias {
     pub key: db::Key,
     pub pattern: db::Pattern,
     pub template: template::Template,
+    /// Restrictions on who can invoke the alias, and how often.
+    pub restrictions: db::Restrictions,
     pub group: Option<String>,
     pub disabled: bool,
 }
@@ -203,11 +265,14 @@ impl Alias {
         let key = db::Key::new(&alias.channel, &alias.name);
         let pattern = db::Pattern::from_db(alias.pattern.as_ref())?;
         let template = template::Template::compile(&alias.text)?;
+        let restrictions =
+            db::Restrictions::from_db(alias.cooldown, alias.user_cooldown, alias.scope.as_deref())?;
 
         Ok(Alias {
             key,
             pattern,
             template,
+            restrictions,
             group: alias.group.clone(),
             disabled: alias.disabled,
         })
@@ -218,9 +283,10 @@ impl fmt::Display for Alias {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             fmt,
-            "template = \"{template}\", pattern = {pattern}, group = {group}, disabled = {disabled}",
+            "template = \"{template}\", pattern = {pattern}, {restrictions}, group = {group}, disabled = {disabled}",
             template = self.template,
             pattern = self.pattern,
+            restrictions = self.restrictions,
             group = self.group.as_deref().unwrap_or("*none*"),
             disabled = self.disabled,
         )
@@ -0,0 +1,227 @@
+use crate::template;
+use anyhow::{bail, Error};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Names which can't be used for arguments since they would shadow other
+/// variables available to command templates.
+///
+/// The names of template helpers are also reserved, see
+/// [template::is_helper].
+const RESERVED: &[&str] = &[
+    "name", "target", "count", "args", "stream", "counters", "balances", "rest",
+];
+
+/// The named arguments accepted by a custom command, like
+/// `{user} {message...?}`.
+///
+/// * `{name}` is a required argument.
+/// * `{name?}` is an optional argument.
+/// * `{name...}` takes the rest of the message, and must come last.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandArgs {
+    args: Vec<Arg>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Arg {
+    name: String,
+    optional: bool,
+    rest: bool,
+}
+
+impl CommandArgs {
+    /// Test if the given string looks like the start of an argument
+    /// specification, as opposed to a template.
+    pub fn is_spec(s: &str) -> bool {
+        s.starts_with('{') && !s.starts_with("{{")
+    }
+
+    /// Bind the given input to the named arguments.
+    ///
+    /// Returns `None` if any required arguments are missing.
+    pub fn bind<'a>(&self, input: &'a str) -> Option<HashMap<String, &'a str>> {
+        let mut out = HashMap::new();
+        let mut rest = input.trim();
+
+        for arg in &self.args {
+            if rest.is_empty() {
+                if arg.optional {
+                    continue;
+                }
+
+                return None;
+            }
+
+            if arg.rest {
+                out.insert(arg.name.clone(), rest);
+                rest = "";
+                continue;
+            }
+
+            let (word, tail) = match rest.find(char::is_whitespace) {
+                Some(n) => (&rest[..n], rest[n..].trim_start()),
+                None => (rest, ""),
+            };
+
+            out.insert(arg.name.clone(), word);
+            rest = tail;
+        }
+
+        Some(out)
+    }
+
+    /// Build a usage string for the arguments, like `<user> [message...]`.
+    pub fn usage(&self) -> String {
+        let mut parts = Vec::new();
+
+        for arg in &self.args {
+            let dots = if arg.rest { "..." } else { "" };
+
+            if arg.optional {
+                parts.push(format!("[{}{}]", arg.name, dots));
+            } else {
+                parts.push(format!("<{}{}>", arg.name, dots));
+            }
+        }
+
+        parts.join(" ")
+    }
+}
+
+impl std::str::FromStr for CommandArgs {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut args = Vec::new();
+        let mut seen = HashSet::new();
+
+        for word in s.split_whitespace() {
+            if word.len() < 2 || !word.starts_with('{') || !word.ends_with('}') {
+                bail!("expected argument like `{{name}}`, but got `{}`", word);
+            }
+
+            let mut name = &word[1..word.len() - 1];
+
+            let optional = name.ends_with('?');
+
+            if optional {
+                name = &name[..name.len() - 1];
+            }
+
+            let rest = name.ends_with("...");
+
+            if rest {
+                name = &name[..name.len() - 3];
+            }
+
+            let valid = name
+                .chars()
+                .next()
+                .map(|c| c.is_ascii_alphabetic() || c == '_')
+                .unwrap_or_default()
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+            if !valid {
+                bail!("bad argument name `{}`", name);
+            }
+
+            if RESERVED.contains(&name) || template::is_helper(name) {
+                bail!("`{}` is reserved and can't be used as an argument", name);
+            }
+
+            if !seen.insert(name) {
+                bail!("argument `{}` is specified more than once", name);
+            }
+
+            if let Some(last) = args.last() {
+                if last.rest {
+                    bail!("argument `{}` can't follow `{}...`", name, last.name);
+                }
+
+                if last.optional && !optional {
+                    bail!(
+                        "required argument `{}` can't follow optional `{}`",
+                        name,
+                        last.name
+                    );
+                }
+            }
+
+            args.push(Arg {
+                name: name.to_string(),
+                optional,
+                rest,
+            });
+        }
+
+        Ok(CommandArgs { args })
+    }
+}
+
+impl fmt::Display for CommandArgs {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut it = self.args.iter().peekable();
+
+        while let Some(arg) = it.next() {
+            let dots = if arg.rest { "..." } else { "" };
+            let optional = if arg.optional { "?" } else { "" };
+            write!(fmt, "{{{}{}{}}}", arg.name, dots, optional)?;
+
+            if it.peek().is_some() {
+                fmt.write_str(" ")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl serde::Serialize for CommandArgs {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CommandArgs;
+    use anyhow::Error;
+
+    #[test]
+    fn test_parse() -> Result<(), Error> {
+        let args = str::parse::<CommandArgs>("{user} {times?} {message...?}")?;
+        assert_eq!("{user} {times?} {message...?}", args.to_string());
+        assert_eq!("<user> [times] [message...]", args.usage());
+
+        assert!(str::parse::<CommandArgs>("user").is_err());
+        assert!(str::parse::<CommandArgs>("{name}").is_err());
+        assert!(str::parse::<CommandArgs>("{touser}").is_err());
+        assert!(str::parse::<CommandArgs>("{random}").is_err());
+        assert!(str::parse::<CommandArgs>("{a} {a}").is_err());
+        assert!(str::parse::<CommandArgs>("{a?} {b}").is_err());
+        assert!(str::parse::<CommandArgs>("{a...} {b?}").is_err());
+        assert!(str::parse::<CommandArgs>("{1a}").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bind() -> Result<(), Error> {
+        let args = str::parse::<CommandArgs>("{user} {message...?}")?;
+
+        let bound = args
+            .bind("  setbac  hello there ")
+            .expect("bound arguments");
+        assert_eq!(Some(&"setbac"), bound.get("user"));
+        assert_eq!(Some(&"hello there"), bound.get("message"));
+
+        let bound = args.bind("setbac").expect("bound arguments");
+        assert_eq!(None, bound.get("message"));
+
+        assert!(args.bind("  ").is_none());
+        Ok(())
+    }
+}
@@ -16,8 +16,13 @@ struct Database(db::Database);
 impl Database {
     private_database_group_fns!(commands, Command, db::Key);
 
-    /// Edit the text for the given key.
-    async fn edit(&self, key: &db::Key, text: &str) -> Result<db::models::Command, Error> {
+    /// Edit the text for the given key, and the arguments if specified.
+    async fn edit(
+        &self,
+        key: &db::Key,
+        text: &str,
+        args: Option<Option<String>>,
+    ) -> Result<db::models::Command, Error> {
         use db::schema::commands::dsl;
 
         let key = key.clone();
@@ -38,6 +43,11 @@ impl Database {
                             text: text.to_string(),
                             group: None,
                             disabled: false,
+                            cooldown: None,
+                            user_cooldown: None,
+                            scope: None,
+                            cost: None,
+                            args: args.flatten(),
                         };
 
                         diesel::insert_into(dsl::commands)
@@ -46,10 +56,26 @@ impl Database {
                         Ok(command)
                     }
                     Some(command) => {
-                        let mut set = db::models::UpdateCommand::default();
-                        set.text = Some(&text);
-                        diesel::update(filter).set(&set).execute(c)?;
-                        Ok(command)
+                        let args = match args {
+                            Some(args) => {
+                                diesel::update(filter)
+                                    .set((dsl::text.eq(&text), dsl::args.eq(args.as_deref())))
+                                    .execute(c)?;
+
+                                args
+                            }
+                            None => {
+                                diesel::update(filter).set(dsl::text.eq(&text)).execute(c)?;
+
+                                command.args.clone()
+                            }
+                        };
+
+                        Ok(db::models::Command {
+                            text,
+                            args,
+                            ..command
+                        })
                     }
                 }
             })
@@ -83,6 +109,55 @@ impl Database {
             .await
     }
 
+    /// Edit the cost of a command.
+    async fn edit_cost(&self, key: &db::Key, cost: Option<i64>) -> Result<bool, anyhow::Error> {
+        use db::schema::commands::dsl;
+
+        let key = key.clone();
+
+        self.0
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::commands
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .set(dsl::cost.eq(cost))
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Edit the restrictions of a command.
+    async fn edit_restrictions(
+        &self,
+        key: &db::Key,
+        restrictions: &db::Restrictions,
+    ) -> Result<bool, anyhow::Error> {
+        use db::schema::commands::dsl;
+
+        let key = key.clone();
+        let (cooldown, user_cooldown, scope) = restrictions.to_db();
+
+        self.0
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::commands
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .set((
+                    dsl::cooldown.eq(cooldown),
+                    dsl::user_cooldown.eq(user_cooldown),
+                    dsl::scope.eq(scope),
+                ))
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
     /// Increment the given key.
     async fn increment(&self, key: &db::Key) -> Result<bool, Error> {
         use db::schema::commands::dsl;
@@ -129,17 +204,37 @@ impl Commands {
         })
     }
 
-    /// Insert a word into the bad words list.
+    /// Edit the template of the given command, creating it if it doesn't
+    /// exist.
+    ///
+    /// The arguments are only changed if `args` is specified, where
+    /// `Some(None)` removes them.
     pub async fn edit(
         &self,
         channel: &str,
         name: &str,
         template: template::Template,
+        args: Option<Option<db::CommandArgs>>,
     ) -> Result<(), Error> {
         let key = db::Key::new(channel, name);
 
         let mut inner = self.inner.write().await;
-        let command = self.db.edit(&key, template.source()).await?;
+        let command = self
+            .db
+            .edit(
+                &key,
+                template.source(),
+                args.as_ref().map(|a| a.as_ref().map(|a| a.to_string())),
+            )
+            .await?;
+
+        let args = match args {
+            Some(args) => args,
+            None => match command.args.as_deref() {
+                Some(args) => Some(str::parse::<db::CommandArgs>(args)?),
+                None => None,
+            },
+        };
 
         if command.disabled {
             inner.remove(&key);
@@ -152,6 +247,13 @@ impl Commands {
                 count: Arc::new(AtomicUsize::new(command.count as usize)),
                 template,
                 vars,
+                restrictions: db::Restrictions::from_db(
+                    command.cooldown,
+                    command.user_cooldown,
+                    command.scope.as_deref(),
+                )?,
+                cost: command.cost,
+                args,
                 group: command.group,
                 disabled: command.disabled,
             });
@@ -177,6 +279,46 @@ impl Commands {
         }))
     }
 
+    /// Edit the cost for the given command.
+    pub async fn edit_cost(
+        &self,
+        channel: &str,
+        name: &str,
+        cost: Option<i64>,
+    ) -> Result<bool, anyhow::Error> {
+        let key = db::Key::new(channel, name);
+
+        if !self.db.edit_cost(&key, cost).await? {
+            return Ok(false);
+        }
+
+        self.inner.write().await.modify(key, |command| {
+            command.cost = cost;
+        });
+
+        Ok(true)
+    }
+
+    /// Edit the restrictions for the given command.
+    pub async fn edit_restrictions(
+        &self,
+        channel: &str,
+        name: &str,
+        restrictions: db::Restrictions,
+    ) -> Result<bool, anyhow::Error> {
+        let key = db::Key::new(channel, name);
+
+        if !self.db.edit_restrictions(&key, &restrictions).await? {
+            return Ok(false);
+        }
+
+        self.inner.write().await.modify(key, |command| {
+            command.restrictions = restrictions;
+        });
+
+        Ok(true)
+    }
+
     /// Increment the specified command.
     pub async fn increment(&self, command: &Command) -> Result<(), Error> {
         self.db.increment(&command.key).await?;
@@ -210,6 +352,12 @@ pub struct Command {
     count: Arc<AtomicUsize>,
     pub template: template::Template,
     vars: HashSet<String>,
+    /// Restrictions on who can invoke the command, and how often.
+    pub restrictions: db::Restrictions,
+    /// Currency to charge for invoking the command. Negative amounts pay out.
+    pub cost: Option<i64>,
+    /// Named arguments accepted by the command.
+    pub args: Option<db::CommandArgs>,
     pub group: Option<String>,
     pub disabled: bool,
 }
@@ -239,6 +387,19 @@ impl Command {
         let vars = template.vars();
 
         let pattern = db::Pattern::from_db(command.pattern.as_ref())?;
+        let restrictions = db::Restrictions::from_db(
+            command.cooldown,
+            command.user_cooldown,
+            command.scope.as_deref(),
+        )?;
+
+        let args = match command.args.as_deref() {
+            Some(args) => Some(
+                str::parse::<db::CommandArgs>(args)
+                    .with_context(|| anyhow!("bad arguments for command `{:?}`", command))?,
+            ),
+            None => None,
+        };
 
         Ok(Command {
             key,
@@ -246,6 +407,9 @@ impl Command {
             count,
             template,
             vars,
+            restrictions,
+            cost: command.cost,
+            args,
             group: command.group.clone(),
             disabled: command.disabled,
         })
@@ -284,9 +448,12 @@ impl fmt::Display for Command {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             fmt,
-            "template = \"{template}\", pattern = {pattern}, group = {group}, disabled = {disabled}",
+            "template = \"{template}\", args = {args}, pattern = {pattern}, {restrictions}, cost = {cost}, group = {group}, disabled = {disabled}",
             template = self.template,
+            args = self.args.as_ref().map(|a| format!("\"{}\"", a)).as_deref().unwrap_or("*none*"),
             pattern = self.pattern,
+            restrictions = self.restrictions,
+            cost = self.cost.map(|c| c.to_string()).as_deref().unwrap_or("*none*"),
             group = self.group.as_deref().unwrap_or("*none*"),
             disabled = self.disabled,
         )
@@ -0,0 +1,142 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+use std::collections::HashMap;
+
+pub use self::models::Counter;
+
+#[derive(Clone)]
+pub struct Counters {
+    db: db::Database,
+}
+
+impl Counters {
+    /// Open the counters database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all counters in the given channel.
+    pub async fn list(&self, channel: &str) -> Result<Vec<Counter>> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::counters
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::name.asc())
+                    .load::<Counter>(c)?)
+            })
+            .await
+    }
+
+    /// Get the values of all counters in the given channel, keyed by name.
+    pub async fn values(&self, channel: &str) -> Result<HashMap<String, i64>> {
+        Ok(self
+            .list(channel)
+            .await?
+            .into_iter()
+            .map(|c| (c.name, c.value))
+            .collect())
+    }
+
+    /// Get a single counter.
+    pub async fn get(&self, channel: &str, name: &str) -> Result<Option<Counter>> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::counters
+                    .filter(dsl::channel.eq(channel).and(dsl::name.eq(name)))
+                    .first::<Counter>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Add the given amount to a counter, creating it if it doesn't exist.
+    pub async fn add(&self, channel: &str, name: &str, amount: i64) -> Result<Counter> {
+        self.modify(channel, name, move |counter| counter.value += amount)
+            .await
+    }
+
+    /// Set the value of a counter, creating it if it doesn't exist.
+    pub async fn set(&self, channel: &str, name: &str, value: i64) -> Result<Counter> {
+        self.modify(channel, name, move |counter| counter.value = value)
+            .await
+    }
+
+    /// Set the scope required to modify a counter, creating it if it doesn't
+    /// exist.
+    pub async fn set_scope(
+        &self,
+        channel: &str,
+        name: &str,
+        scope: Option<String>,
+    ) -> Result<Counter> {
+        self.modify(channel, name, move |counter| counter.scope = scope)
+            .await
+    }
+
+    /// Delete the given counter.
+    pub async fn delete(&self, channel: &str, name: &str) -> Result<bool> {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::counters.filter(dsl::channel.eq(channel).and(dsl::name.eq(name))),
+                )
+                .execute(c)?;
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Modify a counter in a transaction, creating it if it doesn't exist.
+    async fn modify<F>(&self, channel: &str, name: &str, f: F) -> Result<Counter>
+    where
+        F: 'static + Send + FnOnce(&mut Counter),
+    {
+        use self::schema::counters::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let counter = dsl::counters
+                        .filter(dsl::channel.eq(&channel).and(dsl::name.eq(&name)))
+                        .first::<Counter>(c)
+                        .optional()?;
+
+                    let mut counter = counter.unwrap_or_else(|| Counter {
+                        channel,
+                        name,
+                        value: 0,
+                        scope: None,
+                    });
+
+                    f(&mut counter);
+
+                    diesel::replace_into(dsl::counters)
+                        .values(&counter)
+                        .execute(c)?;
+
+                    Ok(counter)
+                })
+            })
+            .await
+    }
+}
@@ -0,0 +1,73 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::HeistStake;
+
+/// Stakes which are held in escrow while a heist is pending.
+#[derive(Clone)]
+pub struct HeistStakes {
+    db: db::Database,
+}
+
+impl HeistStakes {
+    /// Open the heist stakes database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all stakes held in escrow for the given channel.
+    pub async fn list(&self, channel: &str) -> Result<Vec<HeistStake>> {
+        use self::schema::heist_stakes::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::heist_stakes
+                    .filter(dsl::channel.eq(&channel))
+                    .load::<HeistStake>(c)?)
+            })
+            .await
+    }
+
+    /// Record a stake which has been moved into escrow.
+    pub async fn insert(&self, channel: &str, user: &str, amount: i64) -> Result<()> {
+        use self::schema::heist_stakes::dsl;
+
+        let stake = HeistStake {
+            channel: channel.to_string(),
+            user: user.to_string(),
+            amount,
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::heist_stakes)
+                    .values(&stake)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Remove a stake which has been settled.
+    pub async fn remove(&self, channel: &str, user: &str) -> Result<()> {
+        use self::schema::heist_stakes::dsl;
+
+        let channel = channel.to_string();
+        let user = user.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(
+                    dsl::heist_stakes.filter(dsl::channel.eq(&channel).and(dsl::user.eq(&user))),
+                )
+                .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+}
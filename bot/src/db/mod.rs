@@ -2,12 +2,24 @@
 mod macros;
 mod after_streams;
 mod aliases;
+mod command_args;
 pub(crate) mod commands;
+mod counters;
+mod heist_stakes;
 mod matcher;
 pub(crate) mod models;
+mod moderation_log;
+mod offences;
+mod polls;
+mod predictions;
 mod promotions;
+mod queue;
+mod quotes;
+mod raffles;
+mod restrictions;
 pub(crate) mod schema;
 mod script_storage;
+mod shop;
 mod themes;
 mod words;
 
@@ -20,12 +32,26 @@ use thiserror::Error;
 
 pub use self::after_streams::{AfterStream, AfterStreams};
 pub use self::aliases::{Alias, Aliases};
+pub use self::command_args::CommandArgs;
 pub use self::commands::{Command, Commands};
+pub use self::counters::{Counter, Counters};
+pub use self::heist_stakes::{HeistStake, HeistStakes};
 pub use self::matcher::Captures;
+pub use self::moderation_log::{
+    InsertModerationLogEntry, ModerationLog, ModerationLogEntry, ModerationLogFilter,
+};
+pub use self::offences::{Offence, Offences};
+pub use self::polls::{Poll, PollVote, Polls};
+pub use self::predictions::{Prediction, PredictionStake, PredictionState, Predictions};
 pub use self::promotions::{Promotion, Promotions};
+pub use self::queue::{Queue, QueueEntry};
+pub use self::quotes::{Quote, Quotes};
+pub use self::raffles::{Raffle, RaffleEntry, Raffles};
+pub use self::restrictions::Restrictions;
 pub use self::script_storage::ScriptStorage;
+pub use self::shop::{RedemptionState, Shop, ShopItem, ShopRedemption};
 pub use self::themes::{Theme, Themes};
-pub use self::words::{Word, Words};
+pub use self::words::{Word, WordKind, Words};
 
 pub use self::matcher::Key;
 pub(crate) use self::matcher::{Matchable, Matcher, Pattern};
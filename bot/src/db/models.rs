@@ -1,5 +1,8 @@
 use super::schema::{
-    after_streams, aliases, bad_words, balances, commands, promotions, script_keys, songs, themes,
+    after_streams, aliases, bad_words, balances, commands, counters, currency_transactions,
+    heist_stakes, moderation_log, offences, poll_votes, polls, prediction_stakes, predictions,
+    promotions, queue_entries, quotes, raffle_entries, raffles, script_keys, shop_items,
+    shop_redemptions, songs, themes,
 };
 use crate::track_id::TrackId;
 use chrono::NaiveDateTime;
@@ -42,15 +45,16 @@ pub struct Command {
     pub group: Option<String>,
     /// If the command is disabled.
     pub disabled: bool,
-}
-
-#[derive(Debug, Clone, Default, diesel::AsChangeset)]
-#[table_name = "commands"]
-pub struct UpdateCommand<'a> {
-    pub count: Option<i32>,
-    pub text: Option<&'a str>,
-    pub group: Option<&'a str>,
-    pub disabled: Option<bool>,
+    /// Cooldown in seconds between invocations of the command.
+    pub cooldown: Option<i32>,
+    /// Cooldown in seconds between invocations of the command by the same user.
+    pub user_cooldown: Option<i32>,
+    /// Scope required to invoke the command.
+    pub scope: Option<String>,
+    /// Currency to charge for invoking the command. Negative amounts pay out.
+    pub cost: Option<i64>,
+    /// The named arguments accepted by the command, like `{user} {message?}`.
+    pub args: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, diesel::Queryable, diesel::Insertable)]
@@ -68,6 +72,12 @@ pub struct Alias {
     pub group: Option<String>,
     /// If the promotion is disabled.
     pub disabled: bool,
+    /// Cooldown in seconds between invocations of the alias.
+    pub cooldown: Option<i32>,
+    /// Cooldown in seconds between invocations of the alias by the same user.
+    pub user_cooldown: Option<i32>,
+    /// Scope required to invoke the alias.
+    pub scope: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, diesel::Insertable)]
@@ -113,6 +123,8 @@ pub struct InsertAfterStream {
 pub struct BadWord {
     pub word: String,
     pub why: Option<String>,
+    /// How the word is matched, `regex` or `glob`. Matched as a word if empty.
+    pub kind: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, diesel::Queryable)]
@@ -212,3 +224,344 @@ pub struct ScriptKey {
 pub struct SetScriptKeyValue<'a> {
     pub value: &'a [u8],
 }
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct CurrencyTransaction {
+    /// The unique identifier of the transaction.
+    pub id: i32,
+    /// The channel the transaction belongs to.
+    pub channel: String,
+    /// The user whose balance was modified.
+    pub user: String,
+    /// The amount the balance was modified by.
+    pub amount: i64,
+    /// The reason for the transaction.
+    pub reason: String,
+    /// The balance of the user after the transaction.
+    pub balance: i64,
+    /// When the transaction happened.
+    pub created_at: NaiveDateTime,
+}
+
+/// Insert model for currency transactions.
+#[derive(diesel::Insertable)]
+#[table_name = "currency_transactions"]
+pub struct InsertCurrencyTransaction<'a> {
+    pub channel: &'a str,
+    pub user: &'a str,
+    pub amount: i64,
+    pub reason: &'a str,
+    pub balance: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Raffle {
+    /// The unique identifier of the raffle.
+    pub id: i32,
+    /// The channel the raffle belongs to.
+    pub channel: String,
+    /// The keyword used to enter the raffle.
+    pub keyword: String,
+    /// The cost of a single ticket.
+    pub ticket_cost: i64,
+    /// The maximum number of tickets a single user can hold.
+    pub max_tickets: i32,
+    /// If the raffle is accepting entries.
+    pub open: bool,
+    /// If the raffle has ended.
+    pub ended: bool,
+    /// When the raffle was opened.
+    pub created_at: NaiveDateTime,
+}
+
+/// Insert model for raffles.
+#[derive(diesel::Insertable)]
+#[table_name = "raffles"]
+pub struct InsertRaffle<'a> {
+    pub channel: &'a str,
+    pub keyword: &'a str,
+    pub ticket_cost: i64,
+    pub max_tickets: i32,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "raffle_entries"]
+pub struct RaffleEntry {
+    /// The raffle the entry belongs to.
+    pub raffle_id: i32,
+    /// The user who entered.
+    pub user: String,
+    /// The number of tickets held by the user.
+    pub tickets: i32,
+    /// If the user was a subscriber when they entered.
+    pub subscriber: bool,
+    /// If the user has been drawn as a winner.
+    pub won: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "shop_items"]
+pub struct ShopItem {
+    /// The channel the item belongs to.
+    pub channel: String,
+    /// The name of the item.
+    pub name: String,
+    /// The cost of the item.
+    pub cost: i64,
+    /// Cooldown in seconds between redemptions of the item.
+    pub cooldown: Option<i32>,
+    /// Number of items left in stock, unlimited if not set.
+    pub stock: Option<i32>,
+    /// The scope required to redeem the item.
+    pub scope: Option<String>,
+    /// Template used to respond to a redemption.
+    pub response: Option<String>,
+    /// If redemptions have to be fulfilled manually.
+    pub manual: bool,
+    /// If the item is disabled.
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct ShopRedemption {
+    /// The unique identifier of the redemption.
+    pub id: i32,
+    /// The channel the redemption belongs to.
+    pub channel: String,
+    /// The item that was redeemed.
+    pub item: String,
+    /// The user who redeemed the item.
+    pub user: String,
+    /// What the user paid for the item.
+    pub cost: i64,
+    /// Message provided by the user.
+    pub message: Option<String>,
+    /// The state of the redemption.
+    pub state: String,
+    /// When the item was redeemed.
+    pub created_at: NaiveDateTime,
+    /// When the redemption was fulfilled or rejected.
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+/// Insert model for shop redemptions.
+#[derive(diesel::Insertable)]
+#[table_name = "shop_redemptions"]
+pub struct InsertShopRedemption<'a> {
+    pub channel: &'a str,
+    pub item: &'a str,
+    pub user: &'a str,
+    pub cost: i64,
+    pub message: Option<&'a str>,
+    pub state: &'a str,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Poll {
+    /// The unique identifier of the poll.
+    pub id: i32,
+    /// The channel the poll belongs to.
+    pub channel: String,
+    /// The question being asked.
+    pub question: String,
+    /// JSON-encoded options of the poll.
+    pub options: String,
+    /// If votes are weighted by the balance of the voter.
+    pub weighted: bool,
+    /// When the poll closes automatically.
+    pub closes_at: Option<NaiveDateTime>,
+    /// If the poll is closed.
+    pub closed: bool,
+    /// When the poll was started.
+    pub created_at: NaiveDateTime,
+}
+
+/// Insert model for polls.
+#[derive(diesel::Insertable)]
+#[table_name = "polls"]
+pub struct InsertPoll<'a> {
+    pub channel: &'a str,
+    pub question: &'a str,
+    pub options: &'a str,
+    pub weighted: bool,
+    pub closes_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "poll_votes"]
+pub struct PollVote {
+    /// The poll the vote belongs to.
+    pub poll_id: i32,
+    /// The user who voted.
+    pub user: String,
+    /// The option voted for.
+    pub option: String,
+    /// The weight of the vote.
+    pub weight: i64,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Prediction {
+    /// The unique identifier of the prediction.
+    pub id: i32,
+    /// The channel the prediction belongs to.
+    pub channel: String,
+    /// The question being predicted.
+    pub question: String,
+    /// JSON-encoded list of outcomes.
+    pub outcomes: String,
+    /// When the prediction stops accepting stakes.
+    pub locks_at: NaiveDateTime,
+    /// The state of the prediction.
+    pub state: String,
+    /// The winning outcome, if resolved.
+    pub winner: Option<String>,
+    /// When the prediction was opened.
+    pub created_at: NaiveDateTime,
+}
+
+/// Insert model for predictions.
+#[derive(diesel::Insertable)]
+#[table_name = "predictions"]
+pub struct InsertPrediction<'a> {
+    pub channel: &'a str,
+    pub question: &'a str,
+    pub outcomes: &'a str,
+    pub locks_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "prediction_stakes"]
+pub struct PredictionStake {
+    /// The prediction the stake belongs to.
+    pub prediction_id: i32,
+    /// The user who placed the stake.
+    pub user: String,
+    /// The outcome the user is betting on.
+    pub outcome: String,
+    /// The amount staked.
+    pub amount: i64,
+    /// If the stake has been paid out or refunded.
+    pub settled: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct QueueEntry {
+    /// The unique identifier of the entry.
+    pub id: i32,
+    /// The channel the queue belongs to.
+    pub channel: String,
+    /// The user waiting in the queue.
+    pub user: String,
+    /// If the user has priority, like subscribers.
+    pub priority: bool,
+    /// When the user paid to skip ahead, if they did.
+    pub skipped_at: Option<NaiveDateTime>,
+    /// When the user joined the queue.
+    pub joined_at: NaiveDateTime,
+}
+
+/// Insert model for queue entries.
+#[derive(diesel::Insertable)]
+#[table_name = "queue_entries"]
+pub struct InsertQueueEntry<'a> {
+    pub channel: &'a str,
+    pub user: &'a str,
+    pub priority: bool,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct Quote {
+    /// The unique identifier of the quote.
+    pub id: i32,
+    /// The channel the quote belongs to.
+    pub channel: String,
+    /// The text of the quote.
+    pub quote: String,
+    /// The user who added the quote.
+    pub added_by: String,
+    /// The game being played when the quote was added.
+    pub game: Option<String>,
+    /// When the quote was added.
+    pub created_at: NaiveDateTime,
+}
+
+/// Insert model for quotes.
+#[derive(diesel::Insertable)]
+#[table_name = "quotes"]
+pub struct InsertQuote<'a> {
+    pub channel: &'a str,
+    pub quote: &'a str,
+    pub added_by: &'a str,
+    pub game: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "counters"]
+pub struct Counter {
+    /// The channel the counter belongs to.
+    pub channel: String,
+    /// The name of the counter.
+    pub name: String,
+    /// The current value of the counter.
+    pub value: i64,
+    /// The scope required to modify the counter, if any.
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable, diesel::Insertable)]
+#[table_name = "offences"]
+pub struct Offence {
+    /// The channel the offence was committed in.
+    pub channel: String,
+    /// The user who committed the offence.
+    pub user: String,
+    /// The filter which was triggered.
+    pub filter: String,
+    /// The number of offences which haven't decayed yet.
+    pub count: i32,
+    /// When the last offence was committed.
+    pub last_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, serde::Serialize, diesel::Queryable)]
+pub struct ModerationLogEntry {
+    /// The id of the entry.
+    pub id: i32,
+    /// The channel the action was taken in.
+    pub channel: String,
+    /// The action taken, like `delete`, `timeout 10m`, `ban`, or `permit`.
+    pub action: String,
+    /// The user the action was taken against.
+    pub user: String,
+    /// The message which caused the action, if any.
+    pub message: Option<String>,
+    /// The rule which matched, like a filter or a scope for permits.
+    pub rule: Option<String>,
+    /// Who took the action. Empty for automated actions.
+    pub actor: Option<String>,
+    /// When the action was taken.
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, diesel::Insertable)]
+#[table_name = "moderation_log"]
+pub struct InsertModerationLogEntry {
+    pub channel: String,
+    pub action: String,
+    pub user: String,
+    pub message: Option<String>,
+    pub rule: Option<String>,
+    pub actor: Option<String>,
+}
+
+#[derive(Debug, Clone, diesel::Queryable, diesel::Insertable)]
+#[table_name = "heist_stakes"]
+pub struct HeistStake {
+    /// The channel the heist is held in.
+    pub channel: String,
+    /// The user who staked currency.
+    pub user: String,
+    /// The amount held in escrow for the user.
+    pub amount: i64,
+}
@@ -0,0 +1,121 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::{InsertModerationLogEntry, ModerationLogEntry};
+
+/// The default number of entries to list.
+const DEFAULT_LIMIT: i64 = 100;
+
+/// Filter used when listing the moderation log.
+#[derive(Debug, Default, Clone, serde::Deserialize)]
+pub struct ModerationLogFilter {
+    /// Only list actions taken against the given user.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Only list actions starting with the given action, like `timeout`.
+    #[serde(default)]
+    pub action: Option<String>,
+    /// Only list actions where the matched rule starts with the given rule.
+    #[serde(default)]
+    pub rule: Option<String>,
+    /// Only list actions taken by the given actor.
+    #[serde(default)]
+    pub actor: Option<String>,
+    /// Only list entries with an id lower than the given one, used to page
+    /// through the log.
+    #[serde(default)]
+    pub before: Option<i32>,
+    /// The maximum number of entries to list.
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+/// A log of all moderation actions taken.
+#[derive(Clone)]
+pub struct ModerationLog {
+    db: db::Database,
+}
+
+impl ModerationLog {
+    /// Open the moderation log database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Record a moderation action.
+    pub async fn record(&self, entry: InsertModerationLogEntry) -> Result<()> {
+        use self::schema::moderation_log::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::moderation_log)
+                    .values(&entry)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List moderation actions in the given channel, newest first.
+    pub async fn list(
+        &self,
+        channel: &str,
+        filter: ModerationLogFilter,
+    ) -> Result<Vec<ModerationLogEntry>> {
+        use self::schema::moderation_log::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let mut query = dsl::moderation_log
+                    .filter(dsl::channel.eq(channel))
+                    .into_boxed();
+
+                if let Some(user) = filter.user {
+                    query = query.filter(dsl::user.eq(db::user_id(&user)));
+                }
+
+                if let Some(action) = filter.action {
+                    query = query.filter(dsl::action.like(format!("{}%", action)));
+                }
+
+                if let Some(rule) = filter.rule {
+                    query = query.filter(dsl::rule.like(format!("{}%", rule)));
+                }
+
+                if let Some(actor) = filter.actor {
+                    query = query.filter(dsl::actor.eq(actor));
+                }
+
+                if let Some(before) = filter.before {
+                    query = query.filter(dsl::id.lt(before));
+                }
+
+                Ok(query
+                    .order(dsl::id.desc())
+                    .limit(filter.limit.unwrap_or(DEFAULT_LIMIT))
+                    .load::<ModerationLogEntry>(c)?)
+            })
+            .await
+    }
+
+    /// Get a single entry.
+    pub async fn get(&self, channel: &str, id: i32) -> Result<Option<ModerationLogEntry>> {
+        use self::schema::moderation_log::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::moderation_log
+                    .filter(dsl::channel.eq(channel).and(dsl::id.eq(id)))
+                    .first::<ModerationLogEntry>(c)
+                    .optional()?)
+            })
+            .await
+    }
+}
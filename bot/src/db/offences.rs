@@ -0,0 +1,78 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use crate::utils;
+use anyhow::Result;
+use chrono::Utc;
+use diesel::prelude::*;
+
+pub use self::models::Offence;
+
+/// Keeps track of how many times users have tripped moderation filters.
+#[derive(Clone)]
+pub struct Offences {
+    db: db::Database,
+}
+
+impl Offences {
+    /// Open the offences database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Record an offence against the given filter and return the number of
+    /// offences the user has committed against it.
+    ///
+    /// Previous offences are forgotten if the last one happened longer ago
+    /// than `decay`.
+    pub async fn record(
+        &self,
+        channel: &str,
+        user: &str,
+        filter: &str,
+        decay: utils::Duration,
+    ) -> Result<u32> {
+        use self::schema::offences::dsl;
+
+        let channel = channel.to_string();
+        let user = user.to_string();
+        let filter = filter.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let now = Utc::now().naive_utc();
+
+                    let offence = dsl::offences
+                        .filter(
+                            dsl::channel
+                                .eq(&channel)
+                                .and(dsl::user.eq(&user))
+                                .and(dsl::filter.eq(&filter)),
+                        )
+                        .first::<Offence>(c)
+                        .optional()?;
+
+                    let count = match offence {
+                        Some(offence) if now - offence.last_at < decay.as_chrono() => offence.count,
+                        _ => 0,
+                    };
+
+                    let offence = Offence {
+                        channel,
+                        user,
+                        filter,
+                        count: count.saturating_add(1),
+                        last_at: now,
+                    };
+
+                    diesel::replace_into(dsl::offences)
+                        .values(&offence)
+                        .execute(c)?;
+
+                    Ok(offence.count as u32)
+                })
+            })
+            .await
+    }
+}
@@ -0,0 +1,117 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+pub use self::models::{Poll, PollVote};
+
+#[derive(Clone)]
+pub struct Polls {
+    db: db::Database,
+}
+
+impl Polls {
+    /// Open the polls database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Start a new poll.
+    pub async fn start(
+        &self,
+        channel: &str,
+        question: &str,
+        options: &str,
+        weighted: bool,
+        closes_at: Option<NaiveDateTime>,
+    ) -> Result<Poll> {
+        use self::schema::polls::dsl;
+
+        let channel = channel.to_string();
+        let question = question.to_string();
+        let options = options.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let poll = models::InsertPoll {
+                    channel: &channel,
+                    question: &question,
+                    options: &options,
+                    weighted,
+                    closes_at,
+                };
+
+                diesel::insert_into(dsl::polls).values(&poll).execute(c)?;
+
+                Ok(dsl::polls
+                    .filter(dsl::channel.eq(&channel))
+                    .order(dsl::id.desc())
+                    .first::<Poll>(c)?)
+            })
+            .await
+    }
+
+    /// List all polls which are still running in the given channel.
+    pub async fn running(&self, channel: &str) -> Result<Vec<Poll>> {
+        use self::schema::polls::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::polls
+                    .filter(dsl::channel.eq(channel).and(dsl::closed.eq(false)))
+                    .order(dsl::id.asc())
+                    .load::<Poll>(c)?)
+            })
+            .await
+    }
+
+    /// Close the given poll.
+    pub async fn close(&self, id: i32) -> Result<()> {
+        use self::schema::polls::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::polls.filter(dsl::id.eq(id)))
+                    .set(dsl::closed.eq(true))
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List all votes cast in the given poll.
+    pub async fn votes(&self, id: i32) -> Result<Vec<PollVote>> {
+        use self::schema::poll_votes::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::poll_votes
+                    .filter(dsl::poll_id.eq(id))
+                    .load::<PollVote>(c)?)
+            })
+            .await
+    }
+
+    /// Record a vote.
+    ///
+    /// Returns `false` if the user has already voted in the poll.
+    pub async fn vote(&self, vote: PollVote) -> Result<bool> {
+        use self::schema::poll_votes::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                // NB: votes are unique per poll and user, so a conflicting
+                // insert means the user has already voted.
+                let inserted = diesel::insert_or_ignore_into(dsl::poll_votes)
+                    .values(&vote)
+                    .execute(c)?;
+
+                Ok(inserted > 0)
+            })
+            .await
+    }
+}
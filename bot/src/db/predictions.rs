@@ -0,0 +1,196 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use std::fmt;
+
+pub use self::models::{Prediction, PredictionStake};
+
+/// The state of a prediction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum PredictionState {
+    /// The prediction is open, or locked and waiting to be resolved.
+    #[serde(rename = "open")]
+    Open,
+    /// The prediction has been resolved and winners paid out.
+    #[serde(rename = "resolved")]
+    Resolved,
+    /// The prediction was cancelled and all stakes refunded.
+    #[serde(rename = "refunded")]
+    Refunded,
+}
+
+impl PredictionState {
+    /// Get the state as it is stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PredictionState::Open => "open",
+            PredictionState::Resolved => "resolved",
+            PredictionState::Refunded => "refunded",
+        }
+    }
+}
+
+impl fmt::Display for PredictionState {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+#[derive(Clone)]
+pub struct Predictions {
+    db: db::Database,
+}
+
+impl Predictions {
+    /// Open the predictions database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Open a new prediction.
+    pub async fn open(
+        &self,
+        channel: &str,
+        question: &str,
+        outcomes: &str,
+        locks_at: NaiveDateTime,
+    ) -> Result<Prediction> {
+        use self::schema::predictions::dsl;
+
+        let channel = channel.to_string();
+        let question = question.to_string();
+        let outcomes = outcomes.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let prediction = models::InsertPrediction {
+                    channel: &channel,
+                    question: &question,
+                    outcomes: &outcomes,
+                    locks_at,
+                };
+
+                diesel::insert_into(dsl::predictions)
+                    .values(&prediction)
+                    .execute(c)?;
+
+                Ok(dsl::predictions
+                    .filter(dsl::channel.eq(&channel))
+                    .order(dsl::id.desc())
+                    .first::<Prediction>(c)?)
+            })
+            .await
+    }
+
+    /// Get the prediction which is currently open in the given channel.
+    pub async fn current(&self, channel: &str) -> Result<Option<Prediction>> {
+        use self::schema::predictions::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::predictions
+                    .filter(
+                        dsl::channel
+                            .eq(channel)
+                            .and(dsl::state.eq(PredictionState::Open.as_str())),
+                    )
+                    .order(dsl::id.desc())
+                    .first::<Prediction>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Stop accepting stakes at the given time.
+    pub async fn lock(&self, id: i32, locks_at: NaiveDateTime) -> Result<()> {
+        use self::schema::predictions::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::predictions.filter(dsl::id.eq(id)))
+                    .set(dsl::locks_at.eq(locks_at))
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Mark the given prediction as finished.
+    pub async fn finish(
+        &self,
+        id: i32,
+        state: PredictionState,
+        winner: Option<&str>,
+    ) -> Result<()> {
+        use self::schema::predictions::dsl;
+
+        let winner = winner.map(String::from);
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::predictions.filter(dsl::id.eq(id)))
+                    .set((dsl::state.eq(state.as_str()), dsl::winner.eq(winner)))
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List all stakes placed in the given prediction.
+    pub async fn stakes(&self, id: i32) -> Result<Vec<PredictionStake>> {
+        use self::schema::prediction_stakes::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::prediction_stakes
+                    .filter(dsl::prediction_id.eq(id))
+                    .load::<PredictionStake>(c)?)
+            })
+            .await
+    }
+
+    /// Mark the stake of a single user as settled, or as unsettled again.
+    ///
+    /// Returns `false` if the stake already was in the given state.
+    pub async fn settle(&self, id: i32, user: &str, settled: bool) -> Result<bool> {
+        use self::schema::prediction_stakes::dsl;
+
+        let user = user.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let updated = diesel::update(
+                    dsl::prediction_stakes.filter(
+                        dsl::prediction_id
+                            .eq(id)
+                            .and(dsl::user.eq(&user))
+                            .and(dsl::settled.eq(!settled)),
+                    ),
+                )
+                .set(dsl::settled.eq(settled))
+                .execute(c)?;
+
+                Ok(updated > 0)
+            })
+            .await
+    }
+
+    /// Insert or replace the stake of a single user.
+    pub async fn stake(&self, stake: PredictionStake) -> Result<()> {
+        use self::schema::prediction_stakes::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::replace_into(dsl::prediction_stakes)
+                    .values(&stake)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+}
@@ -0,0 +1,102 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+
+pub use self::models::QueueEntry;
+
+#[derive(Clone)]
+pub struct Queue {
+    db: db::Database,
+}
+
+impl Queue {
+    /// Open the queue database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all entries in the given channel, in the order they joined.
+    pub async fn entries(&self, channel: &str) -> Result<Vec<QueueEntry>> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::queue_entries
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::id.asc())
+                    .load::<QueueEntry>(c)?)
+            })
+            .await
+    }
+
+    /// Add a user to the queue.
+    pub async fn join(&self, channel: &str, user: &str, priority: bool) -> Result<QueueEntry> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_string();
+        let user = user.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let entry = models::InsertQueueEntry {
+                    channel: &channel,
+                    user: &user,
+                    priority,
+                };
+
+                diesel::insert_into(dsl::queue_entries)
+                    .values(&entry)
+                    .execute(c)?;
+
+                Ok(dsl::queue_entries
+                    .filter(dsl::channel.eq(&channel).and(dsl::user.eq(&user)))
+                    .first::<QueueEntry>(c)?)
+            })
+            .await
+    }
+
+    /// Mark that the given user has skipped ahead in the queue.
+    pub async fn skip(&self, id: i32, skipped_at: NaiveDateTime) -> Result<()> {
+        use self::schema::queue_entries::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::queue_entries.filter(dsl::id.eq(id)))
+                    .set(dsl::skipped_at.eq(skipped_at))
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Remove the given entries from the queue.
+    pub async fn remove(&self, ids: Vec<i32>) -> Result<()> {
+        use self::schema::queue_entries::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(dsl::queue_entries.filter(dsl::id.eq_any(ids))).execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Remove everyone from the queue in the given channel.
+    pub async fn clear(&self, channel: &str) -> Result<()> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(dsl::queue_entries.filter(dsl::channel.eq(channel))).execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+}
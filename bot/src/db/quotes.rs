@@ -0,0 +1,211 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::Quote;
+
+#[derive(Clone)]
+pub struct Quotes {
+    db: db::Database,
+}
+
+impl Quotes {
+    /// Open the quotes database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all quotes in the given channel.
+    pub async fn list(&self, channel: &str) -> Result<Vec<Quote>> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::quotes
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::id.asc())
+                    .load::<Quote>(c)?)
+            })
+            .await
+    }
+
+    /// Get a single quote by id.
+    pub async fn get(&self, channel: &str, id: i32) -> Result<Option<Quote>> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::quotes
+                    .filter(dsl::channel.eq(channel).and(dsl::id.eq(id)))
+                    .first::<Quote>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Get a random quote.
+    pub async fn random(&self, channel: &str) -> Result<Option<Quote>> {
+        use self::schema::quotes::dsl;
+        use rand::Rng as _;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let filter = dsl::quotes.filter(dsl::channel.eq(channel));
+                let count = filter.clone().count().get_result::<i64>(c)?;
+
+                if count == 0 {
+                    return Ok(None);
+                }
+
+                let offset = rand::thread_rng().gen_range(0, count);
+
+                Ok(filter
+                    .order(dsl::id.asc())
+                    .offset(offset)
+                    .first::<Quote>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Search for quotes which contain all of the given terms.
+    pub async fn search(&self, channel: &str, terms: &[String], limit: i64) -> Result<Vec<Quote>> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_string();
+        let terms = terms.to_vec();
+
+        self.db
+            .asyncify(move |c| {
+                let mut query = dsl::quotes.filter(dsl::channel.eq(channel)).into_boxed();
+
+                for term in terms {
+                    query = query.filter(
+                        dsl::quote
+                            .like(format!("%{}%", escape_like(&term)))
+                            .escape('\\'),
+                    );
+                }
+
+                Ok(query.order(dsl::id.asc()).limit(limit).load::<Quote>(c)?)
+            })
+            .await
+    }
+
+    /// Add a quote.
+    pub async fn add(
+        &self,
+        channel: &str,
+        quote: &str,
+        added_by: &str,
+        game: Option<&str>,
+    ) -> Result<Quote> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_string();
+        let quote = quote.to_string();
+        let added_by = added_by.to_string();
+        let game = game.map(String::from);
+
+        self.db
+            .asyncify(move |c| {
+                let insert = models::InsertQuote {
+                    channel: &channel,
+                    quote: &quote,
+                    added_by: &added_by,
+                    game: game.as_deref(),
+                };
+
+                diesel::insert_into(dsl::quotes)
+                    .values(&insert)
+                    .execute(c)?;
+
+                Ok(dsl::quotes
+                    .filter(dsl::channel.eq(&channel))
+                    .order(dsl::id.desc())
+                    .first::<Quote>(c)?)
+            })
+            .await
+    }
+
+    /// Edit the text and game of the given quote.
+    pub async fn edit(
+        &self,
+        channel: &str,
+        id: i32,
+        quote: &str,
+        game: Option<&str>,
+    ) -> Result<bool> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_string();
+        let quote = quote.to_string();
+        let game = game.map(String::from);
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::quotes.filter(dsl::channel.eq(channel).and(dsl::id.eq(id))),
+                )
+                .set((dsl::quote.eq(quote), dsl::game.eq(game)))
+                .execute(c)?;
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Delete the given quote.
+    pub async fn delete(&self, channel: &str, id: i32) -> Result<bool> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::quotes.filter(dsl::channel.eq(channel).and(dsl::id.eq(id))),
+                )
+                .execute(c)?;
+                Ok(count == 1)
+            })
+            .await
+    }
+}
+
+/// Escape the wildcards in a term so that it can be used in a `LIKE`
+/// expression with `\` as the escape character.
+fn escape_like(term: &str) -> String {
+    let mut out = String::with_capacity(term.len());
+
+    for c in term.chars() {
+        match c {
+            '%' | '_' | '\\' => out.push('\\'),
+            _ => (),
+        }
+
+        out.push(c);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape_like;
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!("100\\%", escape_like("100%"));
+        assert_eq!("snake\\_case", escape_like("snake_case"));
+        assert_eq!("back\\\\slash", escape_like("back\\slash"));
+        assert_eq!("plain", escape_like("plain"));
+    }
+}
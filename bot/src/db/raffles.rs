@@ -0,0 +1,166 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::Result;
+use diesel::prelude::*;
+
+pub use self::models::{Raffle, RaffleEntry};
+
+#[derive(Clone)]
+pub struct Raffles {
+    db: db::Database,
+}
+
+impl Raffles {
+    /// Open the raffles database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Get the raffle which is currently running in the given channel, if any.
+    pub async fn current(&self, channel: &str) -> Result<Option<Raffle>> {
+        use self::schema::raffles::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::raffles
+                    .filter(dsl::channel.eq(channel).and(dsl::ended.eq(false)))
+                    .order(dsl::id.desc())
+                    .first::<Raffle>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Open a new raffle, ending any raffle currently running in the channel.
+    pub async fn open(
+        &self,
+        channel: &str,
+        keyword: &str,
+        ticket_cost: i64,
+        max_tickets: i32,
+    ) -> Result<Raffle> {
+        use self::schema::raffles::dsl;
+
+        let channel = channel.to_string();
+        let keyword = keyword.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    diesel::update(dsl::raffles.filter(dsl::channel.eq(&channel)))
+                        .set((dsl::open.eq(false), dsl::ended.eq(true)))
+                        .execute(c)?;
+
+                    let raffle = models::InsertRaffle {
+                        channel: &channel,
+                        keyword: &keyword,
+                        ticket_cost,
+                        max_tickets,
+                    };
+
+                    diesel::insert_into(dsl::raffles)
+                        .values(&raffle)
+                        .execute(c)?;
+
+                    Ok(dsl::raffles
+                        .filter(dsl::channel.eq(&channel))
+                        .order(dsl::id.desc())
+                        .first::<Raffle>(c)?)
+                })
+            })
+            .await
+    }
+
+    /// Stop accepting entries to the given raffle.
+    pub async fn close(&self, id: i32) -> Result<()> {
+        use self::schema::raffles::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::raffles.filter(dsl::id.eq(id)))
+                    .set(dsl::open.eq(false))
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// End the given raffle.
+    pub async fn end(&self, id: i32) -> Result<()> {
+        use self::schema::raffles::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(dsl::raffles.filter(dsl::id.eq(id)))
+                    .set((dsl::open.eq(false), dsl::ended.eq(true)))
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Get the entry of a single user.
+    pub async fn entry(&self, id: i32, user: &str) -> Result<Option<RaffleEntry>> {
+        use self::schema::raffle_entries::dsl;
+
+        let user = user.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::raffle_entries
+                    .filter(dsl::raffle_id.eq(id).and(dsl::user.eq(user)))
+                    .first::<RaffleEntry>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// List all entries to the given raffle.
+    pub async fn entries(&self, id: i32) -> Result<Vec<RaffleEntry>> {
+        use self::schema::raffle_entries::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::raffle_entries
+                    .filter(dsl::raffle_id.eq(id))
+                    .order(dsl::user.asc())
+                    .load::<RaffleEntry>(c)?)
+            })
+            .await
+    }
+
+    /// Insert or update the entry of a user.
+    pub async fn enter(&self, entry: RaffleEntry) -> Result<()> {
+        use self::schema::raffle_entries::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                diesel::replace_into(dsl::raffle_entries)
+                    .values(&entry)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Mark the given user as a winner of the raffle.
+    pub async fn mark_winner(&self, id: i32, user: &str) -> Result<()> {
+        use self::schema::raffle_entries::dsl;
+
+        let user = user.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::update(
+                    dsl::raffle_entries.filter(dsl::raffle_id.eq(id).and(dsl::user.eq(user))),
+                )
+                .set(dsl::won.eq(true))
+                .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+}
@@ -0,0 +1,142 @@
+use crate::auth;
+use crate::utils;
+use anyhow::{bail, Result};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Restrictions on who can invoke a command or an alias, and how often.
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct Restrictions {
+    /// Cooldown shared by everyone in the channel.
+    #[serde(default)]
+    pub cooldown: Option<utils::Duration>,
+    /// Cooldown applied to each user individually.
+    #[serde(default)]
+    pub user_cooldown: Option<utils::Duration>,
+    /// Scope required to invoke.
+    #[serde(default)]
+    pub scope: Option<auth::Scope>,
+    /// When the thing was last invoked, globally and by user.
+    #[serde(skip)]
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    last: Option<Instant>,
+    users: HashMap<String, Instant>,
+}
+
+impl Restrictions {
+    /// Convert database columns into restrictions.
+    pub fn from_db(
+        cooldown: Option<i32>,
+        user_cooldown: Option<i32>,
+        scope: Option<&str>,
+    ) -> Result<Self> {
+        let scope = match scope {
+            Some(scope) => Some(Self::parse_scope(scope)?),
+            None => None,
+        };
+
+        Ok(Self {
+            cooldown: cooldown.map(|s| utils::Duration::seconds(s as u64)),
+            user_cooldown: user_cooldown.map(|s| utils::Duration::seconds(s as u64)),
+            scope,
+            state: Default::default(),
+        })
+    }
+
+    /// Convert restrictions into database columns.
+    pub fn to_db(&self) -> (Option<i32>, Option<i32>, Option<String>) {
+        (
+            self.cooldown.map(|d| d.num_seconds() as i32),
+            self.user_cooldown.map(|d| d.num_seconds() as i32),
+            self.scope.map(|s| s.to_string()),
+        )
+    }
+
+    /// Parse a scope, refusing scopes which don't exist.
+    pub fn parse_scope(scope: &str) -> Result<auth::Scope> {
+        match str::parse::<auth::Scope>(scope)? {
+            auth::Scope::Unknown => bail!("no scope named `{}`", scope),
+            scope => Ok(scope),
+        }
+    }
+
+    /// Test if the cooldowns permit an invocation by the given user, and
+    /// register the invocation if they do.
+    pub fn is_open(&self, user: Option<&str>) -> bool {
+        let now = Instant::now();
+        let mut state = self.state.lock();
+
+        if let (Some(cooldown), Some(last)) = (self.cooldown, state.last) {
+            if now - last < cooldown.as_std() {
+                return false;
+            }
+        }
+
+        if let (Some(cooldown), Some(user)) = (self.user_cooldown, user) {
+            let cooldown = cooldown.as_std();
+            state.users.retain(|_, last| now - *last < cooldown);
+
+            if state.users.contains_key(user) {
+                return false;
+            }
+
+            state.users.insert(user.to_string(), now);
+        }
+
+        state.last = Some(now);
+        true
+    }
+}
+
+impl fmt::Display for Restrictions {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "cooldown = {cooldown}, user cooldown = {user_cooldown}, scope = {scope}",
+            cooldown = Optional(self.cooldown.as_ref()),
+            user_cooldown = Optional(self.user_cooldown.as_ref()),
+            scope = Optional(self.scope.as_ref()),
+        )
+    }
+}
+
+/// Helper to display an optional value.
+struct Optional<'a, T>(Option<&'a T>);
+
+impl<T> fmt::Display for Optional<'_, T>
+where
+    T: fmt::Display,
+{
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(value) => value.fmt(fmt),
+            None => "*none*".fmt(fmt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Restrictions;
+    use crate::utils::Duration;
+
+    #[test]
+    fn test_cooldowns() {
+        let mut restrictions = Restrictions::default();
+        restrictions.user_cooldown = Some(Duration::hours(1));
+
+        assert!(restrictions.is_open(Some("foo")));
+        assert!(!restrictions.is_open(Some("foo")));
+        assert!(restrictions.is_open(Some("bar")));
+
+        restrictions.cooldown = Some(Duration::hours(1));
+        assert!(!restrictions.is_open(Some("baz")));
+    }
+}
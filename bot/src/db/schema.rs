@@ -16,6 +16,11 @@ table! {
         text -> Text,
         group -> Nullable<Text>,
         disabled -> Bool,
+        cooldown -> Nullable<Integer>,
+        user_cooldown -> Nullable<Integer>,
+        scope -> Nullable<Text>,
+        cost -> Nullable<BigInt>,
+        args -> Nullable<Text>,
     }
 }
 
@@ -33,6 +38,7 @@ table! {
     bad_words (word) {
         word -> Text,
         why -> Nullable<Text>,
+        kind -> Nullable<Text>,
     }
 }
 
@@ -63,6 +69,9 @@ table! {
         text -> Text,
         group -> Nullable<Text>,
         disabled -> Bool,
+        cooldown -> Nullable<Integer>,
+        user_cooldown -> Nullable<Integer>,
+        scope -> Nullable<Text>,
     }
 }
 
@@ -106,6 +115,17 @@ table! {
     }
 }
 
+// Grants that have been temporarily given.
+table! {
+    temporary_grants (id) {
+        id -> Integer,
+        scope -> Text,
+        principal -> Text,
+        expires_at -> Timestamp,
+        uses -> Nullable<Integer>,
+    }
+}
+
 table! {
     script_keys (channel, key) {
         channel -> Text,
@@ -113,3 +133,173 @@ table! {
         value -> Binary,
     }
 }
+
+table! {
+    currency_transactions (id) {
+        id -> Integer,
+        channel -> Text,
+        user -> Text,
+        amount -> BigInt,
+        reason -> Text,
+        balance -> BigInt,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    raffles (id) {
+        id -> Integer,
+        channel -> Text,
+        keyword -> Text,
+        ticket_cost -> BigInt,
+        max_tickets -> Integer,
+        open -> Bool,
+        ended -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    raffle_entries (raffle_id, user) {
+        raffle_id -> Integer,
+        user -> Text,
+        tickets -> Integer,
+        subscriber -> Bool,
+        won -> Bool,
+    }
+}
+
+table! {
+    shop_items (channel, name) {
+        channel -> Text,
+        name -> Text,
+        cost -> BigInt,
+        cooldown -> Nullable<Integer>,
+        stock -> Nullable<Integer>,
+        scope -> Nullable<Text>,
+        response -> Nullable<Text>,
+        manual -> Bool,
+        disabled -> Bool,
+    }
+}
+
+table! {
+    shop_redemptions (id) {
+        id -> Integer,
+        channel -> Text,
+        item -> Text,
+        user -> Text,
+        cost -> BigInt,
+        message -> Nullable<Text>,
+        state -> Text,
+        created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    polls (id) {
+        id -> Integer,
+        channel -> Text,
+        question -> Text,
+        options -> Text,
+        weighted -> Bool,
+        closes_at -> Nullable<Timestamp>,
+        closed -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    poll_votes (poll_id, user) {
+        poll_id -> Integer,
+        user -> Text,
+        option -> Text,
+        weight -> BigInt,
+    }
+}
+
+table! {
+    predictions (id) {
+        id -> Integer,
+        channel -> Text,
+        question -> Text,
+        outcomes -> Text,
+        locks_at -> Timestamp,
+        state -> Text,
+        winner -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    prediction_stakes (prediction_id, user) {
+        prediction_id -> Integer,
+        user -> Text,
+        outcome -> Text,
+        amount -> BigInt,
+        settled -> Bool,
+    }
+}
+
+table! {
+    queue_entries (id) {
+        id -> Integer,
+        channel -> Text,
+        user -> Text,
+        priority -> Bool,
+        skipped_at -> Nullable<Timestamp>,
+        joined_at -> Timestamp,
+    }
+}
+
+table! {
+    quotes (id) {
+        id -> Integer,
+        channel -> Text,
+        quote -> Text,
+        added_by -> Text,
+        game -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    counters (channel, name) {
+        channel -> Text,
+        name -> Text,
+        value -> BigInt,
+        scope -> Nullable<Text>,
+    }
+}
+
+table! {
+    offences (channel, user, filter) {
+        channel -> Text,
+        user -> Text,
+        filter -> Text,
+        count -> Integer,
+        last_at -> Timestamp,
+    }
+}
+
+table! {
+    moderation_log (id) {
+        id -> Integer,
+        channel -> Text,
+        action -> Text,
+        user -> Text,
+        message -> Nullable<Text>,
+        rule -> Nullable<Text>,
+        actor -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    heist_stakes (channel, user) {
+        channel -> Text,
+        user -> Text,
+        amount -> BigInt,
+    }
+}
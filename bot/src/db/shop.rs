@@ -0,0 +1,298 @@
+use crate::db;
+use crate::db::models;
+use crate::db::schema;
+use anyhow::{bail, Result};
+use chrono::Utc;
+use diesel::prelude::*;
+use std::fmt;
+
+pub use self::models::{ShopItem, ShopRedemption};
+
+/// The state of a redemption.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum RedemptionState {
+    /// Waiting to be fulfilled by the streamer.
+    #[serde(rename = "pending")]
+    Pending,
+    /// The redemption has been fulfilled.
+    #[serde(rename = "fulfilled")]
+    Fulfilled,
+    /// The redemption was rejected and refunded.
+    #[serde(rename = "rejected")]
+    Rejected,
+}
+
+impl RedemptionState {
+    /// Get the state as it is stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RedemptionState::Pending => "pending",
+            RedemptionState::Fulfilled => "fulfilled",
+            RedemptionState::Rejected => "rejected",
+        }
+    }
+}
+
+impl fmt::Display for RedemptionState {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(fmt)
+    }
+}
+
+#[derive(Clone)]
+pub struct Shop {
+    db: db::Database,
+}
+
+impl Shop {
+    /// Open the shop database.
+    pub async fn load(db: db::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all items in the given channel.
+    pub async fn items(&self, channel: &str) -> Result<Vec<ShopItem>> {
+        use self::schema::shop_items::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::shop_items
+                    .filter(dsl::channel.eq(channel))
+                    .order(dsl::cost.asc())
+                    .load::<ShopItem>(c)?)
+            })
+            .await
+    }
+
+    /// Get a single item.
+    pub async fn item(&self, channel: &str, name: &str) -> Result<Option<ShopItem>> {
+        use self::schema::shop_items::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::shop_items
+                    .filter(dsl::channel.eq(channel).and(dsl::name.eq(name)))
+                    .first::<ShopItem>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Insert or replace the given item.
+    pub async fn edit(&self, mut item: ShopItem) -> Result<()> {
+        use self::schema::shop_items::dsl;
+
+        item.name = item.name.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                diesel::replace_into(dsl::shop_items)
+                    .values(&item)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// Delete the given item.
+    pub async fn delete(&self, channel: &str, name: &str) -> Result<bool> {
+        use self::schema::shop_items::dsl;
+
+        let channel = channel.to_string();
+        let name = name.to_lowercase();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::shop_items.filter(dsl::channel.eq(channel).and(dsl::name.eq(name))),
+                )
+                .execute(c)?;
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Record a redemption of the given item, taking it out of stock.
+    ///
+    /// Fails if the item is out of stock.
+    pub async fn redeem(
+        &self,
+        item: &ShopItem,
+        user: &str,
+        message: Option<&str>,
+    ) -> Result<ShopRedemption> {
+        use self::schema::shop_items::dsl as items;
+        use self::schema::shop_redemptions::dsl;
+
+        let item = item.clone();
+        let user = user.to_string();
+        let message = message.map(String::from);
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let filter = items::shop_items.filter(
+                        items::channel
+                            .eq(&item.channel)
+                            .and(items::name.eq(&item.name)),
+                    );
+
+                    let stock = filter
+                        .clone()
+                        .select(items::stock)
+                        .first::<Option<i32>>(c)?;
+
+                    if let Some(stock) = stock {
+                        if stock <= 0 {
+                            bail!("item is out of stock");
+                        }
+
+                        diesel::update(filter)
+                            .set(items::stock.eq(stock - 1))
+                            .execute(c)?;
+                    }
+
+                    let state = if item.manual {
+                        RedemptionState::Pending
+                    } else {
+                        RedemptionState::Fulfilled
+                    };
+
+                    let redemption = models::InsertShopRedemption {
+                        channel: &item.channel,
+                        item: &item.name,
+                        user: &user,
+                        cost: item.cost,
+                        message: message.as_deref(),
+                        state: state.as_str(),
+                    };
+
+                    diesel::insert_into(dsl::shop_redemptions)
+                        .values(&redemption)
+                        .execute(c)?;
+
+                    Ok(dsl::shop_redemptions
+                        .filter(dsl::channel.eq(&item.channel))
+                        .order(dsl::id.desc())
+                        .first::<ShopRedemption>(c)?)
+                })
+            })
+            .await
+    }
+
+    /// List redemptions in the given channel, optionally filtered by state.
+    pub async fn redemptions(
+        &self,
+        channel: &str,
+        state: Option<RedemptionState>,
+        limit: i64,
+    ) -> Result<Vec<ShopRedemption>> {
+        use self::schema::shop_redemptions::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                let mut query = dsl::shop_redemptions
+                    .filter(dsl::channel.eq(channel))
+                    .into_boxed();
+
+                if let Some(state) = state {
+                    query = query.filter(dsl::state.eq(state.as_str()));
+                }
+
+                Ok(query
+                    .order(dsl::id.desc())
+                    .limit(limit)
+                    .load::<ShopRedemption>(c)?)
+            })
+            .await
+    }
+
+    /// Get the pending redemption with the given id.
+    pub async fn pending(&self, channel: &str, id: i32) -> Result<Option<ShopRedemption>> {
+        use self::schema::shop_redemptions::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::shop_redemptions
+                    .filter(
+                        dsl::channel
+                            .eq(channel)
+                            .and(dsl::id.eq(id))
+                            .and(dsl::state.eq(RedemptionState::Pending.as_str())),
+                    )
+                    .first::<ShopRedemption>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Resolve a pending redemption by fulfilling or rejecting it.
+    ///
+    /// Rejecting a redemption puts the item back in stock.
+    ///
+    /// Returns the resolved redemption, or `None` if there is no pending
+    /// redemption with the given id.
+    pub async fn resolve(
+        &self,
+        channel: &str,
+        id: i32,
+        state: RedemptionState,
+    ) -> Result<Option<ShopRedemption>> {
+        use self::schema::shop_items::dsl as items;
+        use self::schema::shop_redemptions::dsl;
+
+        let channel = channel.to_string();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(|| {
+                    let filter = dsl::shop_redemptions.filter(
+                        dsl::channel
+                            .eq(&channel)
+                            .and(dsl::id.eq(id))
+                            .and(dsl::state.eq(RedemptionState::Pending.as_str())),
+                    );
+
+                    let redemption = match filter.clone().first::<ShopRedemption>(c).optional()? {
+                        Some(redemption) => redemption,
+                        None => return Ok(None),
+                    };
+
+                    let now = Utc::now().naive_utc();
+
+                    diesel::update(filter)
+                        .set((dsl::state.eq(state.as_str()), dsl::resolved_at.eq(now)))
+                        .execute(c)?;
+
+                    if state == RedemptionState::Rejected {
+                        diesel::update(
+                            items::shop_items.filter(
+                                items::channel
+                                    .eq(&channel)
+                                    .and(items::name.eq(&redemption.item))
+                                    .and(items::stock.is_not_null()),
+                            ),
+                        )
+                        .set(items::stock.eq(items::stock + 1))
+                        .execute(c)?;
+                    }
+
+                    Ok(Some(ShopRedemption {
+                        state: state.as_str().to_string(),
+                        resolved_at: Some(now),
+                        ..redemption
+                    }))
+                })
+            })
+            .await
+    }
+}
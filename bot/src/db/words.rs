@@ -1,7 +1,10 @@
 use crate::db;
 use crate::template;
+use crate::utils;
+use anyhow::bail;
 use diesel::prelude::*;
 use std::collections::HashMap;
+use std::fmt;
 use std::sync::Arc;
 use tokio::sync::{RwLock, RwLockReadGuard};
 
@@ -11,35 +14,267 @@ pub fn tokenize(word: &str) -> String {
     inflector::string::singularize::to_singular(&word)
 }
 
+/// Normalize common character substitutions in the given message, like `4`
+/// for `a` or `$` for `s`.
+///
+/// Mentions like `@setbac` are left alone, and so is punctuation like `!` and
+/// `|` at the start or end of a word.
+pub fn normalize(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut rest = message;
+
+    while !rest.is_empty() {
+        let n = rest
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(rest.len());
+        out.push_str(&rest[..n]);
+        rest = &rest[n..];
+
+        let n = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        normalize_word(&mut out, &rest[..n]);
+        rest = &rest[n..];
+    }
+
+    return out;
+
+    fn normalize_word(out: &mut String, word: &str) {
+        let is_mention = word.starts_with('@')
+            && word[1..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+
+        if is_mention {
+            out.extend(word.chars().flat_map(char::to_lowercase));
+            return;
+        }
+
+        // NB: `@`, `$`, and `+` are never trimmed so that things like `@$$`
+        // are still normalized.
+        let is_punctuation = |c: char| c.is_ascii_punctuation() && !matches!(c, '@' | '$' | '+');
+        let body = word.trim_matches(is_punctuation);
+        let start = word.len() - word.trim_start_matches(is_punctuation).len();
+
+        out.push_str(&word[..start]);
+        out.extend(body.chars().flat_map(char::to_lowercase).map(substitute));
+        out.push_str(&word[start + body.len()..]);
+    }
+
+    fn substitute(c: char) -> char {
+        match c {
+            '0' => 'o',
+            '1' | '!' | '|' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' | '+' => 't',
+            '8' => 'b',
+            '9' => 'g',
+            c => c,
+        }
+    }
+}
+
+/// Convert a glob like `bad*` into an anchored regular expression.
+fn glob_to_regex(glob: &str) -> String {
+    let mut out = String::from("^");
+
+    for c in glob.chars() {
+        match c {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            c => out.push_str(&regex::escape(c.encode_utf8(&mut [0u8; 4]))),
+        }
+    }
+
+    out.push('$');
+    out
+}
+
+/// How a bad word is matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordKind {
+    /// Matched against individual words, exactly or by how they sound.
+    Word,
+    /// A regular expression matched against the whole message.
+    Regex,
+    /// A glob, like `bad*`, matched against individual words.
+    Glob,
+}
+
+impl WordKind {
+    /// Convert the database column into a kind.
+    fn from_db(kind: Option<&str>) -> Result<Self, anyhow::Error> {
+        match kind {
+            Some(kind) => str::parse(kind),
+            None => Ok(WordKind::Word),
+        }
+    }
+
+    /// Convert the kind into a database column.
+    fn to_db(self) -> Option<String> {
+        match self {
+            WordKind::Word => None,
+            kind => Some(kind.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for WordKind {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            WordKind::Word => "word".fmt(fmt),
+            WordKind::Regex => "regex".fmt(fmt),
+            WordKind::Glob => "glob".fmt(fmt),
+        }
+    }
+}
+
+impl std::str::FromStr for WordKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "word" => Ok(WordKind::Word),
+            "regex" => Ok(WordKind::Regex),
+            "glob" => Ok(WordKind::Glob),
+            other => bail!("bad word kind `{}`", other),
+        }
+    }
+}
+
+/// How a compiled bad word is matched.
+enum Matcher {
+    /// Matched against individual words, exactly or by how they sound.
+    Word,
+    /// A regular expression matched against the whole message.
+    Regex(regex::Regex),
+    /// A glob matched against individual words.
+    Glob(regex::Regex),
+}
+
+/// A bad word which has been compiled and is ready to be inserted.
+struct Compiled {
+    word: Arc<Word>,
+    matcher: Matcher,
+}
+
+impl Compiled {
+    /// Compile a bad word, failing if its pattern or its template is bad.
+    fn new(word: &str, kind: WordKind, why: Option<&str>) -> Result<Self, anyhow::Error> {
+        let why = why.map(template::Template::compile).transpose()?;
+
+        let (word, matcher) = match kind {
+            WordKind::Word => (tokenize(word), Matcher::Word),
+            WordKind::Regex => {
+                let regex = regex::RegexBuilder::new(word)
+                    .case_insensitive(true)
+                    .build()?;
+
+                (word.to_string(), Matcher::Regex(regex))
+            }
+            WordKind::Glob => {
+                let regex = regex::RegexBuilder::new(&glob_to_regex(word))
+                    .case_insensitive(true)
+                    .build()?;
+
+                (word.to_string(), Matcher::Glob(regex))
+            }
+        };
+
+        Ok(Compiled {
+            word: Arc::new(Word { word, kind, why }),
+            matcher,
+        })
+    }
+}
+
 #[derive(Debug, Default)]
 struct Inner {
     hashed: HashMap<eudex::Hash, Arc<Word>>,
     exact: HashMap<String, Arc<Word>>,
+    regexes: HashMap<String, (regex::Regex, Arc<Word>)>,
+    globs: HashMap<String, (regex::Regex, Arc<Word>)>,
 }
 
 impl Inner {
-    /// Insert a bad word.
-    fn insert(&mut self, word: &str, why: Option<&str>) -> Result<(), anyhow::Error> {
-        let word = Word {
-            word: tokenize(word),
-            why: why.map(template::Template::compile).transpose()?,
-        };
-
-        let word = Arc::new(word);
-        self.hashed
-            .insert(eudex::Hash::new(&word.word), Arc::clone(&word));
-        self.exact.insert(word.word.to_string(), Arc::clone(&word));
-        Ok(())
+    /// Insert a compiled bad word, replacing the given word.
+    fn insert(&mut self, word: &str, compiled: Compiled) {
+        self.remove(word);
+
+        let Compiled { word, matcher } = compiled;
+
+        match matcher {
+            Matcher::Word => {
+                self.hashed
+                    .insert(eudex::Hash::new(&word.word), Arc::clone(&word));
+                self.exact.insert(word.word.to_string(), word);
+            }
+            Matcher::Regex(regex) => {
+                self.regexes.insert(word.word.to_string(), (regex, word));
+            }
+            Matcher::Glob(regex) => {
+                self.globs.insert(word.word.to_string(), (regex, word));
+            }
+        }
     }
 
     /// Insert a bad word.
     fn remove(&mut self, word: &str) {
+        self.regexes.remove(word);
+        self.globs.remove(word);
+
         let word = tokenize(word);
 
         // TODO: there might be hash conflicts. Deal with them.
         self.hashed.remove(&eudex::Hash::new(&word));
         self.exact.remove(&word);
     }
+
+    /// Test the given word, optionally also by how it sounds.
+    fn test(&self, word: &str, phonetic: bool) -> Option<Arc<Word>> {
+        let word = tokenize(word);
+
+        if phonetic {
+            if let Some(w) = self.hashed.get(&eudex::Hash::new(&word)) {
+                return Some(Arc::clone(w));
+            }
+        }
+
+        if let Some(w) = self.exact.get(&word) {
+            return Some(Arc::clone(w));
+        }
+
+        for (regex, w) in self.globs.values() {
+            if regex.is_match(&word) {
+                return Some(Arc::clone(w));
+            }
+        }
+
+        None
+    }
+
+    /// Test the given message, both as it is and with common character
+    /// substitutions normalized.
+    ///
+    /// Words are only matched by how they sound in the message as it is,
+    /// since the two combined are prone to false positives.
+    fn test_message(&self, message: &str) -> Option<Arc<Word>> {
+        let normalized = normalize(message);
+
+        for (message, phonetic) in &[(message, true), (normalized.as_str(), false)] {
+            for word in utils::TrimmedWords::new(message) {
+                if let Some(w) = self.test(word, *phonetic) {
+                    return Some(w);
+                }
+            }
+
+            for (regex, w) in self.regexes.values() {
+                if regex.is_match(message) {
+                    return Some(Arc::clone(w));
+                }
+            }
+        }
+
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -56,35 +291,25 @@ impl Database {
     }
 
     /// Insert or update an existing word.
-    async fn edit(&self, word: &str, why: Option<&str>) -> Result<(), anyhow::Error> {
+    async fn edit(
+        &self,
+        word: &str,
+        kind: WordKind,
+        why: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
         use db::schema::bad_words::dsl;
 
-        let word = word.to_string();
-        let why = why.map(|w| w.to_string());
+        let bad_word = db::models::BadWord {
+            word: word.to_string(),
+            why: why.map(|s| s.to_string()),
+            kind: kind.to_db(),
+        };
 
         self.0
             .asyncify(move |c| {
-                let filter = dsl::bad_words.filter(dsl::word.eq(&word));
-                let b = filter.clone().first::<db::models::BadWord>(c).optional()?;
-
-                match b {
-                    None => {
-                        let bad_word = db::models::BadWord {
-                            word,
-                            why: why.map(|s| s.to_string()),
-                        };
-
-                        diesel::insert_into(dsl::bad_words)
-                            .values(&bad_word)
-                            .execute(c)?;
-                    }
-                    Some(_) => {
-                        diesel::update(filter)
-                            .set(why.map(|w| dsl::why.eq(w)))
-                            .execute(c)?;
-                    }
-                }
-
+                diesel::replace_into(dsl::bad_words)
+                    .values(&bad_word)
+                    .execute(c)?;
                 Ok(())
             })
             .await
@@ -119,7 +344,9 @@ impl Words {
         let mut inner = Inner::default();
 
         for word in db.list().await? {
-            inner.insert(&word.word, word.why.as_deref())?;
+            let kind = WordKind::from_db(word.kind.as_deref())?;
+            let compiled = Compiled::new(&word.word, kind, word.why.as_deref())?;
+            inner.insert(&word.word, compiled);
         }
 
         Ok(Words {
@@ -128,11 +355,18 @@ impl Words {
         })
     }
 
-    /// Insert a word into the bad words list.
-    pub async fn edit(&self, word: &str, why: Option<&str>) -> Result<(), anyhow::Error> {
-        self.db.edit(word, why).await?;
-        let mut inner = self.inner.write().await;
-        inner.insert(word, why)?;
+    /// Insert a word or a pattern into the bad words list.
+    pub async fn edit(
+        &self,
+        word: &str,
+        kind: WordKind,
+        why: Option<&str>,
+    ) -> Result<(), anyhow::Error> {
+        // NB: compile first, so that bad patterns are rejected before they're
+        // stored, and only update the in-memory list once they are.
+        let compiled = Compiled::new(word, kind, why)?;
+        self.db.edit(word, kind, why).await?;
+        self.inner.write().await.insert(word, compiled);
         Ok(())
     }
 
@@ -163,22 +397,75 @@ pub struct Tester<'a> {
 impl Tester<'_> {
     /// Test the given word.
     pub fn test(&self, word: &str) -> Option<Arc<Word>> {
-        let word = tokenize(word);
-
-        if let Some(w) = self.inner.hashed.get(&eudex::Hash::new(&word)) {
-            return Some(Arc::clone(w));
-        }
-
-        if let Some(w) = self.inner.exact.get(&word) {
-            return Some(Arc::clone(w));
-        }
+        self.inner.test(word, true)
+    }
 
-        None
+    /// Test the given message, returning the rule which matched it.
+    pub fn test_message(&self, message: &str) -> Option<Arc<Word>> {
+        self.inner.test_message(message)
     }
 }
 
 #[derive(Debug)]
 pub struct Word {
     pub word: String,
+    pub kind: WordKind,
     pub why: Option<template::Template>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{glob_to_regex, normalize, Compiled, Inner, WordKind};
+    use anyhow::Error;
+
+    fn insert(inner: &mut Inner, word: &str, kind: WordKind) -> Result<(), Error> {
+        inner.insert(word, Compiled::new(word, kind, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!("hello", normalize("H3LL0"));
+        assert_eq!("ass", normalize("@$$"));
+        assert_eq!("shit", normalize("sh!t"));
+        assert_eq!("hey  @s3tbac!", normalize("Hey  @S3tBac!"));
+        assert_eq!("what the hell!?", normalize("what the hell!?"));
+        assert_eq!("!command ||", normalize("!command ||"));
+        assert_eq!("^a\\.b.*$", glob_to_regex("a.b*"));
+    }
+
+    #[test]
+    fn test_message() -> Result<(), Error> {
+        let mut inner = Inner::default();
+        insert(&mut inner, "heck", WordKind::Word)?;
+        insert(&mut inner, "d?rn*", WordKind::Glob)?;
+        insert(&mut inner, "fudge\\s+it", WordKind::Regex)?;
+
+        let test = |inner: &Inner, message: &str| inner.test_message(message).map(|w| w.kind);
+
+        assert_eq!(Some(WordKind::Word), test(&inner, "what the h3ck"));
+        assert_eq!(Some(WordKind::Glob), test(&inner, "oh Darnit!"));
+        assert_eq!(Some(WordKind::Regex), test(&inner, "FUDGE   it all"));
+        assert_eq!(None, test(&inner, "what a nice day"));
+
+        inner.remove("d?rn*");
+        assert_eq!(None, test(&inner, "oh darnit"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_false_positives() -> Result<(), Error> {
+        let mut inner = Inner::default();
+        insert(&mut inner, "ass", WordKind::Word)?;
+        insert(&mut inner, "tit", WordKind::Word)?;
+
+        let test = |inner: &Inner, message: &str| inner.test_message(message).map(|w| w.kind);
+
+        assert_eq!(None, test(&inner, "hey @ss_wizard, want to play?"));
+        assert_eq!(None, test(&inner, "what a great stream!"));
+        assert_eq!(None, test(&inner, "I passed the title screen!!"));
+        assert_eq!(None, test(&inner, "GG, see you all next time!"));
+        assert_eq!(Some(WordKind::Word), test(&inner, "you are an @$$"));
+        Ok(())
+    }
+}
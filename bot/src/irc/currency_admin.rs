@@ -1,11 +1,12 @@
 use crate::auth::Scope;
 use crate::command;
-use crate::currency::{BalanceTransferError, Currency};
+use crate::currency::{BalanceTransferError, Currency, Reason, TopOrder};
 use crate::db;
 use crate::injector::Injector;
 use crate::prelude::*;
 use crate::utils;
 use anyhow::Error;
+use chrono::Utc;
 use std::sync::Arc;
 
 /// Handler for the !admin command.
@@ -120,6 +121,7 @@ impl command::Handler for Handler {
                         &taker,
                         amount,
                         user.is_streamer(),
+                        Reason::Transfer,
                     )
                     .await;
 
@@ -166,7 +168,7 @@ impl command::Handler for Handler {
                 }
 
                 currency
-                    .balance_add(ctx.user.channel(), &boosted_user, amount)
+                    .balance_add(ctx.user.channel(), &boosted_user, amount, Reason::Admin)
                     .await?;
 
                 if amount >= 0 {
@@ -193,7 +195,7 @@ impl command::Handler for Handler {
                 let amount: i64 = ctx.next_parse("<amount>")?;
 
                 currency
-                    .add_channel_all(ctx.user.channel(), amount, 0)
+                    .add_channel_all(ctx.user.channel(), amount, 0, Reason::Windfall)
                     .await?;
 
                 if amount >= 0 {
@@ -212,10 +214,108 @@ impl command::Handler for Handler {
                     .await;
                 }
             }
+            Some("top") => {
+                let limit = ctx
+                    .next_parse_optional::<i64>()?
+                    .unwrap_or(5)
+                    .min(10)
+                    .max(1);
+                let balances = currency.top(ctx.channel(), TopOrder::Amount, limit).await?;
+
+                let entries = balances
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, b)| format!("#{} {} ({})", i + 1, b.user, b.amount))
+                    .collect::<Vec<_>>();
+
+                match utils::human_list(&entries) {
+                    Some(list) => respond!(ctx, "Top {}: {}", currency.name, list),
+                    None => respond!(ctx, "No one has any {} yet!", currency.name),
+                }
+            }
+            Some("watchtime") => {
+                if ctx.next().as_deref() != Some("top") {
+                    respond_bail!("Expected: top [n]");
+                }
+
+                if !currency.has_watch_time() {
+                    respond!(ctx, "Watch time is not tracked for {}", currency.name);
+                    return Ok(());
+                }
+
+                let limit = ctx
+                    .next_parse_optional::<i64>()?
+                    .unwrap_or(5)
+                    .min(10)
+                    .max(1);
+                let balances = currency
+                    .top(ctx.channel(), TopOrder::WatchTime, limit)
+                    .await?;
+
+                let entries = balances
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, b)| {
+                        let watch_time = utils::compact_duration(std::time::Duration::from_secs(
+                            b.watch_time.max(0) as u64,
+                        ));
+
+                        format!("#{} {} ({})", i + 1, b.user, watch_time)
+                    })
+                    .collect::<Vec<_>>();
+
+                match utils::human_list(&entries) {
+                    Some(list) => respond!(ctx, "Top watch time: {}", list),
+                    None => respond!(ctx, "No one has watched the stream yet!"),
+                }
+            }
+            Some("history") => {
+                ctx.check_scope(Scope::CurrencyShow).await?;
+                let to_show = db::user_id(&ctx.next_str("<user>")?);
+
+                let transactions = match currency
+                    .transactions(ctx.channel(), Some(&to_show), 0, 5)
+                    .await
+                {
+                    Ok(transactions) => transactions,
+                    Err(e) => respond_bail!("Can't show history for {}: {}", to_show, e),
+                };
+
+                if transactions.is_empty() {
+                    respond!(ctx, "No transactions for {user}.", user = to_show);
+                    return Ok(());
+                }
+
+                let now = Utc::now().naive_utc();
+                let mut entries = Vec::new();
+
+                for t in transactions {
+                    let ago = now.signed_duration_since(t.created_at);
+                    let ago = utils::compact_duration(ago.to_std().unwrap_or_default());
+
+                    entries.push(format!(
+                        "{amount:+} {reason} => {balance} ({ago} ago)",
+                        amount = t.amount,
+                        reason = t.reason,
+                        balance = t.balance,
+                        ago = ago,
+                    ));
+                }
+
+                respond!(
+                    ctx,
+                    "Latest {currency} for {user}: {entries}",
+                    currency = currency.name,
+                    user = to_show,
+                    entries = entries.join(", "),
+                );
+            }
             Some(..) => {
                 let mut alts = Vec::new();
 
                 alts.push("give");
+                alts.push("top");
+                alts.push("watchtime top");
 
                 if ctx.user.has_scope(Scope::CurrencyBoost).await {
                     alts.push("boost");
@@ -231,8 +331,10 @@ impl command::Handler for Handler {
 
                 if ctx.user.has_scope(Scope::CurrencyShow).await {
                     alts.push("show");
+                    alts.push("history");
                 } else {
                     alts.push("show 🛇");
+                    alts.push("history 🛇");
                 }
 
                 respond!(ctx, "Expected: {alts}", alts = alts.join(", "));
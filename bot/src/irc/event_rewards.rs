@@ -0,0 +1,99 @@
+use crate::bus;
+use crate::currency::{Currency, Reason};
+use crate::irc::{SubPlan, UserNotice};
+use crate::prelude::*;
+use anyhow::Result;
+
+/// Currency rewards handed out in response to chat events.
+pub(crate) struct EventRewards {
+    sub_prime: settings::Var<i64>,
+    sub_tier1: settings::Var<i64>,
+    sub_tier2: settings::Var<i64>,
+    sub_tier3: settings::Var<i64>,
+    gifted_sub: settings::Var<i64>,
+    bits: settings::Var<i64>,
+    raid: settings::Var<i64>,
+}
+
+/// The outcome of rewarding an event.
+pub(crate) enum Rewarded {
+    /// Nothing was rewarded.
+    None,
+    /// A single user was rewarded.
+    User,
+    /// Everyone in chat was rewarded with the given amount due to a raid.
+    Raid { amount: i64, count: usize },
+}
+
+impl EventRewards {
+    /// Set up event rewards from the given settings.
+    pub(crate) async fn new(settings: &settings::Settings) -> Result<Self> {
+        let settings = settings.scoped("currency/rewards");
+
+        Ok(Self {
+            sub_prime: settings.var("sub/prime", 0).await?,
+            sub_tier1: settings.var("sub/tier1", 0).await?,
+            sub_tier2: settings.var("sub/tier2", 0).await?,
+            sub_tier3: settings.var("sub/tier3", 0).await?,
+            gifted_sub: settings.var("gifted-sub", 0).await?,
+            bits: settings.var("bits", 0).await?,
+            raid: settings.var("raid", 0).await?,
+        })
+    }
+
+    /// Reward the given event.
+    pub(crate) async fn reward(
+        &self,
+        currency: &Currency,
+        channel: &str,
+        event: &bus::Global,
+    ) -> Result<Rewarded> {
+        let (user, amount) = match event {
+            bus::Global::UserNotice { notice, .. } => match notice {
+                UserNotice::Sub { user, plan, .. } | UserNotice::Resub { user, plan, .. } => {
+                    (user, self.sub_amount(*plan).await)
+                }
+                // NB: a community gift is followed by one `subgift` notice per
+                // recipient, so we only reward those to avoid paying twice.
+                UserNotice::SubGift { user, .. } => (user, self.gifted_sub.load().await),
+                UserNotice::Raid { .. } => {
+                    let amount = self.raid.load().await;
+
+                    if amount <= 0 {
+                        return Ok(Rewarded::None);
+                    }
+
+                    let count = currency
+                        .add_channel_all(channel, amount, 0, Reason::Event)
+                        .await?;
+                    return Ok(Rewarded::Raid { amount, count });
+                }
+                _ => return Ok(Rewarded::None),
+            },
+            bus::Global::Cheer { user, bits, .. } => {
+                let amount = (*bits as i64 / 100) * self.bits.load().await;
+                (user, amount)
+            }
+            _ => return Ok(Rewarded::None),
+        };
+
+        if amount <= 0 {
+            return Ok(Rewarded::None);
+        }
+
+        currency
+            .balance_add(channel, user, amount, Reason::Event)
+            .await?;
+        Ok(Rewarded::User)
+    }
+
+    /// Get the reward for a subscription of the given plan.
+    async fn sub_amount(&self, plan: SubPlan) -> i64 {
+        match plan {
+            SubPlan::Prime => self.sub_prime.load().await,
+            SubPlan::Tier1 => self.sub_tier1.load().await,
+            SubPlan::Tier2 => self.sub_tier2.load().await,
+            SubPlan::Tier3 => self.sub_tier3.load().await,
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use crate::api::{self, twitch};
 use crate::auth::{Auth, Role, Scope};
-use crate::bus;
+use crate::bus::{self, Message as _};
 use crate::command;
-use crate::currency::CurrencyBuilder;
+use crate::currency::{BalanceTransferError, Currency, CurrencyBuilder, Reason, COMMAND_COSTS};
 use crate::db;
 use crate::idle;
 use crate::injector::{self, Injector, Key};
@@ -13,6 +13,7 @@ use crate::prelude::*;
 use crate::script;
 use crate::stream_info;
 use crate::task;
+use crate::template::{self, Template};
 use crate::utils::{self, Cooldown, Duration};
 use anyhow::{anyhow, bail, Context as _, Error, Result};
 use irc::client::{self, Client};
@@ -21,7 +22,7 @@ use irc::proto::message::{Message, Tag};
 use leaky_bucket::LeakyBuckets;
 use notify::{RecommendedWatcher, Watcher};
 use parking_lot::RwLock;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::mem;
 use std::path::PathBuf;
@@ -33,14 +34,24 @@ use tracing_futures::Instrument as _;
 
 // re-exports
 pub use self::sender::Sender;
+pub use self::user_notice::{SubPlan, UserNotice};
 
 mod chat_log;
 mod currency_admin;
+mod event_rewards;
+mod moderation;
 mod sender;
+mod spam;
+mod user_notice;
 
 const SERVER: &str = "irc.chat.twitch.tv";
 const TWITCH_TAGS_CAP: &str = "twitch.tv/tags";
 const TWITCH_COMMANDS_CAP: &str = "twitch.tv/commands";
+const DEFAULT_INSUFFICIENT_BALANCE: &str =
+    "{{name}} -> You need {{cost}} {{currency}} to use `{{command}}`, but you only have {{balance}}";
+const DEFAULT_MODERATION: &str = "{{name}} -> {{reason}} ({{action}})";
+const DEFAULT_BAD_WORDS_REASON: &str = "Please watch your language";
+const DEFAULT_URL_WHITELIST_REASON: &str = "Links to that site aren't allowed in chat";
 
 struct TwitchSetup {
     streamer_stream: injector::Stream<oauth2::SyncToken>,
@@ -166,6 +177,7 @@ pub struct Irc {
     pub db: db::Database,
     pub bad_words: db::Words,
     pub global_bus: Arc<bus::Bus<bus::Global>>,
+    pub queue_bus: Arc<bus::Bus<bus::Queue>>,
     pub command_bus: Arc<bus::Bus<bus::Command>>,
     pub modules: Vec<Box<dyn module::Module>>,
     pub restart: utils::Restart,
@@ -184,6 +196,7 @@ impl Irc {
             db,
             bad_words,
             global_bus,
+            queue_bus,
             command_bus,
             modules,
             restart,
@@ -217,6 +230,11 @@ impl Irc {
             bot_user: None,
         };
 
+        let (mut extra_channels_stream, mut extra_channels) = settings
+            .stream::<Vec<String>>("extra-channels")
+            .or_default()
+            .await?;
+
         'outer: loop {
             let (bot, bot_twitch, streamer, streamer_twitch) = twitch_setup.setup().await?;
 
@@ -226,376 +244,651 @@ impl Irc {
             log::trace!("Streamer: {:?}", streamer);
             log::trace!("Bot: {:?}", bot);
 
-            let chat_channel = format!("#{}", channel.name);
-            *global_channel.write().await = Some(chat_channel.clone());
+            *global_channel.write().await = Some(format!("#{}", channel.name));
 
-            let access_token = bot_twitch.token.read().await?.access_token().to_string();
+            let mut targets = vec![Target {
+                channel,
+                streamer,
+                streamer_twitch,
+                settings: settings.clone(),
+                primary: true,
+            }];
+
+            for name in &extra_channels {
+                match Target::extra(&bot_twitch, &settings, name).await {
+                    Ok(Some(target)) => targets.push(target),
+                    Ok(None) => log::warn!("Not joining channel `{}`: no such user", name),
+                    Err(e) => log_error!(e, "Failed to look up channel `{}`", name),
+                }
+            }
 
-            let irc_client_config = client::data::config::Config {
-                nickname: Some(bot.name.to_string()),
-                channels: vec![chat_channel.clone()],
-                password: Some(format!("oauth:{}", access_token)),
-                server: Some(String::from(SERVER)),
-                port: Some(6697),
-                use_tls: Some(true),
-                ..client::data::config::Config::default()
-            };
+            let mut leave = Vec::new();
+            let mut channels = futures::stream::FuturesUnordered::new();
 
-            let mut client = Client::from_config(irc_client_config).await?;
-            client.identify()?;
+            for target in targets {
+                let (leave_tx, leave_rx) = oneshot::channel();
+                leave.push(leave_tx);
 
-            let chat_settings = settings.scoped("chat");
+                let name = target.channel.name.clone();
 
-            let url_whitelist_enabled = chat_settings.var("url-whitelist/enabled", true).await?;
-            let bad_words_enabled = chat_settings.var("bad-words/enabled", false).await?;
-            let sender_ty = chat_settings.var("sender-type", sender::Type::Chat).await?;
-            let threshold = chat_settings.var("idle-detection/threshold", 5).await?;
-            let idle = idle::Idle::new(threshold);
+                let channel_loop = ChannelLoop {
+                    db: db.clone(),
+                    bad_words: bad_words.clone(),
+                    global_bus: global_bus.clone(),
+                    queue_bus: queue_bus.clone(),
+                    command_bus: command_bus.clone(),
+                    modules: &modules,
+                    restart: restart.clone(),
+                    auth: auth.clone(),
+                    injector: injector.clone(),
+                    stream_state_tx: stream_state_tx.clone(),
+                    message_log: message_log.clone(),
+                    script_dirs: script_dirs.clone(),
+                    bot: bot.clone(),
+                    bot_twitch: bot_twitch.clone(),
+                    root_settings: settings.clone(),
+                    target,
+                    leave_rx,
+                };
 
-            let nightbot = injector.var::<api::NightBot>().await?;
+                channels.push(
+                    channel_loop
+                        .run()
+                        .instrument(trace_span!(target: "futures", "channel", name = name.as_str()))
+                        .boxed(),
+                );
+            }
 
-            let mut buckets = LeakyBuckets::new();
+            loop {
+                futures::select! {
+                    exit = channels.select_next_some() => {
+                        match exit? {
+                            Exit::Shutdown => break 'outer,
+                            Exit::Restart => {
+                                tokio::time::delay_for(time::Duration::from_secs(5)).await;
+                                continue 'outer;
+                            }
+                            Exit::Left => {
+                                if channels.is_empty() {
+                                    continue 'outer;
+                                }
+                            }
+                        }
+                    }
+                    update = twitch_setup.streamer_stream.select_next_some() => {
+                        if twitch_setup.update_streamer(update).await? {
+                            leave_all(&mut leave);
+                        }
+                    },
+                    update = twitch_setup.bot_stream.select_next_some() => {
+                        if twitch_setup.update_bot(update).await? {
+                            leave_all(&mut leave);
+                        }
+                    },
+                    update = extra_channels_stream.select_next_some() => {
+                        extra_channels = update;
+                        leave_all(&mut leave);
+                    }
+                }
+            }
+        }
 
-            let sender = Sender::new(
-                sender_ty,
-                chat_channel.clone(),
-                client.sender(),
-                nightbot.clone(),
-                &buckets,
-            )?;
+        Ok(())
+    }
+}
 
-            let mut futures = futures::stream::FuturesUnordered::new();
+/// Signal all joined channels to leave.
+fn leave_all(leave: &mut Vec<oneshot::Sender<()>>) {
+    for tx in leave.drain(..) {
+        let _ = tx.send(());
+    }
+}
 
-            let coordinate = buckets.coordinate()?;
+/// How a channel loop exited.
+enum Exit {
+    /// The channel was asked to leave, like when a token changed.
+    Left,
+    /// A component errored and the bot should rejoin.
+    Restart,
+    /// A component exited and the bot should shut down.
+    Shutdown,
+}
 
-            let future = async move {
-                coordinate.await?;
-                Ok(())
-            };
+/// A channel to join.
+struct Target {
+    /// The channel to join.
+    channel: Arc<twitch::Channel>,
+    /// The owner of the channel.
+    streamer: Arc<twitch::User>,
+    /// Client for the owner of the channel. For additional channels this is
+    /// the bot.
+    streamer_twitch: api::Twitch,
+    /// Settings of the channel.
+    settings: settings::Settings,
+    /// If this is the channel of the streamer account.
+    primary: bool,
+}
 
-            futures.push(
-                future
-                    .instrument(trace_span!(target: "futures", "buckets-coordinator",))
-                    .boxed(),
-            );
+impl Target {
+    /// Look up an additional channel by name.
+    async fn extra(
+        bot_twitch: &api::Twitch,
+        settings: &settings::Settings,
+        name: &str,
+    ) -> Result<Option<Self>> {
+        let name = name.trim_start_matches('#').to_lowercase();
 
-            let stream_info = {
-                let (stream_info, mut stream_state_rx, future) =
-                    stream_info::setup(streamer.clone(), streamer_twitch.clone());
+        let user = match bot_twitch.user_by_login(&name).await? {
+            Some(user) => user,
+            None => return Ok(None),
+        };
 
-                let mut stream_state_tx = stream_state_tx.clone();
+        let channel = bot_twitch.channel_by_id(&user.id).await?;
+        let streamer = twitch::User::from_channel(&channel);
 
-                let forward = async move {
-                    loop {
-                        let m = stream_state_rx.select_next_some().await;
-                        stream_state_tx
-                            .send(m)
-                            .await
-                            .map_err(|_| anyhow!("failed to send"))?;
-                    }
-                };
+        Ok(Some(Target {
+            channel: Arc::new(channel),
+            streamer: Arc::new(streamer),
+            streamer_twitch: bot_twitch.clone(),
+            settings: settings.channel(&name),
+            primary: false,
+        }))
+    }
+}
 
-                futures.push(
-                    forward
-                        .instrument(trace_span!(target: "futures", "stream-info-forward",))
-                        .boxed(),
-                );
-                futures.push(
-                    future
-                        .instrument(trace_span!(target: "futures", "stream-info-refresh",))
-                        .boxed(),
-                );
+/// The chat loop of a single channel, with its own connection, handlers and
+/// stream information.
+struct ChannelLoop<'a> {
+    db: db::Database,
+    bad_words: db::Words,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+    queue_bus: Arc<bus::Bus<bus::Queue>>,
+    command_bus: Arc<bus::Bus<bus::Command>>,
+    modules: &'a [Box<dyn module::Module>],
+    restart: utils::Restart,
+    auth: Auth,
+    injector: Injector,
+    stream_state_tx: mpsc::Sender<stream_info::StreamState>,
+    message_log: MessageLog,
+    script_dirs: Vec<PathBuf>,
+    bot: Arc<twitch::User>,
+    bot_twitch: api::Twitch,
+    /// Settings which are shared by all channels.
+    root_settings: settings::Settings,
+    target: Target,
+    leave_rx: oneshot::Receiver<()>,
+}
 
-                stream_info
+impl ChannelLoop<'_> {
+    async fn run(self) -> Result<Exit> {
+        let ChannelLoop {
+            db,
+            bad_words,
+            global_bus,
+            queue_bus,
+            command_bus,
+            modules,
+            restart,
+            auth,
+            injector,
+            stream_state_tx,
+            message_log,
+            script_dirs,
+            bot,
+            bot_twitch,
+            root_settings,
+            target,
+            leave_rx,
+        } = self;
+
+        let Target {
+            channel,
+            streamer,
+            streamer_twitch,
+            settings,
+            primary,
+        } = target;
+
+        let mut leave_rx = leave_rx.fuse();
+
+        let chat_channel = format!("#{}", channel.name);
+
+        let access_token = bot_twitch.token.read().await?.access_token().to_string();
+
+        let irc_client_config = client::data::config::Config {
+            nickname: Some(bot.name.to_string()),
+            channels: vec![chat_channel.clone()],
+            password: Some(format!("oauth:{}", access_token)),
+            server: Some(String::from(SERVER)),
+            port: Some(6697),
+            use_tls: Some(true),
+            ..client::data::config::Config::default()
+        };
+
+        let mut client = Client::from_config(irc_client_config).await?;
+        client.identify()?;
+
+        let chat_settings = settings.scoped("chat");
+
+        let url_whitelist_enabled = chat_settings.var("url-whitelist/enabled", true).await?;
+        let bad_words_enabled = chat_settings.var("bad-words/enabled", false).await?;
+        let bad_words_punishments = chat_settings
+            .var("bad-words/punishments", moderation::Punishments::default())
+            .await?;
+        let url_whitelist_punishments = chat_settings
+            .var(
+                "url-whitelist/punishments",
+                moderation::Punishments::default(),
+            )
+            .await?;
+        let spam = spam::Filters::load(&chat_settings.scoped("spam")).await?;
+        let moderation_decay = chat_settings
+            .var("moderation/decay", Duration::hours(1))
+            .await?;
+        let moderation_template = chat_settings
+            .var(
+                "moderation/template",
+                Template::compile(DEFAULT_MODERATION)?,
+            )
+            .await?;
+        let sender_ty = chat_settings.var("sender-type", sender::Type::Chat).await?;
+        let threshold = chat_settings.var("idle-detection/threshold", 5).await?;
+        let insufficient_balance = settings
+            .var(
+                "command/insufficient-balance",
+                Template::compile(DEFAULT_INSUFFICIENT_BALANCE)?,
+            )
+            .await?;
+        let idle = idle::Idle::new(threshold);
+
+        let nightbot = injector.var::<api::NightBot>().await?;
+
+        let mut buckets = LeakyBuckets::new();
+
+        let sender = Sender::new(
+            sender_ty,
+            chat_channel.clone(),
+            client.sender(),
+            nightbot.clone(),
+            &buckets,
+        )?;
+
+        let mut futures = futures::stream::FuturesUnordered::new();
+
+        let coordinate = buckets.coordinate()?;
+
+        let future = async move {
+            coordinate.await?;
+            Ok(())
+        };
+
+        futures.push(
+            future
+                .instrument(trace_span!(target: "futures", "buckets-coordinator",))
+                .boxed(),
+        );
+
+        let stream_info = {
+            let (stream_info, mut stream_state_rx, future) =
+                stream_info::setup(streamer.clone(), streamer_twitch.clone());
+
+            let mut stream_state_tx = stream_state_tx.clone();
+
+            // NB: only the channel of the streamer notifies about after
+            // streams, other channels just drain their updates.
+            let forward = async move {
+                loop {
+                    let m = stream_state_rx.select_next_some().await;
+
+                    if !primary {
+                        continue;
+                    }
+
+                    stream_state_tx
+                        .send(m)
+                        .await
+                        .map_err(|_| anyhow!("failed to send"))?;
+                }
             };
 
             futures.push(
-                refresh_mods_future(sender.clone())
-                    .instrument(trace_span!(target: "futures", "refresh-mods",))
+                forward
+                    .instrument(trace_span!(target: "futures", "stream-info-forward",))
+                    .boxed(),
+            );
+            futures.push(
+                future
+                    .instrument(trace_span!(target: "futures", "stream-info-refresh",))
                     .boxed(),
             );
 
-            let mut handlers = module::Handlers::default();
+            stream_info
+        };
 
-            let scripts = script::load_dir(channel.name.clone(), db.clone(), &script_dirs).await?;
+        // NB: makes information on the stream of the streamer available to
+        // components outside of chat, like the song file.
+        if primary {
+            injector.update(stream_info.clone()).await;
+        }
 
-            let (scripts_watch_tx, scripts_watch_rx) = sync::mpsc::unbounded_channel();
-            let mut scripts_watch_rx = scripts_watch_rx.fuse();
+        futures.push(
+            refresh_mods_future(sender.clone())
+                .instrument(trace_span!(target: "futures", "refresh-mods",))
+                .boxed(),
+        );
 
-            let _watcher = if !script_dirs.is_empty() {
-                let mut watcher: RecommendedWatcher = Watcher::new_immediate(move |e| {
-                    let _ = scripts_watch_tx.send(e);
-                })?;
+        let mut handlers = module::Handlers::default();
 
-                for d in &script_dirs {
-                    if d.is_dir() {
-                        watcher.watch(d, notify::RecursiveMode::Recursive)?;
-                    }
-                }
+        let scripts = script::load_dir(channel.name.clone(), db.clone(), &script_dirs).await?;
 
-                Some(watcher)
-            } else {
-                None
-            };
+        let (scripts_watch_tx, scripts_watch_rx) = sync::mpsc::unbounded_channel();
+        let mut scripts_watch_rx = scripts_watch_rx.fuse();
 
-            for module in modules.iter() {
-                if log::log_enabled!(log::Level::Trace) {
-                    log::trace!("initializing module: {}", module.ty());
+        let _watcher = if !script_dirs.is_empty() {
+            let mut watcher: RecommendedWatcher = Watcher::new_immediate(move |e| {
+                let _ = scripts_watch_tx.send(e);
+            })?;
+
+            for d in &script_dirs {
+                if d.is_dir() {
+                    watcher.watch(d, notify::RecursiveMode::Recursive)?;
                 }
+            }
 
-                let result = module
-                    .hook(module::HookContext {
-                        handlers: &mut handlers,
-                        futures: &mut futures,
-                        stream_info: &stream_info,
-                        idle: &idle,
-                        twitch: &bot_twitch,
-                        streamer_twitch: &streamer_twitch,
-                        sender: &sender,
-                        settings: &settings,
-                        injector: &injector,
-                        auth: &auth,
-                    })
-                    .await;
+            Some(watcher)
+        } else {
+            None
+        };
 
-                result.with_context(|| anyhow!("failed to initialize module: {}", module.ty()))?;
+        let mut message_hooks = command::MessageHooks::default();
+
+        for module in modules.iter() {
+            if !primary && !module.extra_channels() {
+                continue;
             }
 
-            let currency_handler = currency_admin::setup(&injector).await?;
+            if log::log_enabled!(log::Level::Trace) {
+                log::trace!("initializing module: {}", module.ty());
+            }
 
-            let future = currency_loop(
-                streamer_twitch.clone(),
-                channel.clone(),
-                sender.clone(),
-                idle.clone(),
-                injector.clone(),
-                chat_settings.clone(),
-                settings.clone(),
-            )
-            .await?;
+            let result = module
+                .hook(module::HookContext {
+                    handlers: &mut handlers,
+                    message_hooks: &mut message_hooks,
+                    futures: &mut futures,
+                    stream_info: &stream_info,
+                    idle: &idle,
+                    twitch: &bot_twitch,
+                    streamer_twitch: &streamer_twitch,
+                    sender: &sender,
+                    settings: &settings,
+                    injector: &injector,
+                    auth: &auth,
+                    global_bus: &global_bus,
+                    queue_bus: &queue_bus,
+                })
+                .await;
+
+            result.with_context(|| anyhow!("failed to initialize module: {}", module.ty()))?;
+        }
 
-            futures.push(
-                future
-                    .instrument(trace_span!(target: "futures", "currency-loop",))
-                    .boxed(),
-            );
+        let currency_handler = currency_admin::setup(&injector).await?;
+
+        let active_chatters = Arc::new(RwLock::new(HashSet::new()));
+
+        let future = CurrencyLoop {
+            twitch: streamer_twitch.clone(),
+            channel: channel.clone(),
+            sender: sender.clone(),
+            idle: idle.clone(),
+            injector: injector.clone(),
+            chat_settings: chat_settings.clone(),
+            settings: settings.clone(),
+            currency_settings: root_settings.clone(),
+            global_bus: global_bus.clone(),
+            stream_info: stream_info.clone(),
+            active_chatters: active_chatters.clone(),
+            bot: bot.name.to_string(),
+            primary,
+        }
+        .setup()
+        .await?;
 
-            let (mut whitelisted_hosts_stream, whitelisted_hosts) = chat_settings
-                .stream("whitelisted-hosts")
-                .or_default()
-                .await?;
+        futures.push(
+            future
+                .instrument(trace_span!(target: "futures", "currency-loop",))
+                .boxed(),
+        );
 
-            let (mut moderator_cooldown_stream, moderator_cooldown) = chat_settings
-                .stream("moderator-cooldown")
-                .optional()
-                .await?;
+        let (mut whitelisted_hosts_stream, whitelisted_hosts) = chat_settings
+            .stream("whitelisted-hosts")
+            .or_default()
+            .await?;
 
-            let (mut api_url_stream, api_url) =
-                settings.stream("remote/api-url").optional().await?;
+        let (mut moderator_cooldown_stream, moderator_cooldown) = chat_settings
+            .stream("moderator-cooldown")
+            .optional()
+            .await?;
 
-            let join_message = chat_settings.get::<String>("join-message").await?;
+        let (mut api_url_stream, api_url) = settings.stream("remote/api-url").optional().await?;
 
-            let leave_message = chat_settings
-                .get::<String>("leave-message")
-                .await?
-                .unwrap_or_else(|| String::from("Leaving chat... VoHiYo"));
+        let join_message = chat_settings.get::<String>("join-message").await?;
 
-            let mut chat_log_builder = chat_log::Builder::new(
-                bot_twitch.clone(),
-                &injector,
-                message_log.clone(),
-                settings.scoped("chat-log"),
-            )
-            .await?;
+        let leave_message = chat_settings
+            .get::<String>("leave-message")
+            .await?
+            .unwrap_or_else(|| String::from("Leaving chat... VoHiYo"));
 
-            let (mut commands_stream, commands) = injector.stream().await;
-            let (mut aliases_stream, aliases) = injector.stream().await;
+        let mut chat_log_builder = chat_log::Builder::new(
+            bot_twitch.clone(),
+            &injector,
+            message_log.clone(),
+            settings.scoped("chat-log"),
+        )
+        .await?;
 
-            let mut pong_timeout = None;
+        let (mut commands_stream, commands) = injector.stream().await;
+        let (mut aliases_stream, aliases) = injector.stream().await;
 
-            let mut handler = Handler {
-                streamer,
+        let mut pong_timeout = None;
+
+        let mut handler = Handler {
+            streamer,
+            sender: sender.clone(),
+            moderators: Default::default(),
+            vips: Default::default(),
+            whitelisted_hosts,
+            commands,
+            counters: injector.var().await?,
+            currency: injector.var().await?,
+            insufficient_balance,
+            bad_words: &bad_words,
+            global_bus: &global_bus,
+            aliases,
+            api_url: Arc::new(api_url),
+            moderator_cooldown,
+            handlers,
+            scripts,
+            idle: &idle,
+            pong_timeout: &mut pong_timeout,
+            token: &bot_twitch.token,
+            handler_shutdown: false,
+            stream_info: &stream_info,
+            auth: &auth,
+            currency_handler,
+            active_chatters,
+            url_whitelist_enabled,
+            bad_words_enabled,
+            bad_words_punishments,
+            url_whitelist_punishments,
+            spam,
+            offences: injector.var().await?,
+            moderation_decay,
+            moderation_template,
+            moderation_log: injector.var().await?,
+            moderated: Default::default(),
+            chat_log: chat_log_builder.build()?,
+            channel,
+            context_inner: Arc::new(command::ContextInner {
                 sender: sender.clone(),
-                moderators: Default::default(),
-                vips: Default::default(),
-                whitelisted_hosts,
-                commands,
-                bad_words: &bad_words,
-                global_bus: &global_bus,
-                aliases,
-                api_url: Arc::new(api_url),
-                moderator_cooldown,
-                handlers,
-                scripts,
-                idle: &idle,
-                pong_timeout: &mut pong_timeout,
-                token: &bot_twitch.token,
-                handler_shutdown: false,
-                stream_info: &stream_info,
-                auth: &auth,
-                currency_handler,
-                url_whitelist_enabled,
-                bad_words_enabled,
-                chat_log: chat_log_builder.build()?,
-                channel,
-                context_inner: Arc::new(command::ContextInner {
-                    sender: sender.clone(),
-                    scope_cooldowns: sync::Mutex::new(auth.scope_cooldowns()),
-                    message_hooks: sync::RwLock::new(Default::default()),
-                    restart: restart.clone(),
-                }),
-            };
+                scope_cooldowns: sync::Mutex::new(auth.scope_cooldowns()),
+                message_hooks: sync::RwLock::new(message_hooks),
+                restart: restart.clone(),
+            }),
+        };
 
-            let mut outgoing = client
-                .outgoing()
-                .ok_or_else(|| anyhow!("missing outgoing future for irc client"))?;
+        let mut outgoing = client
+            .outgoing()
+            .ok_or_else(|| anyhow!("missing outgoing future for irc client"))?;
 
-            let mut client_stream = client.stream()?;
+        let mut client_stream = client.stream()?;
 
-            let mut ping_interval = tokio::time::interval(time::Duration::from_secs(10)).fuse();
-            let mut commands = command_bus.subscribe().fuse();
+        let mut ping_interval = tokio::time::interval(time::Duration::from_secs(10)).fuse();
+        let mut commands = command_bus.subscribe().fuse();
 
-            let mut leave = None;
+        let mut leave = None;
 
-            let sender = handler.sender.clone();
+        let sender = handler.sender.clone();
 
-            // Things to do when joining.
-            let mut join_task = Some(Box::pin(async move {
-                sender.cap_req(TWITCH_TAGS_CAP).await;
-                sender.cap_req(TWITCH_COMMANDS_CAP).await;
+        // Things to do when joining.
+        let mut join_task = Some(Box::pin(async move {
+            sender.cap_req(TWITCH_TAGS_CAP).await;
+            sender.cap_req(TWITCH_COMMANDS_CAP).await;
 
-                if let Some(join_message) = join_message.as_ref() {
-                    // greeting when bot joins.
-                    sender.privmsg_immediate(join_message);
-                }
-            }));
+            if let Some(join_message) = join_message.as_ref() {
+                // greeting when bot joins.
+                sender.privmsg_immediate(join_message);
+            }
+        }));
 
-            #[allow(clippy::unnecessary_mut_passed)]
-            while leave.is_none() {
-                futures::select! {
-                    _ = join_task.current() => {
-                        log::trace!("Done sending capabilities request and join message");
-                    }
-                    ev = scripts_watch_rx.select_next_some() => {
-                        if let Ok(ev) = ev {
-                            if let Err(e) = handler.handle_script_filesystem_event(ev) {
-                                log_error!(e, "failed to handle script filesystem event");
-                            }
+        #[allow(clippy::unnecessary_mut_passed)]
+        while leave.is_none() {
+            futures::select! {
+                _ = join_task.current() => {
+                    log::trace!("Done sending capabilities request and join message");
+                }
+                ev = scripts_watch_rx.select_next_some() => {
+                    if let Ok(ev) = ev {
+                        if let Err(e) = handler.handle_script_filesystem_event(ev) {
+                            log_error!(e, "failed to handle script filesystem event");
                         }
                     }
-                    command = commands.select_next_some() => {
-                        let command = command?;
+                }
+                command = commands.select_next_some() => {
+                    let command = command?;
 
-                        match command {
-                            bus::Command::Raw { command } => {
-                                log::trace!("Raw command: {}", command);
+                    match command {
+                        // NB: raw commands are sent from the web chat of the
+                        // streamer.
+                        bus::Command::Raw { command } if primary => {
+                            log::trace!("Raw command: {}", command);
 
-                                if let Err(e) = handler.raw(command).await {
-                                    log_error!(e, "Failed to handle message");
-                                }
+                            if let Err(e) = handler.raw(command).await {
+                                log_error!(e, "Failed to handle message");
                             }
                         }
-                    }
-                    future = futures.select_next_some() => {
-                        match future {
-                            Ok(..) => {
-                                log::warn!("IRC component exited, exiting...");
-                                break 'outer;
-                            }
-                            Err(e) => {
-                                log_warn!(e, "IRC component errored, restarting in 5 seconds");
-                                tokio::time::delay_for(time::Duration::from_secs(5)).await;
-                                continue 'outer;
-                            }
+                        bus::Command::Untimeout { channel, user } if channel == chat_channel => {
+                            log::info!("Removing timeout from: {}", user);
+                            handler.sender.untimeout(&user);
+                        }
+                        bus::Command::Unban { channel, user } if channel == chat_channel => {
+                            log::info!("Unbanning: {}", user);
+                            handler.sender.unban(&user);
                         }
+                        _ => (),
                     }
-                    update = twitch_setup.streamer_stream.select_next_some() => {
-                        if twitch_setup.update_streamer(update).await? {
-                            leave = Some(tokio::time::delay_for(time::Duration::from_secs(1)));
+                }
+                future = futures.select_next_some() => {
+                    match future {
+                        Ok(..) => {
+                            log::warn!("IRC component exited, exiting...");
+                            return Ok(Exit::Shutdown);
                         }
-                    },
-                    update = twitch_setup.bot_stream.select_next_some() => {
-                        if twitch_setup.update_bot(update).await? {
-                            leave = Some(tokio::time::delay_for(time::Duration::from_secs(1)));
+                        Err(e) => {
+                            log_warn!(e, "IRC component errored, restarting in 5 seconds");
+                            return Ok(Exit::Restart);
                         }
-                    },
-                    update = commands_stream.select_next_some() => {
-                        handler.commands = update;
-                    }
-                    update = aliases_stream.select_next_some() => {
-                        handler.aliases = update;
                     }
-                    cache = chat_log_builder.cache_stream.select_next_some() => {
-                        chat_log_builder.cache = cache;
-                        handler.chat_log = chat_log_builder.build()?;
-                    }
-                    update = chat_log_builder.enabled_stream.select_next_some() => {
-                        chat_log_builder.enabled = update;
-                        chat_log_builder.message_log.enabled(update).await;
-                        handler.chat_log = chat_log_builder.build()?;
-                    }
-                    update = chat_log_builder.emotes_enabled_stream.select_next_some() => {
-                        chat_log_builder.emotes_enabled = update;
-                        handler.chat_log = chat_log_builder.build()?;
-                    }
-                    update = api_url_stream.select_next_some() => {
-                        handler.api_url = Arc::new(update);
-                    }
-                    update = moderator_cooldown_stream.select_next_some() => {
-                        handler.moderator_cooldown = update;
-                    }
-                    _ = ping_interval.select_next_some() => {
-                        handler.send_ping()?;
-                    }
-                    _ = handler.pong_timeout.current() => {
-                        bail!("server not responding");
+                }
+                _ = leave_rx => {
+                    leave = Some(tokio::time::delay_for(time::Duration::from_secs(1)));
+                }
+                update = commands_stream.select_next_some() => {
+                    handler.commands = update;
+                }
+                update = aliases_stream.select_next_some() => {
+                    handler.aliases = update;
+                }
+                cache = chat_log_builder.cache_stream.select_next_some() => {
+                    chat_log_builder.cache = cache;
+                    handler.chat_log = chat_log_builder.build()?;
+                }
+                update = chat_log_builder.enabled_stream.select_next_some() => {
+                    chat_log_builder.enabled = update;
+                    chat_log_builder.message_log.enabled(update).await;
+                    handler.chat_log = chat_log_builder.build()?;
+                }
+                update = chat_log_builder.emotes_enabled_stream.select_next_some() => {
+                    chat_log_builder.emotes_enabled = update;
+                    handler.chat_log = chat_log_builder.build()?;
+                }
+                update = api_url_stream.select_next_some() => {
+                    handler.api_url = Arc::new(update);
+                }
+                update = moderator_cooldown_stream.select_next_some() => {
+                    handler.moderator_cooldown = update;
+                }
+                _ = ping_interval.select_next_some() => {
+                    handler.send_ping()?;
+                }
+                _ = handler.pong_timeout.current() => {
+                    bail!("server not responding");
+                }
+                update = whitelisted_hosts_stream.next() => {
+                    if let Some(update) = update {
+                        handler.whitelisted_hosts = update;
                     }
-                    update = whitelisted_hosts_stream.next() => {
-                        if let Some(update) = update {
-                            handler.whitelisted_hosts = update;
-                        }
-                    },
-                    message = client_stream.next() => {
-                        if let Some(m) = message.transpose()? {
-                            if let Err(e) = handler.handle(m).await {
-                                log_error!(e, "Failed to handle message");
-                            }
-                        }
-
-                        if handler.handler_shutdown {
-                            bail!("handler forcibly shut down");
+                },
+                message = client_stream.next() => {
+                    if let Some(m) = message.transpose()? {
+                        if let Err(e) = handler.handle(m).await {
+                            log_error!(e, "Failed to handle message");
                         }
                     }
-                    _ = outgoing => {
-                        bail!("outgoing future ended unexpectedly");
-                    }
-                    _ = leave.current() => {
-                        break;
+
+                    if handler.handler_shutdown {
+                        bail!("handler forcibly shut down");
                     }
                 }
+                _ = outgoing => {
+                    bail!("outgoing future ended unexpectedly");
+                }
+                _ = leave.current() => {
+                    break;
+                }
             }
+        }
 
-            handler.sender.privmsg_immediate(leave_message);
+        handler.sender.privmsg_immediate(leave_message);
 
-            #[allow(clippy::never_loop, clippy::unnecessary_mut_passed)]
-            loop {
-                futures::select! {
-                    _ = outgoing => {
-                        bail!("outgoing future ended unexpectedly");
-                    }
-                    _ = leave.current() => {
-                        break;
-                    }
+        #[allow(clippy::never_loop, clippy::unnecessary_mut_passed)]
+        loop {
+            futures::select! {
+                _ = outgoing => {
+                    bail!("outgoing future ended unexpectedly");
+                }
+                _ = leave.current() => {
+                    break;
                 }
             }
         }
 
-        Ok(())
+        Ok(Exit::Left)
     }
 }
 
-/// Set up a reward loop.
-async fn currency_loop(
+/// The reward loop of a channel.
+struct CurrencyLoop {
     twitch: api::Twitch,
     channel: Arc<twitch::Channel>,
     sender: Sender,
@@ -603,129 +896,264 @@ async fn currency_loop(
     injector: Injector,
     chat_settings: settings::Settings,
     settings: settings::Settings,
-) -> Result<impl Future<Output = Result<()>>> {
-    log::trace!("Setting up currency loop");
+    /// Settings used to build the currency, which is shared by all channels.
+    currency_settings: settings::Settings,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+    stream_info: stream_info::StreamInfo,
+    active_chatters: Arc<RwLock<HashSet<String>>>,
+    /// Name of the bot, which is excluded from leaderboards.
+    bot: String,
+    /// If this is the channel of the streamer, which is responsible for
+    /// injecting the currency.
+    primary: bool,
+}
 
-    let reward = 10;
-    let default_interval = Duration::seconds(60 * 10);
+impl CurrencyLoop {
+    /// Build the currency, only injecting it from the channel of the streamer.
+    async fn build(builder: &CurrencyBuilder, primary: bool) -> Option<Currency> {
+        if primary {
+            builder.build_and_inject().await
+        } else {
+            builder.build()
+        }
+    }
 
-    let (mut interval_stream, mut reward_interval) = chat_settings
-        .stream("viewer-reward/interval")
-        .or_with(default_interval)
-        .await?;
+    /// Set up the reward loop.
+    async fn setup(self) -> Result<impl Future<Output = Result<()>>> {
+        let CurrencyLoop {
+            twitch,
+            channel,
+            sender,
+            idle,
+            injector,
+            chat_settings,
+            settings,
+            currency_settings,
+            global_bus,
+            stream_info,
+            active_chatters,
+            bot,
+            primary,
+        } = self;
 
-    let reward_percentage = chat_settings.var("viewer-reward%", 100).await?;
-    let (mut viewer_reward_stream, viewer_reward) = chat_settings
-        .stream("viewer-reward/enabled")
-        .or_with(false)
-        .await?;
-    let (mut notify_rewards_stream, mut notify_rewards) = settings
-        .stream("currency/notify-rewards")
-        .or_with(true)
-        .await?;
+        log::trace!("Setting up currency loop");
 
-    let (mut ty_stream, ty) = settings.stream("currency/type").or_default().await?;
-    let (mut enabled_stream, enabled) = settings.stream("currency/enabled").or_default().await?;
-    let (mut name_stream, name) = settings.stream("currency/name").optional().await?;
-    let (mut command_enabled_stream, command_enabled) = settings
-        .stream("currency/command-enabled")
-        .or_with(true)
-        .await?;
-    let (mut mysql_url_stream, mysql_url) =
-        settings.stream("currency/mysql/url").optional().await?;
-    let (mut mysql_schema_stream, mysql_schema) = settings
-        .stream("currency/mysql/schema")
-        .or_default()
-        .await?;
+        let reward = 10;
+        let default_interval = Duration::seconds(60 * 10);
 
-    let (mut db_stream, db) = injector.stream::<db::Database>().await;
+        let (mut interval_stream, mut reward_interval) = chat_settings
+            .stream("viewer-reward/interval")
+            .or_with(default_interval)
+            .await?;
 
-    let mut builder = CurrencyBuilder::new(twitch, mysql_schema, injector.clone());
-    builder.db = db;
-    builder.ty = ty;
-    builder.enabled = enabled;
-    builder.command_enabled = command_enabled;
-    builder.name = name.map(Arc::new);
-    builder.mysql_url = mysql_url;
+        let reward_percentage = chat_settings.var("viewer-reward%", 100).await?;
+        let (mut viewer_reward_stream, viewer_reward) = chat_settings
+            .stream("viewer-reward/enabled")
+            .or_with(false)
+            .await?;
+        let activity_enabled = chat_settings
+            .var("viewer-reward/activity/enabled", false)
+            .await?;
+        let chatter_percentage = chat_settings
+            .var("viewer-reward/activity/chatter%", 200)
+            .await?;
+        let subscriber_percentage = chat_settings
+            .var("viewer-reward/activity/subscriber%", 100)
+            .await?;
+        let vip_percentage = chat_settings
+            .var("viewer-reward/activity/vip%", 100)
+            .await?;
+        let (mut notify_rewards_stream, mut notify_rewards) = settings
+            .stream("currency/notify-rewards")
+            .or_with(true)
+            .await?;
 
-    let mut currency = builder.build_and_inject().await;
+        let (mut ty_stream, ty) = currency_settings
+            .stream("currency/type")
+            .or_default()
+            .await?;
+        let (mut enabled_stream, enabled) = currency_settings
+            .stream("currency/enabled")
+            .or_default()
+            .await?;
+        let (mut name_stream, name) = currency_settings.stream("currency/name").optional().await?;
+        let (mut command_enabled_stream, command_enabled) = currency_settings
+            .stream("currency/command-enabled")
+            .or_with(true)
+            .await?;
+        let (mut mysql_url_stream, mysql_url) = currency_settings
+            .stream("currency/mysql/url")
+            .optional()
+            .await?;
+        let (mut mysql_schema_stream, mysql_schema) = currency_settings
+            .stream("currency/mysql/schema")
+            .or_default()
+            .await?;
+        let (mut top_ignore_stream, top_ignore) = currency_settings
+            .stream("currency/top/ignore")
+            .or_default()
+            .await?;
 
-    Ok(async move {
-        let new_timer = |interval: &Duration, viewer_reward: bool| {
-            if viewer_reward && !interval.is_empty() {
-                Some(tokio::time::interval(interval.as_std()))
-            } else {
-                None
-            }
-        };
+        let (mut db_stream, db) = injector.stream::<db::Database>().await;
+
+        let mut builder = CurrencyBuilder::new(twitch, mysql_schema, injector.clone());
+        builder.db = db;
+        builder.ty = ty;
+        builder.enabled = enabled;
+        builder.command_enabled = command_enabled;
+        builder.name = name.map(Arc::new);
+        builder.mysql_url = mysql_url;
+        builder.top_ignore = top_ignore;
+        builder.bot = Some(bot);
+
+        let mut currency = Self::build(&builder, primary).await;
+
+        let event_rewards = event_rewards::EventRewards::new(&settings).await?;
+        let mut events = global_bus.subscribe().fuse();
+
+        Ok(async move {
+            let new_timer = |interval: &Duration, viewer_reward: bool| {
+                if viewer_reward && !interval.is_empty() {
+                    Some(tokio::time::interval(interval.as_std()))
+                } else {
+                    None
+                }
+            };
 
-        let mut timer = new_timer(&reward_interval, viewer_reward);
+            let mut timer = new_timer(&reward_interval, viewer_reward);
 
-        loop {
-            futures::select! {
-                update = interval_stream.select_next_some() => {
-                    reward_interval = update;
-                    timer = new_timer(&reward_interval, viewer_reward);
-                }
-                update = notify_rewards_stream.select_next_some() => {
-                    notify_rewards = update;
-                }
-                update = db_stream.select_next_some() => {
-                    builder.db = update;
-                    currency = builder.build_and_inject().await;
-                }
-                enabled = enabled_stream.select_next_some() => {
-                    builder.enabled = enabled;
-                    currency = builder.build_and_inject().await;
-                }
-                update = ty_stream.select_next_some() => {
-                    builder.ty = update;
-                    currency = builder.build_and_inject().await;
-                }
-                name = name_stream.select_next_some() => {
-                    builder.name = name.map(Arc::new);
-                    currency = builder.build_and_inject().await;
-                }
-                mysql_url = mysql_url_stream.select_next_some() => {
-                    builder.mysql_url = mysql_url;
-                    currency = builder.build_and_inject().await;
-                }
-                update = mysql_schema_stream.select_next_some() => {
-                    builder.mysql_schema = update;
-                    currency = builder.build_and_inject().await;
-                }
-                command_enabled = command_enabled_stream.select_next_some() => {
-                    builder.command_enabled = command_enabled;
-                    currency = builder.build_and_inject().await;
-                }
-                viewer_reward = viewer_reward_stream.select_next_some() => {
-                    timer = new_timer(&reward_interval, viewer_reward);
-                }
-                _ = timer.select_next_some() => {
-                    let currency = match currency.as_ref() {
-                        Some(currency) => currency,
-                        None => continue,
-                    };
-
-                    let seconds = reward_interval.num_seconds() as i64;
-
-                    log::trace!("running reward loop");
-
-                    let reward = (reward * reward_percentage.load().await as i64) / 100i64;
-                    let count = currency
-                        .add_channel_all(&channel.name, reward, seconds)
-                        .await?;
-
-                    if notify_rewards && count > 0 && !idle.is_idle().await {
-                        sender.privmsg(format!(
-                            "/me has given {} {} to all viewers!",
-                            reward, currency.name
-                        )).await;
+            loop {
+                futures::select! {
+                    update = interval_stream.select_next_some() => {
+                        reward_interval = update;
+                        timer = new_timer(&reward_interval, viewer_reward);
+                    }
+                    update = notify_rewards_stream.select_next_some() => {
+                        notify_rewards = update;
+                    }
+                    update = db_stream.select_next_some() => {
+                        builder.db = update;
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    enabled = enabled_stream.select_next_some() => {
+                        builder.enabled = enabled;
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    update = ty_stream.select_next_some() => {
+                        builder.ty = update;
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    name = name_stream.select_next_some() => {
+                        builder.name = name.map(Arc::new);
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    mysql_url = mysql_url_stream.select_next_some() => {
+                        builder.mysql_url = mysql_url;
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    update = mysql_schema_stream.select_next_some() => {
+                        builder.mysql_schema = update;
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    command_enabled = command_enabled_stream.select_next_some() => {
+                        builder.command_enabled = command_enabled;
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    top_ignore = top_ignore_stream.select_next_some() => {
+                        builder.top_ignore = top_ignore;
+                        currency = Self::build(&builder, primary).await;
+                    }
+                    viewer_reward = viewer_reward_stream.select_next_some() => {
+                        timer = new_timer(&reward_interval, viewer_reward);
+                    }
+                    event = events.select_next_some() => {
+                        let (currency, event) = match (currency.as_ref(), event) {
+                            (Some(currency), Ok(event)) => (currency, event),
+                            _ => continue,
+                        };
+
+                        // NB: all channels publish their events on the same bus.
+                        if event.channel() != Some(sender.channel()) {
+                            continue;
+                        }
+
+                        match event_rewards.reward(currency, &channel.name, &event).await {
+                            Ok(event_rewards::Rewarded::Raid { amount, count }) => {
+                                if notify_rewards && count > 0 {
+                                    sender.privmsg(format!(
+                                        "/me has given {} {} to all viewers for the raid!",
+                                        amount, currency.name
+                                    )).await;
+                                }
+                            }
+                            Ok(..) => (),
+                            Err(e) => {
+                                log_error!(e, "failed to reward chat event");
+                            }
+                        }
+                    }
+                    _ = timer.select_next_some() => {
+                        let currency = match currency.as_ref() {
+                            Some(currency) => currency,
+                            None => continue,
+                        };
+
+                        let seconds = reward_interval.num_seconds() as i64;
+
+                        log::trace!("running reward loop");
+
+                        let reward = (reward * reward_percentage.load().await as i64) / 100i64;
+                        // NB: always take the set of active chatters, so that it
+                        // only covers the last interval.
+                        let active = mem::take(&mut *active_chatters.write());
+
+                        let count = if activity_enabled.load().await {
+                            let chatter = chatter_percentage.load().await as i64;
+                            let subscriber = subscriber_percentage.load().await as i64;
+                            let vip = vip_percentage.load().await as i64;
+
+                            currency
+                                .add_channel_all_scaled(
+                                    &channel.name,
+                                    reward,
+                                    seconds,
+                                    Reason::WatchReward,
+                                    |user, is_vip| {
+                                        let mut scale = 100;
+
+                                        if active.contains(user) {
+                                            scale = (scale * chatter) / 100;
+                                        }
+
+                                        if stream_info.is_subscriber(user) {
+                                            scale = (scale * subscriber) / 100;
+                                        }
+
+                                        if is_vip {
+                                            scale = (scale * vip) / 100;
+                                        }
+
+                                        scale
+                                    },
+                                )
+                                .await?
+                        } else {
+                            currency
+                                .add_channel_all(&channel.name, reward, seconds, Reason::WatchReward)
+                                .await?
+                        };
+
+                        if notify_rewards && count > 0 && !idle.is_idle().await {
+                            sender.privmsg(format!(
+                                "/me has given {} {} to all viewers!",
+                                reward, currency.name
+                            )).await;
+                        }
                     }
                 }
             }
-        }
-    })
+        })
+    }
 }
 
 /// Handler for incoming messages.
@@ -742,6 +1170,12 @@ struct Handler<'a> {
     whitelisted_hosts: HashSet<String>,
     /// All registered commands.
     commands: Option<db::Commands>,
+    /// Named counters accessible from command templates.
+    counters: injector::Var<Option<db::Counters>>,
+    /// Currency used to charge for commands which have a cost.
+    currency: injector::Var<Option<Currency>>,
+    /// Response when a user can't afford a command.
+    insufficient_balance: settings::Var<Template>,
     /// Bad words.
     bad_words: &'a db::Words,
     /// For sending notifications.
@@ -770,8 +1204,27 @@ struct Handler<'a> {
     auth: &'a Auth,
     /// Handler for currencies.
     currency_handler: Arc<currency_admin::Handler>,
+    /// Users who have chatted since the last viewer reward.
+    active_chatters: Arc<RwLock<HashSet<String>>>,
     bad_words_enabled: settings::Var<bool>,
     url_whitelist_enabled: settings::Var<bool>,
+    /// Punishments for tripping the bad words filter.
+    bad_words_punishments: settings::Var<moderation::Punishments>,
+    /// Punishments for tripping the URL whitelist.
+    url_whitelist_punishments: settings::Var<moderation::Punishments>,
+    /// Filters for spammy messages.
+    spam: spam::Filters,
+    /// Offences committed by users, used to escalate punishments.
+    offences: injector::Var<Option<db::Offences>>,
+    /// How long it takes for offences to be forgotten.
+    moderation_decay: settings::Var<Duration>,
+    /// Explanation posted when a user is moderated.
+    moderation_template: settings::Var<Template>,
+    /// Log of moderation actions.
+    moderation_log: injector::Var<Option<db::ModerationLog>>,
+    /// Users and message ids the bot has moderated itself, so that the
+    /// CLEARCHAT or CLEARMSG echoed back by Twitch isn't logged twice.
+    moderated: RwLock<HashSet<String>>,
     /// Handler for chat logs.
     chat_log: Option<chat_log::ChatLog>,
     /// Information on the current channel.
@@ -831,6 +1284,21 @@ impl Handler<'_> {
     }
 }
 
+/// Test if the user is permitted to invoke something with the given
+/// restrictions, registering the invocation against its cooldowns if so.
+async fn is_permitted(user: &User, restrictions: &db::Restrictions) -> bool {
+    has_restricted_scope(user, restrictions).await && restrictions.is_open(user.name())
+}
+
+/// Test if the user has the scope required by the given restrictions, without
+/// touching its cooldowns.
+async fn has_restricted_scope(user: &User, restrictions: &db::Restrictions) -> bool {
+    match restrictions.scope {
+        Some(scope) => user.has_scope(scope).await,
+        None => true,
+    }
+}
+
 /// Handle a command.
 async fn process_command(
     command: &str,
@@ -880,12 +1348,17 @@ async fn process_command(
                 }
 
                 task::spawn(async move {
-                    if let Err(e) = handler.handle(&mut ctx).await {
-                        if let Some(command::Respond(respond)) = e.downcast_ref() {
-                            respond!(ctx, respond);
-                        } else {
-                            respond!(ctx, "Sorry, something went wrong :(");
-                            log_error!(e, "Error when processing command");
+                    match handler.handle(&mut ctx).await {
+                        Ok(()) => {
+                            ctx.consume_scopes(scope).await;
+                        }
+                        Err(e) => {
+                            if let Some(command::Respond(respond)) = e.downcast_ref() {
+                                respond!(ctx, respond);
+                            } else {
+                                respond!(ctx, "Sorry, something went wrong :(");
+                                log_error!(e, "Error when processing command");
+                            }
                         }
                     }
                 });
@@ -916,64 +1389,420 @@ impl<'a> Handler<'a> {
         };
 
         log::info!("Attempting to delete message: {}", id);
+        self.moderated.write().insert(id.clone());
         user.inner.sender.delete(id);
         Ok(())
     }
 
-    /// Test if the message should be deleted.
-    async fn should_be_deleted(&self, user: &User, message: &str) -> bool {
+    /// Process a custom command which matched the message.
+    async fn process_custom_command(
+        &self,
+        user: &User,
+        commands: &db::Commands,
+        command: &db::Command,
+        captures: db::Captures<'_>,
+        args: &str,
+    ) -> Result<()> {
+        if !has_restricted_scope(user, &command.restrictions).await {
+            return Ok(());
+        }
+
+        // NB: named arguments only apply to commands matched by name.
+        let arguments = match (command.args.as_ref(), &command.pattern) {
+            (Some(spec), db::Pattern::Name) => match spec.bind(args) {
+                Some(arguments) => arguments,
+                None => {
+                    respond!(user, "Usage: !{} {}", command.key.name, spec.usage());
+                    return Ok(());
+                }
+            },
+            _ => HashMap::new(),
+        };
+
+        if !command.restrictions.is_open(user.name()) {
+            return Ok(());
+        }
+
+        if !self.charge(user, command).await? {
+            return Ok(());
+        }
+
+        let result = self
+            .render_custom_command(user, commands, command, captures, args, arguments)
+            .await;
+
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => {
+                self.refund(user, command).await?;
+                return Err(e);
+            }
+        };
+
+        self.sender.privmsg(response).await;
+
+        if let Some(scope) = command.restrictions.scope {
+            user.consume_scope(scope).await;
+        }
+
+        Ok(())
+    }
+
+    /// Render the response of a custom command.
+    async fn render_custom_command(
+        &self,
+        user: &User,
+        commands: &db::Commands,
+        command: &db::Command,
+        captures: db::Captures<'_>,
+        args: &str,
+        arguments: HashMap<String, &str>,
+    ) -> Result<String> {
+        if command.has_var("count") {
+            commands.increment(command).await?;
+        }
+
+        let counters = match self.counters.load().await {
+            Some(counters) if command.has_var("counter") => counters.values(user.channel()).await?,
+            _ => Default::default(),
+        };
+
+        let balances = if command.has_var("balance") {
+            self.balances(user, command, args).await?
+        } else {
+            Default::default()
+        };
+
+        let vars = CommandVars {
+            name: user.display_name(),
+            target: user.channel(),
+            count: command.count(),
+            args,
+            stream: self.stream_info.template_vars(),
+            counters,
+            balances,
+            arguments,
+            captures,
+        };
+
+        command.render(&vars)
+    }
+
+    /// Charge the user for invoking the given command, if it has a cost,
+    /// responding if they can't afford it.
+    ///
+    /// Returns `false` if the command should not be run.
+    async fn charge(&self, user: &User, command: &db::Command) -> Result<bool> {
+        let cost = match command.cost {
+            Some(cost) if cost != 0 => cost,
+            _ => return Ok(true),
+        };
+
+        let name = match user.name() {
+            Some(name) => name,
+            None => return Ok(true),
+        };
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => return Ok(false),
+        };
+
+        // NB: commands with a negative cost reward the user instead.
+        if cost < 0 {
+            currency
+                .balance_add(user.channel(), name, -cost, Reason::Command)
+                .await?;
+
+            return Ok(true);
+        }
+
+        let result = currency
+            .balance_transfer(
+                user.channel(),
+                name,
+                COMMAND_COSTS,
+                cost,
+                false,
+                Reason::Command,
+            )
+            .await;
+
+        match result {
+            Ok(()) => Ok(true),
+            Err(BalanceTransferError::NoBalance) => {
+                let balance = currency
+                    .balance_of(user.channel(), name)
+                    .await?
+                    .unwrap_or_default()
+                    .balance;
+
+                let response = self.insufficient_balance.load().await.render_to_string(
+                    &InsufficientBalanceVars {
+                        name: user.display_name(),
+                        target: user.channel(),
+                        command: &command.key.name,
+                        cost,
+                        balance,
+                        currency: &currency.name,
+                    },
+                )?;
+
+                self.sender.privmsg(response).await;
+                Ok(false)
+            }
+            Err(BalanceTransferError::Other(e)) => Err(e),
+        }
+    }
+
+    /// Undo the charge for a command which failed to run.
+    async fn refund(&self, user: &User, command: &db::Command) -> Result<()> {
+        let cost = match command.cost {
+            Some(cost) if cost != 0 => cost,
+            _ => return Ok(()),
+        };
+
+        let (name, currency) = match (user.name(), self.currency.load().await) {
+            (Some(name), Some(currency)) => (name, currency),
+            _ => return Ok(()),
+        };
+
+        if cost < 0 {
+            currency
+                .balance_add(user.channel(), name, cost, Reason::Command)
+                .await?;
+        } else {
+            currency
+                .balance_transfer(
+                    user.channel(),
+                    COMMAND_COSTS,
+                    name,
+                    cost,
+                    true,
+                    Reason::Command,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Load balances for use with the `balance` template helper.
+    ///
+    /// Only the balances of the user invoking the command, of the user the
+    /// command is directed at, and of users named literally in the template
+    /// like `{{balance "setbac"}}` are loaded. Any other user renders as `0`.
+    async fn balances(
+        &self,
+        user: &User,
+        command: &db::Command,
+        args: &str,
+    ) -> Result<HashMap<String, i64>> {
+        let mut balances = HashMap::new();
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => return Ok(balances),
+        };
+
+        let to_user = args
+            .split_whitespace()
+            .next()
+            .map(|a| a.trim_start_matches('@'));
+
+        let literals = command.template.helper_literals("balance");
+        let literals = literals.iter().map(|l| l.trim_start_matches('@'));
+
+        for name in user.name().into_iter().chain(to_user).chain(literals) {
+            let name = name.to_lowercase();
+
+            if let Some(balance) = currency.balance_of(user.channel(), &name).await? {
+                balances.insert(name, balance.balance);
+            }
+        }
+
+        Ok(balances)
+    }
+
+    /// Test if the message trips any of the chat filters.
+    async fn check_message(&self, user: &User, message: &str) -> Result<Option<Violation>> {
         // Moderators can say whatever they want.
         if user.is_moderator() {
-            return false;
+            return Ok(None);
         }
 
         if self.bad_words_enabled.load().await {
             if let Some(word) = self.test_bad_words(message).await {
-                if let Some(why) = word.why.as_ref() {
-                    let why = why.render_to_string(&BadWordsVars {
+                let why = word.why.as_ref().map(|why| {
+                    why.render_to_string(&BadWordsVars {
                         name: user.display_name(),
                         target: user.channel(),
-                    });
+                        stream: self.stream_info.template_vars(),
+                    })
+                });
 
-                    match why {
-                        Ok(why) => {
-                            self.sender.privmsg(&why).await;
-                        }
-                        Err(e) => {
-                            log_error!(e, "failed to render response");
-                        }
+                let reason = match why {
+                    Some(Ok(why)) => why,
+                    Some(Err(e)) => {
+                        log_error!(e, "failed to render response");
+                        String::from(DEFAULT_BAD_WORDS_REASON)
+                    }
+                    None => String::from(DEFAULT_BAD_WORDS_REASON),
+                };
+
+                return Ok(Some(Violation {
+                    filter: "bad-words",
+                    rule: format!("bad-words: {} `{}`", word.kind, word.word),
+                    reason,
+                    punishments: self.bad_words_punishments.load().await,
+                }));
+            }
+        }
+
+        // NB: the scope is only tested once a bad link is found, since
+        // bypassing the whitelist consumes a use of a temporary grant.
+        if self.url_whitelist_enabled.load().await && self.has_bad_link(message) {
+            if !user.has_scope(Scope::ChatBypassUrlWhitelist).await {
+                return Ok(Some(Violation {
+                    filter: "url-whitelist",
+                    rule: String::from("url-whitelist"),
+                    reason: String::from(DEFAULT_URL_WHITELIST_REASON),
+                    punishments: self.url_whitelist_punishments.load().await,
+                }));
+            }
+
+            user.consume_scope(Scope::ChatBypassUrlWhitelist).await;
+        }
+
+        Ok(self.spam.test(user, message).await)
+    }
+
+    /// Record a moderation action taken by a moderator, like a `/timeout` in
+    /// chat or a raw command sent through the web UI.
+    ///
+    /// Twitch doesn't tell us which moderator took the action.
+    async fn record_manual(
+        &self,
+        channel: &str,
+        action: moderation::Action,
+        user: &str,
+        message: Option<&str>,
+    ) {
+        let moderation_log = match self.moderation_log.load().await {
+            Some(moderation_log) => moderation_log,
+            None => return,
+        };
+
+        let entry = db::InsertModerationLogEntry {
+            channel: channel.to_string(),
+            action: action.to_string(),
+            user: user.to_string(),
+            message: message.map(|m| m.to_string()),
+            rule: None,
+            actor: Some(String::from("moderator")),
+        };
+
+        if let Err(e) = moderation_log.record(entry).await {
+            log_error!(e, "failed to record moderation action");
+        }
+    }
+
+    /// Moderate the given message, escalating the punishment for users who
+    /// repeatedly trip the same filter.
+    async fn moderate(&self, user: &User, message: &str) -> Result<()> {
+        let violation = match self.check_message(user, message).await? {
+            Some(violation) => violation,
+            None => return Ok(()),
+        };
+
+        let offences = match (self.offences.load().await, user.name()) {
+            (Some(offences), Some(name)) => {
+                let decay = self.moderation_decay.load().await;
+
+                // NB: failing to record an offence shouldn't let the message
+                // through, so treat it as a first offence.
+                match offences
+                    .record(user.channel(), name, violation.filter, decay)
+                    .await
+                {
+                    Ok(offences) => offences,
+                    Err(e) => {
+                        log_error!(e, "failed to record offence");
+                        1
                     }
                 }
+            }
+            _ => 1,
+        };
 
-                return true;
+        let action = violation.punishments.action(offences);
+
+        log::info!(
+            "Moderating {:?} for tripping `{}` (offence #{}): {}",
+            user.name(),
+            violation.filter,
+            offences,
+            action
+        );
+
+        match (action, user.name()) {
+            (moderation::Action::Warn, _) => (),
+            (moderation::Action::Delete, _) => {
+                self.delete_message(user)?;
+            }
+            (moderation::Action::Timeout(duration), Some(name)) => {
+                self.moderated.write().insert(name.to_string());
+                self.sender
+                    .timeout(name, duration.num_seconds(), &violation.reason);
+            }
+            (moderation::Action::Ban, Some(name)) => {
+                self.moderated.write().insert(name.to_string());
+                self.sender.ban(name, &violation.reason);
+            }
+            // NB: injected messages can't be timed out or banned.
+            _ => {
+                self.delete_message(user)?;
             }
         }
 
-        #[allow(clippy::collapsible_if)]
+        if let (Some(moderation_log), Some(name)) = (self.moderation_log.load().await, user.name())
         {
-            if !user.has_scope(Scope::ChatBypassUrlWhitelist).await
-                && self.url_whitelist_enabled.load().await
-            {
-                if self.has_bad_link(message) {
-                    return true;
-                }
+            let entry = db::InsertModerationLogEntry {
+                channel: user.channel().to_string(),
+                action: action.to_string(),
+                user: name.to_string(),
+                message: Some(message.to_string()),
+                rule: Some(violation.rule.clone()),
+                actor: None,
+            };
+
+            if let Err(e) = moderation_log.record(entry).await {
+                log_error!(e, "failed to record moderation action");
             }
         }
 
-        false
+        let response = self
+            .moderation_template
+            .load()
+            .await
+            .render_to_string(&ModerationVars {
+                name: user.display_name(),
+                target: user.channel(),
+                filter: violation.filter,
+                reason: &violation.reason,
+                action: action.describe(),
+                offences,
+                stream: self.stream_info.template_vars(),
+            })?;
+
+        self.sender.privmsg(response).await;
+        Ok(())
     }
 
     /// Test the message for bad words.
     async fn test_bad_words(&self, message: &str) -> Option<Arc<db::Word>> {
         let tester = self.bad_words.tester().await;
-
-        for word in utils::TrimmedWords::new(message) {
-            if let Some(word) = tester.test(word) {
-                return Some(word);
-            }
-        }
-
-        None
+        tester.test_message(message)
     }
 
     /// Check if the given iterator has URLs that need to be
@@ -1023,15 +1852,19 @@ impl<'a> Handler<'a> {
             self.idle.seen();
         }
 
+        if let Some(name) = user.name() {
+            self.active_chatters.write().insert(name.to_string());
+        }
+
         // NB: declared here to be in scope.
         let mut seen = HashSet::new();
         let mut path = Vec::new();
 
         if let Some(aliases) = self.aliases.as_ref() {
-            while let Some((key, next)) = aliases.resolve(user.channel(), message.clone()).await {
-                path.push(key.to_string());
+            while let Some((alias, next)) = aliases.resolve(user.channel(), message.clone()).await {
+                path.push(alias.key.to_string());
 
-                if !seen.insert(key.clone()) {
+                if !seen.insert(alias.key.clone()) {
                     respond!(
                         user,
                         "Recursion found in alias expansion: {} :(",
@@ -1040,6 +1873,14 @@ impl<'a> Handler<'a> {
                     return Ok(());
                 }
 
+                // NB: restricted invocations are silently dropped, but the
+                // message is still subject to moderation.
+                if !is_permitted(user, &alias.restrictions).await {
+                    self.moderate(&user, &*message).await?;
+
+                    return Ok(());
+                }
+
                 message = Arc::new(next);
             }
         }
@@ -1052,19 +1893,8 @@ impl<'a> Handler<'a> {
                 .resolve(user.channel(), first.as_deref(), &it)
                 .await
             {
-                if command.has_var("count") {
-                    commands.increment(&*command).await?;
-                }
-
-                let vars = CommandVars {
-                    name: user.display_name(),
-                    target: user.channel(),
-                    count: command.count(),
-                    captures,
-                };
-
-                let response = command.render(&vars)?;
-                self.sender.privmsg(response).await;
+                self.process_custom_command(user, commands, &command, captures, it.rest())
+                    .await?;
             }
         }
 
@@ -1077,6 +1907,7 @@ impl<'a> Handler<'a> {
                     user: user.clone(),
                     it,
                     inner: self.context_inner.clone(),
+                    checked_scopes: Default::default(),
                 };
 
                 let result = process_command(
@@ -1094,9 +1925,7 @@ impl<'a> Handler<'a> {
             }
         }
 
-        if self.should_be_deleted(&user, &*message).await {
-            self.delete_message(&user)?;
-        }
+        self.moderate(&user, &*message).await?;
 
         Ok(())
     }
@@ -1144,6 +1973,18 @@ impl<'a> Handler<'a> {
                     }));
                 }
 
+                if let Some(bits) = tags.bits {
+                    self.global_bus
+                        .send(bus::Global::Cheer {
+                            channel: self.sender.channel().to_string(),
+                            user: name.clone(),
+                            display_name: tags.display_name.clone(),
+                            bits,
+                            message: (*message).clone(),
+                        })
+                        .await;
+                }
+
                 let user = User {
                     inner: Arc::new(UserInner {
                         tags,
@@ -1225,15 +2066,48 @@ impl<'a> Handler<'a> {
             }
             Command::Raw(ref command, ref tail) => match command.as_str() {
                 "CLEARMSG" => {
-                    if let Some(chat_log) = self.chat_log.as_ref() {
-                        if let Some(tags) = ClearMsgTags::from_tags(m.tags) {
+                    if let Some(tags) = ClearMsgTags::from_tags(m.tags) {
+                        if let Some(chat_log) = self.chat_log.as_ref() {
                             chat_log.message_log.delete_by_id(&tags.target_msg_id).await;
                         }
+
+                        // NB: deletions issued by the bot are already logged.
+                        let manual = !self.moderated.write().remove(&tags.target_msg_id);
+
+                        if let (true, Some(channel), Some(login)) =
+                            (manual, tail.first(), tags.login.as_deref())
+                        {
+                            let message = tail.get(1).map(String::as_str);
+                            self.record_manual(channel, moderation::Action::Delete, login, message)
+                                .await;
+                        }
+                    }
+                }
+                "USERNOTICE" => {
+                    let message = tail.get(1).map(String::as_str);
+
+                    match UserNotice::from_tags(m.tags.take(), message) {
+                        Some(notice) => {
+                            log::trace!("User notice: {:?}", notice);
+                            self.global_bus
+                                .send(bus::Global::UserNotice {
+                                    channel: self.sender.channel().to_string(),
+                                    notice,
+                                })
+                                .await;
+                        }
+                        None => {
+                            log::trace!("Unhandled user notice: {:?}", m);
+                        }
                     }
                 }
                 "CLEARCHAT" => {
+                    let tags = ClearChatTags::from_tags(m.tags.take());
+                    // NB: the first argument is the channel.
+                    let user = tail.get(1);
+
                     if let Some(chat_log) = self.chat_log.as_ref() {
-                        match tail.first() {
+                        match user {
                             Some(user) => {
                                 chat_log.message_log.delete_by_user(user).await;
                             }
@@ -1242,6 +2116,20 @@ impl<'a> Handler<'a> {
                             }
                         }
                     }
+
+                    // NB: timeouts and bans issued by the bot are already logged.
+                    if let (Some(channel), Some(user)) = (tail.first(), user) {
+                        if !self.moderated.write().remove(user) {
+                            let action = match tags.ban_duration {
+                                Some(seconds) => {
+                                    moderation::Action::Timeout(Duration::seconds(seconds))
+                                }
+                                None => moderation::Action::Ban,
+                            };
+
+                            self.record_manual(channel, action, user, None).await;
+                        }
+                    }
                 }
                 _ => {
                     log::trace!("Raw: {:?}", m);
@@ -1349,6 +2237,12 @@ impl<'a> RealUser<'a> {
     pub async fn has_scope(&self, scope: Scope) -> bool {
         self.auth.test_any(scope, self.name, self.roles()).await
     }
+
+    /// Consume one use of a temporary grant which gives the current user the
+    /// given scope, once the action it gates has been taken.
+    pub async fn consume_scope(&self, scope: Scope) {
+        self.auth.consume_any(scope, self.name, self.roles()).await
+    }
 }
 
 /// Information about the user.
@@ -1505,6 +2399,14 @@ impl User {
 
         user.has_scope(scope).await
     }
+
+    /// Consume one use of a temporary grant which gives the current user the
+    /// given scope, once the action it gates has been taken.
+    pub async fn consume_scope(&self, scope: Scope) {
+        if let Some(user) = self.real() {
+            user.consume_scope(scope).await;
+        }
+    }
 }
 
 struct PartitionResponse<'a, I> {
@@ -1608,6 +2510,8 @@ pub struct Tags {
     pub emotes: Option<String>,
     /// Badges part of the message.
     pub badges: Option<String>,
+    /// Number of bits cheered with the message.
+    pub bits: Option<u32>,
 }
 
 impl Tags {
@@ -1621,6 +2525,7 @@ impl Tags {
         let mut color = None;
         let mut emotes = None;
         let mut badges = None;
+        let mut bits = None;
 
         if let Some(tags) = tags {
             for t in tags {
@@ -1633,6 +2538,7 @@ impl Tags {
                         "color" => color = Some(value),
                         "emotes" => emotes = Some(value),
                         "badges" => badges = Some(value),
+                        "bits" => bits = value.parse().ok(),
                         _ => (),
                     },
                     _ => (),
@@ -1648,13 +2554,32 @@ impl Tags {
             color,
             emotes,
             badges,
+            bits,
         }
     }
+
+    /// Count the number of emotes in the message, as indicated by the emotes
+    /// tag, like `300354391:8-16/28087:0-6,18-24`.
+    pub fn emote_count(&self) -> usize {
+        let emotes = match self.emotes.as_deref() {
+            Some(emotes) => emotes,
+            None => return 0,
+        };
+
+        emotes
+            .split('/')
+            .filter_map(|emote| emote.split(':').nth(1))
+            .flat_map(|spans| spans.split(','))
+            .filter(|span| !span.is_empty())
+            .count()
+    }
 }
 
 /// Tags associated with a CLEARMSG.
 struct ClearMsgTags {
     target_msg_id: String,
+    /// The login of the user whose message was deleted.
+    login: Option<String>,
 }
 
 impl ClearMsgTags {
@@ -1662,12 +2587,14 @@ impl ClearMsgTags {
     #[allow(clippy::single_match)]
     fn from_tags(tags: Option<Vec<Tag>>) -> Option<ClearMsgTags> {
         let mut target_msg_id = None;
+        let mut login = None;
 
         if let Some(tags) = tags {
             for t in tags {
                 match t {
                     Tag(name, Some(value)) => match name.as_str() {
                         "target-msg-id" => target_msg_id = Some(value),
+                        "login" => login = Some(value),
                         _ => (),
                     },
                     _ => (),
@@ -1677,10 +2604,51 @@ impl ClearMsgTags {
 
         Some(ClearMsgTags {
             target_msg_id: target_msg_id?,
+            login,
         })
     }
 }
 
+/// Tags associated with a CLEARCHAT.
+struct ClearChatTags {
+    /// How long the user was timed out for, in seconds. Absent for bans.
+    ban_duration: Option<u64>,
+}
+
+impl ClearChatTags {
+    /// Extract tags from message.
+    #[allow(clippy::single_match)]
+    fn from_tags(tags: Option<Vec<Tag>>) -> ClearChatTags {
+        let mut ban_duration = None;
+
+        if let Some(tags) = tags {
+            for t in tags {
+                match t {
+                    Tag(name, Some(value)) => match name.as_str() {
+                        "ban-duration" => ban_duration = str::parse(&value).ok(),
+                        _ => (),
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        ClearChatTags { ban_duration }
+    }
+}
+
+/// A chat filter which was tripped by a message.
+pub(crate) struct Violation {
+    /// The name of the filter.
+    filter: &'static str,
+    /// The rule which matched, like the filter and the bad word.
+    rule: String,
+    /// Why the message isn't allowed.
+    reason: String,
+    /// Punishments configured for the filter.
+    punishments: moderation::Punishments,
+}
+
 #[derive(Debug)]
 pub enum SenderThreadItem {
     Exit,
@@ -1691,6 +2659,28 @@ pub enum SenderThreadItem {
 pub struct BadWordsVars<'a> {
     name: Option<&'a str>,
     target: &'a str,
+    stream: template::StreamVars,
+}
+
+#[derive(serde::Serialize)]
+pub struct ModerationVars<'a> {
+    name: Option<&'a str>,
+    target: &'a str,
+    filter: &'a str,
+    reason: &'a str,
+    action: String,
+    offences: u32,
+    stream: template::StreamVars,
+}
+
+#[derive(serde::Serialize)]
+pub struct InsufficientBalanceVars<'a> {
+    name: Option<&'a str>,
+    target: &'a str,
+    command: &'a str,
+    cost: i64,
+    balance: i64,
+    currency: &'a str,
 }
 
 #[derive(serde::Serialize)]
@@ -1698,6 +2688,12 @@ pub struct CommandVars<'a> {
     name: Option<&'a str>,
     target: &'a str,
     count: i32,
+    args: &'a str,
+    stream: template::StreamVars,
+    counters: HashMap<String, i64>,
+    balances: HashMap<String, i64>,
+    #[serde(flatten)]
+    arguments: HashMap<String, &'a str>,
     #[serde(flatten)]
     captures: db::Captures<'a>,
 }
@@ -0,0 +1,175 @@
+use crate::utils::Duration;
+use anyhow::{bail, Error};
+use std::fmt;
+
+/// An action taken against a user who tripped a chat filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Only explain why the message isn't allowed.
+    Warn,
+    /// Delete the offending message.
+    Delete,
+    /// Time the user out for the given duration.
+    Timeout(Duration),
+    /// Ban the user from the channel.
+    Ban,
+}
+
+impl Action {
+    /// Describe the action, for use in moderation messages.
+    pub fn describe(&self) -> String {
+        match *self {
+            Action::Warn => String::from("warning"),
+            Action::Delete => String::from("message deleted"),
+            Action::Timeout(duration) => format!("timed out for {}", duration),
+            Action::Ban => String::from("banned"),
+        }
+    }
+}
+
+impl std::str::FromStr for Action {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut it = s.split_whitespace();
+
+        let action = match (it.next(), it.next()) {
+            (Some("warn"), None) => Action::Warn,
+            (Some("delete"), None) => Action::Delete,
+            (Some("timeout"), Some(duration)) => Action::Timeout(str::parse(duration)?),
+            (Some("ban"), None) => Action::Ban,
+            _ => bail!(
+                "expected `warn`, `delete`, `timeout <duration>`, or `ban`, but got `{}`",
+                s
+            ),
+        };
+
+        if it.next().is_some() {
+            bail!("unexpected trailing input in `{}`", s);
+        }
+
+        Ok(action)
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Action::Warn => "warn".fmt(fmt),
+            Action::Delete => "delete".fmt(fmt),
+            Action::Timeout(duration) => write!(fmt, "timeout {}", duration),
+            Action::Ban => "ban".fmt(fmt),
+        }
+    }
+}
+
+/// A ladder of actions to take against repeat offenders, like
+/// `warn, delete, timeout 10m, timeout 1h, ban`.
+///
+/// The first offence takes the first action, the second offence the second
+/// action, and so forth. Once the ladder runs out the last action is repeated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Punishments {
+    actions: Vec<Action>,
+}
+
+impl Punishments {
+    /// Get the action to take for the given offence, counting from one.
+    pub fn action(&self, offence: u32) -> Action {
+        let index = (offence.max(1) - 1) as usize;
+
+        self.actions
+            .get(index)
+            .or_else(|| self.actions.last())
+            .copied()
+            .unwrap_or(Action::Delete)
+    }
+}
+
+impl Default for Punishments {
+    fn default() -> Self {
+        Self {
+            actions: vec![Action::Delete],
+        }
+    }
+}
+
+impl std::str::FromStr for Punishments {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut actions = Vec::new();
+
+        for action in s.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            actions.push(str::parse(action)?);
+        }
+
+        if actions.is_empty() {
+            bail!("expected at least one action");
+        }
+
+        Ok(Self { actions })
+    }
+}
+
+impl fmt::Display for Punishments {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut it = self.actions.iter().peekable();
+
+        while let Some(action) = it.next() {
+            action.fmt(fmt)?;
+
+            if it.peek().is_some() {
+                fmt.write_str(", ")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Punishments {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let punishments = String::deserialize(deserializer)?;
+        str::parse(&punishments).map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for Punishments {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Punishments};
+    use crate::utils::Duration;
+    use anyhow::Error;
+
+    #[test]
+    fn test_punishments() -> Result<(), Error> {
+        let punishments = str::parse::<Punishments>("warn, delete,timeout 10m, ban")?;
+        assert_eq!("warn, delete, timeout 10m, ban", punishments.to_string());
+
+        assert_eq!(Action::Warn, punishments.action(1));
+        assert_eq!(Action::Delete, punishments.action(2));
+        assert_eq!(
+            Action::Timeout(Duration::seconds(600)),
+            punishments.action(3)
+        );
+        assert_eq!(Action::Ban, punishments.action(4));
+        assert_eq!(Action::Ban, punishments.action(10));
+
+        assert!(str::parse::<Punishments>("").is_err());
+        assert!(str::parse::<Punishments>("timeout").is_err());
+        assert!(str::parse::<Punishments>("delete 10m").is_err());
+        Ok(())
+    }
+}
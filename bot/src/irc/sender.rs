@@ -83,6 +83,26 @@ impl Sender {
         self.privmsg_immediate(format!("/delete {}", id));
     }
 
+    /// Time out the given user for the given number of seconds.
+    pub fn timeout(&self, user: &str, seconds: u64, reason: &str) {
+        self.privmsg_immediate(format!("/timeout {} {} {}", user, seconds, reason));
+    }
+
+    /// Remove a timeout from the given user.
+    pub fn untimeout(&self, user: &str) {
+        self.privmsg_immediate(format!("/untimeout {}", user));
+    }
+
+    /// Ban the given user.
+    pub fn ban(&self, user: &str, reason: &str) {
+        self.privmsg_immediate(format!("/ban {} {}", user, reason));
+    }
+
+    /// Unban the given user.
+    pub fn unban(&self, user: &str) {
+        self.privmsg_immediate(format!("/unban {}", user));
+    }
+
     /// Get list of mods.
     pub fn mods(&self) {
         self.privmsg_immediate("/mods");
@@ -0,0 +1,338 @@
+use super::moderation::Punishments;
+use super::{User, Violation};
+use crate::auth::Scope;
+use crate::prelude::*;
+use crate::utils::Duration;
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// A single spam filter, which can be enabled, bypassed through a scope, and
+/// has its own punishments.
+struct Filter {
+    name: &'static str,
+    reason: &'static str,
+    scope: Scope,
+    enabled: settings::Var<bool>,
+    punishments: settings::Var<Punishments>,
+}
+
+impl Filter {
+    async fn load(
+        settings: &settings::Settings,
+        name: &'static str,
+        reason: &'static str,
+        scope: Scope,
+    ) -> Result<Self> {
+        Ok(Self {
+            name,
+            reason,
+            scope,
+            enabled: settings.var(&format!("{}/enabled", name), false).await?,
+            punishments: settings
+                .var(&format!("{}/punishments", name), Punishments::default())
+                .await?,
+        })
+    }
+
+    /// Test if the filter is enabled.
+    async fn is_enabled(&self) -> bool {
+        self.enabled.load().await
+    }
+
+    /// Construct a violation of this filter, unless the user is allowed to
+    /// bypass it.
+    ///
+    /// NB: the scope is only tested once the filter has been tripped, since
+    /// bypassing it consumes a use of a temporary grant.
+    async fn violation(&self, user: &User) -> Option<Violation> {
+        if user.has_scope(self.scope).await {
+            user.consume_scope(self.scope).await;
+            return None;
+        }
+
+        Some(Violation {
+            filter: self.name,
+            rule: String::from(self.name),
+            reason: String::from(self.reason),
+            punishments: self.punishments.load().await,
+        })
+    }
+}
+
+/// The last message seen from a user.
+struct Recent {
+    message: String,
+    count: u32,
+    at: Instant,
+}
+
+/// Filters for spammy messages.
+pub(crate) struct Filters {
+    caps: Filter,
+    caps_min_length: settings::Var<usize>,
+    caps_max: settings::Var<u32>,
+    symbols: Filter,
+    symbols_min_length: settings::Var<usize>,
+    symbols_max: settings::Var<u32>,
+    repetition: Filter,
+    repetition_max_chars: settings::Var<usize>,
+    repetition_max_words: settings::Var<usize>,
+    duplicates: Filter,
+    duplicates_max: settings::Var<u32>,
+    duplicates_window: settings::Var<Duration>,
+    length: Filter,
+    length_max: settings::Var<usize>,
+    emotes: Filter,
+    emotes_max: settings::Var<usize>,
+    /// The last message of each user, used to detect duplicates.
+    recent: Mutex<HashMap<String, Recent>>,
+}
+
+impl Filters {
+    /// Load spam filters from the given settings.
+    pub(crate) async fn load(settings: &settings::Settings) -> Result<Self> {
+        Ok(Self {
+            caps: Filter::load(
+                settings,
+                "caps",
+                "Please don't use excessive caps",
+                Scope::ChatBypassCaps,
+            )
+            .await?,
+            caps_min_length: settings.var("caps/min-length", 10).await?,
+            caps_max: settings.var("caps/max%", 70).await?,
+            symbols: Filter::load(
+                settings,
+                "symbols",
+                "Please don't use excessive symbols",
+                Scope::ChatBypassSymbols,
+            )
+            .await?,
+            symbols_min_length: settings.var("symbols/min-length", 10).await?,
+            symbols_max: settings.var("symbols/max%", 50).await?,
+            repetition: Filter::load(
+                settings,
+                "repetition",
+                "Please don't repeat yourself",
+                Scope::ChatBypassRepetition,
+            )
+            .await?,
+            repetition_max_chars: settings.var("repetition/max-chars", 10).await?,
+            repetition_max_words: settings.var("repetition/max-words", 5).await?,
+            duplicates: Filter::load(
+                settings,
+                "duplicates",
+                "Please don't post the same message over and over",
+                Scope::ChatBypassDuplicates,
+            )
+            .await?,
+            duplicates_max: settings.var("duplicates/max", 2).await?,
+            duplicates_window: settings
+                .var("duplicates/window", Duration::seconds(30))
+                .await?,
+            length: Filter::load(
+                settings,
+                "length",
+                "Please keep your messages shorter",
+                Scope::ChatBypassLength,
+            )
+            .await?,
+            length_max: settings.var("length/max", 400).await?,
+            emotes: Filter::load(
+                settings,
+                "emotes",
+                "Please don't use so many emotes",
+                Scope::ChatBypassEmotes,
+            )
+            .await?,
+            emotes_max: settings.var("emotes/max", 15).await?,
+            recent: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Test the message against all spam filters.
+    pub(crate) async fn test(&self, user: &User, message: &str) -> Option<Violation> {
+        if self.length.is_enabled().await && message.chars().count() > self.length_max.load().await
+        {
+            if let Some(violation) = self.length.violation(user).await {
+                return Some(violation);
+            }
+        }
+
+        if self.caps.is_enabled().await {
+            let (letters, upper) = caps(message);
+
+            if letters >= self.caps_min_length.load().await
+                && exceeds(upper, letters, self.caps_max.load().await)
+            {
+                if let Some(violation) = self.caps.violation(user).await {
+                    return Some(violation);
+                }
+            }
+        }
+
+        if self.symbols.is_enabled().await {
+            let (chars, symbols) = symbols(message);
+
+            if chars >= self.symbols_min_length.load().await
+                && exceeds(symbols, chars, self.symbols_max.load().await)
+            {
+                if let Some(violation) = self.symbols.violation(user).await {
+                    return Some(violation);
+                }
+            }
+        }
+
+        if self.repetition.is_enabled().await
+            && (repeated_chars(message) > self.repetition_max_chars.load().await
+                || repeated_words(message) > self.repetition_max_words.load().await)
+        {
+            if let Some(violation) = self.repetition.violation(user).await {
+                return Some(violation);
+            }
+        }
+
+        if self.emotes.is_enabled().await
+            && user.tags().emote_count() > self.emotes_max.load().await
+        {
+            if let Some(violation) = self.emotes.violation(user).await {
+                return Some(violation);
+            }
+        }
+
+        if self.duplicates.is_enabled().await {
+            if let Some(name) = user.name() {
+                let window = self.duplicates_window.load().await;
+                let count = self.record(name, message, window);
+
+                if count > self.duplicates_max.load().await {
+                    return self.duplicates.violation(user).await;
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Record the message from the given user and return how many times in a
+    /// row it has been posted within the window.
+    fn record(&self, name: &str, message: &str, window: Duration) -> u32 {
+        let now = Instant::now();
+        let window = window.as_std();
+        let message = message.trim().to_lowercase();
+
+        let mut recent = self.recent.lock();
+        recent.retain(|_, r| now - r.at < window);
+
+        match recent.get_mut(name) {
+            Some(r) if r.message == message => {
+                r.count += 1;
+                r.at = now;
+                r.count
+            }
+            _ => {
+                recent.insert(
+                    name.to_string(),
+                    Recent {
+                        message,
+                        count: 1,
+                        at: now,
+                    },
+                );
+
+                1
+            }
+        }
+    }
+}
+
+/// Test if `part` is more than `percentage` percent of `total`.
+fn exceeds(part: usize, total: usize, percentage: u32) -> bool {
+    total > 0 && part * 100 > total * percentage as usize
+}
+
+/// Count the number of letters in the message, and how many of them are
+/// uppercase.
+fn caps(message: &str) -> (usize, usize) {
+    let mut letters = 0;
+    let mut upper = 0;
+
+    for c in message.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+
+        if c.is_uppercase() {
+            upper += 1;
+        }
+    }
+
+    (letters, upper)
+}
+
+/// Count the number of non-whitespace characters in the message, and how many
+/// of them are symbols.
+fn symbols(message: &str) -> (usize, usize) {
+    let mut chars = 0;
+    let mut symbols = 0;
+
+    for c in message.chars().filter(|c| !c.is_whitespace()) {
+        chars += 1;
+
+        if !c.is_alphanumeric() {
+            symbols += 1;
+        }
+    }
+
+    (chars, symbols)
+}
+
+/// Find the longest run of the same character in the message.
+fn repeated_chars(message: &str) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    let mut last = None;
+
+    for c in message.chars().filter(|c| !c.is_whitespace()) {
+        if last == Some(c) {
+            current += 1;
+        } else {
+            current = 1;
+            last = Some(c);
+        }
+
+        longest = usize::max(longest, current);
+    }
+
+    longest
+}
+
+/// Find the number of times the most common word appears in the message.
+fn repeated_words(message: &str) -> usize {
+    let mut counts = HashMap::<_, usize>::new();
+
+    for word in message.split_whitespace() {
+        *counts.entry(word.to_lowercase()).or_default() += 1;
+    }
+
+    counts.values().copied().max().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{caps, exceeds, repeated_chars, repeated_words, symbols};
+
+    #[test]
+    fn test_heuristics() {
+        assert_eq!((10, 8), caps("HELLO THEre!"));
+        assert!(exceeds(7, 10, 60));
+        assert!(!exceeds(7, 10, 70));
+        assert!(!exceeds(0, 0, 0));
+
+        assert_eq!((10, 4), symbols("hey !!!! you"));
+        assert_eq!(4, repeated_chars("noooo wait"));
+        assert_eq!(5, repeated_chars("hi !! !!!"));
+        assert_eq!(3, repeated_words("spam Spam eggs spam"));
+        assert_eq!(0, repeated_words(""));
+    }
+}
@@ -0,0 +1,326 @@
+use irc::proto::message::Tag;
+use std::fmt;
+
+/// The plan of a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum SubPlan {
+    #[serde(rename = "prime")]
+    Prime,
+    #[serde(rename = "tier1")]
+    Tier1,
+    #[serde(rename = "tier2")]
+    Tier2,
+    #[serde(rename = "tier3")]
+    Tier3,
+}
+
+impl SubPlan {
+    /// Parse the plan from the `msg-param-sub-plan` tag.
+    fn parse(s: &str) -> Option<SubPlan> {
+        Some(match s {
+            "Prime" => SubPlan::Prime,
+            "1000" => SubPlan::Tier1,
+            "2000" => SubPlan::Tier2,
+            "3000" => SubPlan::Tier3,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for SubPlan {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            SubPlan::Prime => "Prime".fmt(fmt),
+            SubPlan::Tier1 => "Tier 1".fmt(fmt),
+            SubPlan::Tier2 => "Tier 2".fmt(fmt),
+            SubPlan::Tier3 => "Tier 3".fmt(fmt),
+        }
+    }
+}
+
+/// A typed USERNOTICE event.
+///
+/// See: https://dev.twitch.tv/docs/irc/tags#usernotice-twitch-tags
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+#[serde(tag = "type")]
+pub enum UserNotice {
+    /// A user subscribed for the first time.
+    #[serde(rename = "sub")]
+    Sub {
+        user: String,
+        display_name: Option<String>,
+        plan: SubPlan,
+        message: Option<String>,
+    },
+    /// A user resubscribed.
+    #[serde(rename = "resub")]
+    Resub {
+        user: String,
+        display_name: Option<String>,
+        plan: SubPlan,
+        /// Cumulative number of months subscribed.
+        months: u32,
+        /// Consecutive months subscribed, if the user shared it.
+        streak: Option<u32>,
+        message: Option<String>,
+    },
+    /// A user gifted a subscription to another user.
+    #[serde(rename = "subgift")]
+    SubGift {
+        user: String,
+        display_name: Option<String>,
+        plan: SubPlan,
+        recipient: String,
+        recipient_display_name: Option<String>,
+        /// Cumulative number of months the recipient has been subscribed.
+        months: u32,
+    },
+    /// A user gifted a number of subscriptions to random users in the channel.
+    #[serde(rename = "submysterygift")]
+    SubMysteryGift {
+        user: String,
+        display_name: Option<String>,
+        plan: SubPlan,
+        count: u32,
+    },
+    /// The channel is being raided.
+    #[serde(rename = "raid")]
+    Raid {
+        user: String,
+        display_name: Option<String>,
+        viewers: u32,
+    },
+    /// A ritual, like a new chatter.
+    #[serde(rename = "ritual")]
+    Ritual {
+        user: String,
+        display_name: Option<String>,
+        ritual: String,
+    },
+}
+
+impl UserNotice {
+    /// Parse a USERNOTICE from its tags and optional message.
+    ///
+    /// Returns `None` if the notice is of a kind we don't handle, or if it is
+    /// missing required tags.
+    #[allow(clippy::single_match)]
+    pub fn from_tags(tags: Option<Vec<Tag>>, message: Option<&str>) -> Option<UserNotice> {
+        let mut msg_id = None;
+        let mut login = None;
+        let mut display_name = None;
+        let mut plan = None;
+        let mut cumulative_months = None;
+        let mut months = None;
+        let mut streak_months = None;
+        let mut should_share_streak = false;
+        let mut recipient = None;
+        let mut recipient_display_name = None;
+        let mut mass_gift_count = None;
+        let mut viewer_count = None;
+        let mut ritual_name = None;
+
+        if let Some(tags) = tags {
+            for t in tags {
+                match t {
+                    Tag(name, Some(value)) => match name.as_str() {
+                        "msg-id" => msg_id = Some(value),
+                        "login" => login = Some(value),
+                        "display-name" => display_name = Some(value),
+                        "msg-param-sub-plan" => plan = SubPlan::parse(&value),
+                        "msg-param-cumulative-months" => cumulative_months = value.parse().ok(),
+                        "msg-param-months" => months = value.parse().ok(),
+                        "msg-param-streak-months" => streak_months = value.parse().ok(),
+                        "msg-param-should-share-streak" => should_share_streak = value == "1",
+                        "msg-param-recipient-user-name" => recipient = Some(value),
+                        "msg-param-recipient-display-name" => recipient_display_name = Some(value),
+                        "msg-param-mass-gift-count" => mass_gift_count = value.parse().ok(),
+                        "msg-param-viewerCount" => viewer_count = value.parse().ok(),
+                        "msg-param-ritual-name" => ritual_name = Some(value),
+                        _ => (),
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        let user = login?;
+        let message = message.map(String::from);
+
+        Some(match msg_id?.as_str() {
+            "sub" => UserNotice::Sub {
+                user,
+                display_name,
+                plan: plan?,
+                message,
+            },
+            "resub" => UserNotice::Resub {
+                user,
+                display_name,
+                plan: plan?,
+                months: cumulative_months.unwrap_or(1),
+                streak: if should_share_streak {
+                    streak_months
+                } else {
+                    None
+                },
+                message,
+            },
+            "subgift" | "anonsubgift" => UserNotice::SubGift {
+                user,
+                display_name,
+                plan: plan?,
+                recipient: recipient?,
+                recipient_display_name,
+                // NB: gift notices carry `msg-param-months`, not the
+                // cumulative months tag used by resubs.
+                months: months.unwrap_or(1),
+            },
+            "submysterygift" | "anonsubmysterygift" => UserNotice::SubMysteryGift {
+                user,
+                display_name,
+                plan: plan?,
+                count: mass_gift_count.unwrap_or(1),
+            },
+            "raid" => UserNotice::Raid {
+                user,
+                display_name,
+                viewers: viewer_count.unwrap_or_default(),
+            },
+            "ritual" => UserNotice::Ritual {
+                user,
+                display_name,
+                ritual: ritual_name?,
+            },
+            _ => return None,
+        })
+    }
+
+    /// The login of the user that caused the notice.
+    pub fn user(&self) -> &str {
+        match self {
+            UserNotice::Sub { user, .. } => user,
+            UserNotice::Resub { user, .. } => user,
+            UserNotice::SubGift { user, .. } => user,
+            UserNotice::SubMysteryGift { user, .. } => user,
+            UserNotice::Raid { user, .. } => user,
+            UserNotice::Ritual { user, .. } => user,
+        }
+    }
+
+    /// The display name of the user that caused the notice, falling back to
+    /// their login.
+    pub fn display_name(&self) -> &str {
+        let display_name = match self {
+            UserNotice::Sub { display_name, .. } => display_name,
+            UserNotice::Resub { display_name, .. } => display_name,
+            UserNotice::SubGift { display_name, .. } => display_name,
+            UserNotice::SubMysteryGift { display_name, .. } => display_name,
+            UserNotice::Raid { display_name, .. } => display_name,
+            UserNotice::Ritual { display_name, .. } => display_name,
+        };
+
+        display_name.as_deref().unwrap_or_else(|| self.user())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SubPlan, UserNotice};
+    use irc::proto::message::Tag;
+
+    fn tags(tags: &[(&str, &str)]) -> Option<Vec<Tag>> {
+        Some(
+            tags.iter()
+                .map(|(k, v)| Tag(k.to_string(), Some(v.to_string())))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_resub() {
+        let notice = UserNotice::from_tags(
+            tags(&[
+                ("msg-id", "resub"),
+                ("login", "setbac"),
+                ("display-name", "Setbac"),
+                ("msg-param-sub-plan", "2000"),
+                ("msg-param-cumulative-months", "7"),
+                ("msg-param-streak-months", "3"),
+                ("msg-param-should-share-streak", "1"),
+            ]),
+            Some("hello"),
+        );
+
+        assert_eq!(
+            Some(UserNotice::Resub {
+                user: String::from("setbac"),
+                display_name: Some(String::from("Setbac")),
+                plan: SubPlan::Tier2,
+                months: 7,
+                streak: Some(3),
+                message: Some(String::from("hello")),
+            }),
+            notice
+        );
+    }
+
+    #[test]
+    fn test_subgift() {
+        let notice = UserNotice::from_tags(
+            tags(&[
+                ("msg-id", "subgift"),
+                ("login", "gifter"),
+                ("display-name", "Gifter"),
+                ("msg-param-sub-plan", "1000"),
+                ("msg-param-months", "4"),
+                ("msg-param-recipient-user-name", "lucky"),
+                ("msg-param-recipient-display-name", "Lucky"),
+            ]),
+            None,
+        );
+
+        assert_eq!(
+            Some(UserNotice::SubGift {
+                user: String::from("gifter"),
+                display_name: Some(String::from("Gifter")),
+                plan: SubPlan::Tier1,
+                recipient: String::from("lucky"),
+                recipient_display_name: Some(String::from("Lucky")),
+                months: 4,
+            }),
+            notice
+        );
+    }
+
+    #[test]
+    fn test_raid() {
+        let notice = UserNotice::from_tags(
+            tags(&[
+                ("msg-id", "raid"),
+                ("login", "raider"),
+                ("msg-param-viewerCount", "42"),
+            ]),
+            None,
+        );
+
+        assert_eq!(
+            Some(UserNotice::Raid {
+                user: String::from("raider"),
+                display_name: None,
+                viewers: 42,
+            }),
+            notice
+        );
+    }
+
+    #[test]
+    fn test_unsupported() {
+        let notice = UserNotice::from_tags(
+            tags(&[("msg-id", "bitsbadgetier"), ("login", "someone")]),
+            None,
+        );
+
+        assert_eq!(None, notice);
+    }
+}
@@ -441,6 +441,7 @@ async fn try_main(
     injector.update(settings.clone()).await;
 
     let bad_words = db::Words::load(db.clone()).await?;
+    injector.update(bad_words.clone()).await;
 
     injector
         .update(db::AfterStreams::load(db.clone()).await?)
@@ -451,10 +452,25 @@ async fn try_main(
         .update(db::Promotions::load(db.clone()).await?)
         .await;
     injector.update(db::Themes::load(db.clone()).await?).await;
+    injector.update(db::Raffles::load(db.clone()).await?).await;
+    injector.update(db::Shop::load(db.clone()).await?).await;
+    injector.update(db::Polls::load(db.clone()).await?).await;
+    injector
+        .update(db::Predictions::load(db.clone()).await?)
+        .await;
+    injector.update(db::Queue::load(db.clone()).await?).await;
+    injector.update(db::Quotes::load(db.clone()).await?).await;
+    injector.update(db::Counters::load(db.clone()).await?).await;
+    injector.update(db::Offences::load(db.clone()).await?).await;
+    injector.update(db::HeistStakes::load(db.clone()).await?).await;
+    injector
+        .update(db::ModerationLog::load(db.clone()).await?)
+        .await;
 
     let message_bus = Arc::new(bus::Bus::new());
     let global_bus = Arc::new(bus::Bus::new());
     let youtube_bus = Arc::new(bus::Bus::new());
+    let queue_bus = Arc::new(bus::Bus::new());
     let global_channel = injector::Var::new(None);
     let command_bus = Arc::new(bus::Bus::new());
 
@@ -492,6 +508,7 @@ async fn try_main(
         message_bus.clone(),
         global_bus.clone(),
         youtube_bus.clone(),
+        queue_bus.clone(),
         command_bus.clone(),
         auth.clone(),
         global_channel.clone(),
@@ -703,7 +720,17 @@ async fn try_main(
     modules.push(Box::new(module::auth::Module));
     modules.push(Box::new(module::poll::Module));
     modules.push(Box::new(module::weather::Module));
+    modules.push(Box::new(module::gamble::Module));
+    modules.push(Box::new(module::heist::Module));
+    modules.push(Box::new(module::raffle::Module));
+    modules.push(Box::new(module::shop::Module));
+    modules.push(Box::new(module::prediction::Module));
+    modules.push(Box::new(module::queue::Module));
+    modules.push(Box::new(module::quotes::Module));
+    modules.push(Box::new(module::counter::Module));
+    modules.push(Box::new(module::bad_word::Module));
     modules.push(Box::new(module::help::Module));
+    modules.push(Box::new(module::events::Module));
 
     let (stream_state_tx, stream_state_rx) = mpsc::channel(64);
 
@@ -718,6 +745,7 @@ async fn try_main(
         db: db.clone(),
         bad_words,
         global_bus,
+        queue_bus,
         command_bus,
         modules,
         restart,
@@ -3,6 +3,7 @@ use crate::command;
 use crate::db;
 use crate::module;
 use crate::prelude::*;
+use crate::utils;
 use anyhow::Result;
 
 /// Handler for the !alias command.
@@ -55,10 +56,40 @@ impl command::Handler for Handler {
 
                 respond!(ctx, "Edited pattern for alias.");
             }
+            Some("cooldown") => {
+                command_restriction!(
+                    ctx,
+                    aliases,
+                    "alias",
+                    AliasEdit,
+                    cooldown,
+                    str::parse::<utils::Duration>
+                );
+            }
+            Some("user-cooldown") => {
+                command_restriction!(
+                    ctx,
+                    aliases,
+                    "alias",
+                    AliasEdit,
+                    user_cooldown,
+                    str::parse::<utils::Duration>
+                );
+            }
+            Some("scope") => {
+                command_restriction!(
+                    ctx,
+                    aliases,
+                    "alias",
+                    AliasEdit,
+                    scope,
+                    db::Restrictions::parse_scope
+                );
+            }
             None | Some(..) => {
                 respond!(
                     ctx,
-                    "Expected: show, list, edit, delete, enable, disable, or group."
+                    "Expected: show, list, edit, delete, enable, disable, group, cooldown, user-cooldown, or scope."
                 );
             }
         }
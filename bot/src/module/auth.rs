@@ -1,14 +1,45 @@
 use crate::auth;
 use crate::command;
+use crate::db;
 use crate::module;
 use crate::prelude::*;
-use crate::utils::Duration;
+use crate::utils::{self, Duration};
 use anyhow::Result;
 use chrono::Utc;
 
 /// Handler for the !auth command.
 pub struct Handler {
     auth: auth::Auth,
+    moderation_log: injector::Var<Option<db::ModerationLog>>,
+}
+
+impl Handler {
+    /// Record a manual moderation action, like a permit.
+    async fn record(
+        &self,
+        ctx: &command::Context,
+        action: &str,
+        user: String,
+        rule: Option<String>,
+    ) {
+        let moderation_log = match self.moderation_log.load().await {
+            Some(moderation_log) => moderation_log,
+            None => return,
+        };
+
+        let entry = db::InsertModerationLogEntry {
+            channel: ctx.channel().to_string(),
+            action: action.to_string(),
+            user,
+            message: None,
+            rule,
+            actor: ctx.user.name().map(|s| s.to_string()),
+        };
+
+        if let Err(e) = moderation_log.record(entry).await {
+            log_error!(e, "failed to record moderation action");
+        }
+    }
 }
 
 #[async_trait]
@@ -60,9 +91,14 @@ impl command::Handler for Handler {
             Some("permit") => {
                 ctx.check_scope(auth::Scope::AuthPermit).await?;
 
-                let duration: Duration = ctx.next_parse("<duration> <principal> <scope>")?;
-                let principal = ctx.next_parse("<duration> <principal> <scope>")?;
-                let scope = ctx.next_parse("<duration> <principal> <scope>")?;
+                let duration: Duration = ctx.next_parse("<duration> <principal> <scope> [uses]")?;
+                let principal = ctx.next_parse("<duration> <principal> <scope> [uses]")?;
+                let scope = ctx.next_parse("<duration> <principal> <scope> [uses]")?;
+                let uses = ctx.next_parse_optional::<u32>()?;
+
+                if uses == Some(0) {
+                    respond_bail!("Number of uses must be greater than zero");
+                }
 
                 if !ctx.user.has_scope(scope).await {
                     respond!(
@@ -76,20 +112,94 @@ impl command::Handler for Handler {
                 let now = Utc::now();
                 let expires_at = now + duration.as_chrono();
 
-                respond!(
+                let grant = self
+                    .auth
+                    .insert_temporary(scope, principal, expires_at, uses)
+                    .await?;
+
+                self.record(
                     ctx,
-                    "Gave: {scope} to {principal} for {duration}",
-                    duration = duration,
-                    principal = principal,
-                    scope = scope
-                );
-
-                self.auth
-                    .insert_temporary(scope, principal, expires_at)
-                    .await;
+                    "permit",
+                    grant.principal.to_string(),
+                    Some(grant.scope.to_string()),
+                )
+                .await;
+
+                match grant.uses {
+                    Some(uses) => respond!(
+                        ctx,
+                        "Gave: {scope} to {principal} for {duration} or {uses} use(s) (#{id})",
+                        duration = duration,
+                        principal = grant.principal,
+                        scope = grant.scope,
+                        uses = uses,
+                        id = grant.id,
+                    ),
+                    None => respond!(
+                        ctx,
+                        "Gave: {scope} to {principal} for {duration} (#{id})",
+                        duration = duration,
+                        principal = grant.principal,
+                        scope = grant.scope,
+                        id = grant.id,
+                    ),
+                }
+            }
+            Some("permits") => {
+                ctx.check_scope(auth::Scope::AuthPermit).await?;
+
+                let now = Utc::now();
+
+                let grants = self.auth.list_temporary().await.into_iter().map(|g| {
+                    let remaining = (g.expires_at - now).to_std().unwrap_or_default();
+
+                    let uses = match g.uses {
+                        Some(uses) => format!(", {} use(s) left", uses),
+                        None => String::new(),
+                    };
+
+                    format!(
+                        "#{id}: {scope} to {principal} ({remaining} left{uses})",
+                        id = g.id,
+                        scope = g.scope,
+                        principal = g.principal,
+                        remaining = utils::compact_duration(remaining),
+                        uses = uses,
+                    )
+                });
+
+                ctx.respond_lines(grants, "*no temporary grants*").await;
+            }
+            Some("revoke") => {
+                ctx.check_scope(auth::Scope::AuthPermit).await?;
+
+                let id = ctx.next_parse::<i32, _>("<id>")?;
+
+                let grant = self
+                    .auth
+                    .list_temporary()
+                    .await
+                    .into_iter()
+                    .find(|g| g.id == id);
+
+                if self.auth.delete_temporary(id).await? {
+                    if let Some(grant) = grant {
+                        self.record(
+                            ctx,
+                            "revoke",
+                            grant.principal.to_string(),
+                            Some(grant.scope.to_string()),
+                        )
+                        .await;
+                    }
+
+                    respond!(ctx, "Revoked temporary grant #{}", id);
+                } else {
+                    respond!(ctx, "No temporary grant with id #{}", id);
+                }
             }
             _ => {
-                respond!(ctx, "Expected: scopes, permit");
+                respond!(ctx, "Expected: scopes, permit, permits, revoke");
             }
         }
 
@@ -113,9 +223,20 @@ impl super::Module for Module {
 
     async fn hook(
         &self,
-        module::HookContext { handlers, auth, .. }: module::HookContext<'_>,
+        module::HookContext {
+            injector,
+            handlers,
+            auth,
+            ..
+        }: module::HookContext<'_>,
     ) -> Result<()> {
-        handlers.insert("auth", Handler { auth: auth.clone() });
+        handlers.insert(
+            "auth",
+            Handler {
+                auth: auth.clone(),
+                moderation_log: injector.var().await?,
+            },
+        );
         Ok(())
     }
 }
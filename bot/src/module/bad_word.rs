@@ -0,0 +1,102 @@
+use crate::auth;
+use crate::command;
+use crate::db;
+use crate::module;
+use crate::prelude::*;
+use anyhow::Result;
+
+/// Handler for the !badword command.
+pub struct BadWord {
+    bad_words: injector::Var<Option<db::Words>>,
+}
+
+#[async_trait]
+impl command::Handler for BadWord {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::BadWordEdit)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        let bad_words = match self.bad_words.load().await {
+            Some(bad_words) => bad_words,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some(kind @ "word") | Some(kind @ "regex") | Some(kind @ "glob") => {
+                let kind = str::parse::<db::WordKind>(kind)?;
+                let word = ctx.next_str("<word> [why]")?;
+
+                let why = match ctx.rest().trim() {
+                    "" => None,
+                    why => Some(why),
+                };
+
+                if let Err(e) = bad_words.edit(&word, kind, why).await {
+                    respond_bail!("Bad {} `{}`: {}", kind, word, e);
+                }
+
+                respond!(ctx, "Added bad {} `{}`", kind, word);
+            }
+            Some("delete") => {
+                let word = ctx.next_str("<word>")?;
+
+                if bad_words.delete(&word).await? {
+                    respond!(ctx, "Deleted bad word `{}`", word);
+                } else {
+                    respond!(ctx, "No bad word `{}`", word);
+                }
+            }
+            Some("test") => {
+                let message = ctx.rest().trim().to_string();
+
+                if message.is_empty() {
+                    respond_bail!("Expected: <message>");
+                }
+
+                let word = bad_words.tester().await.test_message(&message);
+
+                match word {
+                    Some(word) => respond!(
+                        ctx,
+                        "Message would be moderated by bad {} `{}`",
+                        word.kind,
+                        word.word
+                    ),
+                    None => respond!(ctx, "Message doesn't contain any bad words"),
+                }
+            }
+            _ => {
+                respond!(ctx, "Expected: word, regex, glob, delete, or test.");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "badword"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector, handlers, ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "badword",
+            BadWord {
+                bad_words: injector.var().await?,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -3,6 +3,8 @@ use crate::command;
 use crate::db;
 use crate::module;
 use crate::prelude::*;
+use crate::template::Template;
+use crate::utils;
 
 pub struct Handler {
     pub enabled: settings::Var<bool>,
@@ -32,8 +34,35 @@ impl command::Handler for Handler {
                 ctx.check_scope(auth::Scope::CommandEdit).await?;
 
                 let name = ctx.next_str("<name>")?;
-                let template = ctx.rest_parse("<name> <template>")?;
-                commands.edit(ctx.channel(), &name, template).await?;
+                let rest = ctx.rest().trim();
+
+                // NB: arguments are specified before a `--` separator, like
+                // `!command edit so {user} -- Go check out {{user}}!`. They are
+                // left alone if there is no separator, and removed if nothing
+                // precedes it.
+                let (args, template) = match rest.find(" -- ") {
+                    _ if rest.starts_with("-- ") => (Some(None), &rest[3..]),
+                    Some(n) if db::CommandArgs::is_spec(rest) => {
+                        let args = match str::parse::<db::CommandArgs>(&rest[..n]) {
+                            Ok(args) => args,
+                            Err(e) => respond_bail!("Bad arguments: {}", e),
+                        };
+
+                        (Some(Some(args)), &rest[n + 4..])
+                    }
+                    _ => (None, rest),
+                };
+
+                if template.trim().is_empty() {
+                    respond_bail!("Expected <name> [<arguments> --] <template>");
+                }
+
+                let template = match str::parse::<Template>(template.trim()) {
+                    Ok(template) => template,
+                    Err(e) => respond_bail!("Bad template: {}", e),
+                };
+
+                commands.edit(ctx.channel(), &name, template, args).await?;
 
                 respond!(ctx, "Edited command.");
             }
@@ -62,10 +91,71 @@ impl command::Handler for Handler {
 
                 respond!(ctx, "Edited pattern for command.");
             }
+            Some("cost") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [amount|none]")?;
+
+                let cost = match ctx.next().as_deref() {
+                    Some("none") => None,
+                    Some(cost) => match str::parse::<i64>(cost) {
+                        Ok(cost) => Some(cost),
+                        Err(_) => respond_bail!("Expected <amount> to be a number"),
+                    },
+                    None => {
+                        match commands.get_any(ctx.channel(), &name).await? {
+                            Some(command) => match command.cost {
+                                Some(cost) => respond!(ctx, "Command `{}` costs {}", name, cost),
+                                None => respond!(ctx, "Command `{}` is free", name),
+                            },
+                            None => respond!(ctx, "No command named `{}`.", name),
+                        }
+
+                        return Ok(());
+                    }
+                };
+
+                if !commands.edit_cost(ctx.channel(), &name, cost).await? {
+                    respond!(ctx, "No command named `{}`.", name);
+                    return Ok(());
+                }
+
+                respond!(ctx, "Edited cost for command.");
+            }
+            Some("cooldown") => {
+                command_restriction!(
+                    ctx,
+                    commands,
+                    "command",
+                    CommandEdit,
+                    cooldown,
+                    str::parse::<utils::Duration>
+                );
+            }
+            Some("user-cooldown") => {
+                command_restriction!(
+                    ctx,
+                    commands,
+                    "command",
+                    CommandEdit,
+                    user_cooldown,
+                    str::parse::<utils::Duration>
+                );
+            }
+            Some("scope") => {
+                command_restriction!(
+                    ctx,
+                    commands,
+                    "command",
+                    CommandEdit,
+                    scope,
+                    db::Restrictions::parse_scope
+                );
+            }
             None | Some(..) => {
                 respond!(
                     ctx,
-                    "Expected: show, list, edit, delete, enable, disable, or group."
+                    "Expected: show, list, edit, delete, enable, disable, group, cooldown, user-cooldown, scope, or cost."
                 );
             }
         }
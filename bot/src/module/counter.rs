@@ -0,0 +1,260 @@
+use crate::auth;
+use crate::command;
+use crate::db;
+use crate::module;
+use crate::prelude::*;
+use crate::template::Template;
+use anyhow::Result;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+static DEFAULT_FILE_TEMPLATE: &str = "{{value}}";
+
+/// Handler for the !counter command.
+pub struct Counter {
+    enabled: settings::Var<bool>,
+    counters: injector::Var<Option<db::Counters>>,
+    directory: settings::Var<Option<PathBuf>>,
+    file_template: settings::Var<Template>,
+}
+
+impl Counter {
+    /// Write the given counter to its file, if counter files are enabled.
+    async fn write(&self, counter: &db::Counter) {
+        let directory = match self.directory.load().await {
+            Some(directory) => directory,
+            None => return,
+        };
+
+        let template = self.file_template.load().await;
+        write_file(&directory, &template, counter);
+    }
+}
+
+#[async_trait]
+impl command::Handler for Counter {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Counter)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let counters = match self.counters.load().await {
+            Some(counters) => counters,
+            None => return Ok(()),
+        };
+
+        let name = match ctx.next() {
+            Some(name) => name.to_lowercase(),
+            None => {
+                let list = counters
+                    .list(ctx.channel())
+                    .await?
+                    .into_iter()
+                    .map(|c| format!("{} = {}", c.name, c.value))
+                    .collect::<Vec<_>>();
+
+                if list.is_empty() {
+                    respond_bail!("There are no counters");
+                }
+
+                respond!(ctx, "{}", list.join(", "));
+                return Ok(());
+            }
+        };
+
+        let action = match ctx.next() {
+            Some(action) => action,
+            None => {
+                match counters.get(ctx.channel(), &name).await? {
+                    Some(counter) => respond!(ctx, "{} = {}", counter.name, counter.value),
+                    None => respond!(ctx, "No counter named `{}`", name),
+                }
+
+                return Ok(());
+            }
+        };
+
+        let existing = counters.get(ctx.channel(), &name).await?;
+
+        // NB: counters can only be created and reconfigured by editors, but
+        // updating a counter can be opened up to others through its scope.
+        let scope = existing
+            .as_ref()
+            .and_then(|c| c.scope.as_ref())
+            .filter(|_| action != "scope" && action != "delete");
+
+        match scope {
+            Some(scope) if !ctx.user.has_scope(auth::Scope::CounterEdit).await => {
+                ctx.check_scope(str::parse::<auth::Scope>(scope)?).await?;
+            }
+            _ => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+            }
+        }
+
+        if existing.is_none() && !is_valid_name(&name) {
+            respond_bail!(
+                "Counter names may only contain letters, numbers, dashes, and underscores"
+            );
+        }
+
+        let counter = match action.as_str() {
+            "+" | "-" => {
+                let amount = ctx.next_parse_optional::<i64>()?.unwrap_or(1);
+                let amount = if action == "-" { -amount } else { amount };
+                counters.add(ctx.channel(), &name, amount).await?
+            }
+            "set" => {
+                let value = ctx.next_parse::<i64, _>("<value>")?;
+                counters.set(ctx.channel(), &name, value).await?
+            }
+            "reset" => counters.set(ctx.channel(), &name, 0).await?,
+            "scope" => {
+                let scope = ctx.next_str("<scope|none>")?;
+
+                let scope = match scope.as_str() {
+                    "none" => None,
+                    scope => {
+                        if str::parse::<auth::Scope>(scope)? == auth::Scope::Unknown {
+                            respond_bail!("No scope named `{}`", scope);
+                        }
+
+                        Some(scope.to_string())
+                    }
+                };
+
+                let counter = counters.set_scope(ctx.channel(), &name, scope).await?;
+
+                match &counter.scope {
+                    Some(scope) => respond!(
+                        ctx,
+                        "Counter `{}` can now be updated with the `{}` scope",
+                        name,
+                        scope
+                    ),
+                    None => respond!(ctx, "Counter `{}` can only be updated by editors", name),
+                }
+
+                return Ok(());
+            }
+            "delete" => {
+                if counters.delete(ctx.channel(), &name).await? {
+                    respond!(ctx, "Deleted counter `{}`", name);
+                } else {
+                    respond!(ctx, "No counter named `{}`", name);
+                }
+
+                return Ok(());
+            }
+            _ => {
+                respond_bail!("Expected: +, -, set, reset, scope, or delete.");
+            }
+        };
+
+        self.write(&counter).await;
+        respond!(ctx, "{} = {}", counter.name, counter.value);
+        Ok(())
+    }
+}
+
+/// Test if the given name can be used for a counter.
+///
+/// Names are used as file names, so they are restricted to a safe subset.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Write the value of a counter to `<directory>/<name>.txt`. Log any errors.
+fn write_file(directory: &Path, template: &Template, counter: &db::Counter) {
+    let path = directory.join(format!("{}.txt", counter.name));
+
+    let result = File::create(&path)
+        .map_err(anyhow::Error::from)
+        .and_then(|mut f| template.render(&mut f, counter));
+
+    if let Err(e) = result {
+        log::warn!("failed to write counter: {}: {}", path.display(), e);
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "counter"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            futures,
+            sender,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("counter");
+        let default_template = Template::compile(DEFAULT_FILE_TEMPLATE)?;
+
+        let counters: injector::Var<Option<db::Counters>> = injector.var().await?;
+
+        handlers.insert(
+            "counter",
+            Counter {
+                enabled: settings.var("enabled", false).await?,
+                counters: counters.clone(),
+                directory: settings.optional("directory").await?,
+                file_template: settings
+                    .var("file-template", default_template.clone())
+                    .await?,
+            },
+        );
+
+        let (mut directory_stream, mut directory) =
+            settings.stream::<PathBuf>("directory").optional().await?;
+        let (mut template_stream, mut template) = settings
+            .stream("file-template")
+            .or_with(default_template)
+            .await?;
+        let channel = sender.channel().to_string();
+
+        // NB: rewrite all counter files when the configuration changes.
+        let future = async move {
+            loop {
+                if let (Some(directory), Some(counters)) = (&directory, counters.load().await) {
+                    match counters.list(&channel).await {
+                        Ok(list) => {
+                            for counter in &list {
+                                write_file(directory, &template, counter);
+                            }
+                        }
+                        Err(e) => log_error!(e, "failed to list counters"),
+                    }
+                }
+
+                futures::select! {
+                    update = directory_stream.select_next_some() => {
+                        directory = update;
+                    }
+                    update = template_stream.select_next_some() => {
+                        template = update;
+                    }
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
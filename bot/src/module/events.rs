@@ -0,0 +1,229 @@
+use crate::bus::{self, Message as _};
+use crate::irc;
+use crate::module;
+use crate::prelude::*;
+use crate::template::Template;
+use anyhow::Result;
+
+/// Templated responses to chat events like subscriptions, raids, and cheers.
+struct Events {
+    enabled: settings::Var<bool>,
+    sub: settings::Var<Option<Template>>,
+    resub: settings::Var<Option<Template>>,
+    subgift: settings::Var<Option<Template>>,
+    submysterygift: settings::Var<Option<Template>>,
+    raid: settings::Var<Option<Template>>,
+    raid_min_viewers: settings::Var<u32>,
+    ritual: settings::Var<Option<Template>>,
+    cheer: settings::Var<Option<Template>>,
+    cheer_min_bits: settings::Var<u32>,
+}
+
+impl Events {
+    /// Handle a single event from the global bus.
+    async fn handle(&self, sender: &irc::Sender, event: bus::Global) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        // NB: all channels publish their events on the same bus.
+        if event.channel() != Some(sender.channel()) {
+            return Ok(());
+        }
+
+        let (template, vars) = match event {
+            bus::Global::UserNotice { notice, .. } => {
+                let name = notice.display_name().to_string();
+
+                match notice {
+                    irc::UserNotice::Sub { plan, message, .. } => (
+                        self.sub.load().await,
+                        Vars {
+                            name,
+                            plan: Some(plan.to_string()),
+                            months: Some(1),
+                            message,
+                            ..Vars::default()
+                        },
+                    ),
+                    irc::UserNotice::Resub {
+                        plan,
+                        months,
+                        streak,
+                        message,
+                        ..
+                    } => (
+                        self.resub.load().await,
+                        Vars {
+                            name,
+                            plan: Some(plan.to_string()),
+                            months: Some(months),
+                            streak,
+                            message,
+                            ..Vars::default()
+                        },
+                    ),
+                    irc::UserNotice::SubGift {
+                        plan,
+                        recipient,
+                        recipient_display_name,
+                        months,
+                        ..
+                    } => (
+                        self.subgift.load().await,
+                        Vars {
+                            name,
+                            plan: Some(plan.to_string()),
+                            months: Some(months),
+                            recipient: Some(recipient_display_name.unwrap_or(recipient)),
+                            ..Vars::default()
+                        },
+                    ),
+                    irc::UserNotice::SubMysteryGift { plan, count, .. } => (
+                        self.submysterygift.load().await,
+                        Vars {
+                            name,
+                            plan: Some(plan.to_string()),
+                            count: Some(count),
+                            ..Vars::default()
+                        },
+                    ),
+                    irc::UserNotice::Raid { viewers, .. } => {
+                        if viewers < self.raid_min_viewers.load().await {
+                            return Ok(());
+                        }
+
+                        (
+                            self.raid.load().await,
+                            Vars {
+                                name,
+                                viewers: Some(viewers),
+                                ..Vars::default()
+                            },
+                        )
+                    }
+                    irc::UserNotice::Ritual { ritual, .. } => (
+                        self.ritual.load().await,
+                        Vars {
+                            name,
+                            ritual: Some(ritual),
+                            ..Vars::default()
+                        },
+                    ),
+                }
+            }
+            bus::Global::Cheer {
+                user,
+                display_name,
+                bits,
+                message,
+                ..
+            } => {
+                if bits < self.cheer_min_bits.load().await {
+                    return Ok(());
+                }
+
+                (
+                    self.cheer.load().await,
+                    Vars {
+                        name: display_name.unwrap_or(user),
+                        bits: Some(bits),
+                        message: Some(message),
+                        ..Vars::default()
+                    },
+                )
+            }
+            _ => return Ok(()),
+        };
+
+        if let Some(template) = template {
+            let response = template.render_to_string(&vars)?;
+            sender.privmsg(response).await;
+        }
+
+        Ok(())
+    }
+}
+
+/// Variables available to event templates.
+#[derive(Debug, Default, serde::Serialize)]
+struct Vars {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plan: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    months: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    streak: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    recipient: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    viewers: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ritual: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bits: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "events"
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            futures,
+            sender,
+            settings,
+            global_bus,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("events");
+
+        let events = Events {
+            enabled: settings.var("enabled", false).await?,
+            sub: settings.optional("sub/message").await?,
+            resub: settings.optional("resub/message").await?,
+            subgift: settings.optional("subgift/message").await?,
+            submysterygift: settings.optional("submysterygift/message").await?,
+            raid: settings.optional("raid/message").await?,
+            raid_min_viewers: settings.var("raid/min-viewers", 0).await?,
+            ritual: settings.optional("ritual/message").await?,
+            cheer: settings.optional("cheer/message").await?,
+            cheer_min_bits: settings.var("cheer/min-bits", 0).await?,
+        };
+
+        let sender = sender.clone();
+        let mut rx = global_bus.subscribe();
+
+        let future = async move {
+            while let Some(event) = rx.next().await {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(e) => {
+                        log::warn!("failed to receive chat event: {}", e);
+                        continue;
+                    }
+                };
+
+                if let Err(e) = events.handle(&sender, event).await {
+                    log_error!(e, "failed to respond to chat event");
+                }
+            }
+
+            Ok(())
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
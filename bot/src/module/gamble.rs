@@ -0,0 +1,632 @@
+use crate::auth;
+use crate::command;
+use crate::currency::{BalanceTransferError, Currency, Reason, DUEL_ESCROW as ESCROW};
+use crate::db;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// A bet as specified by a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bet {
+    /// A fixed amount.
+    Amount(i64),
+    /// Everything the user has.
+    All,
+    /// A percentage of what the user has.
+    Percentage(u32),
+}
+
+impl Bet {
+    /// Resolve the bet against the given balance.
+    pub fn resolve(self, balance: i64) -> i64 {
+        match self {
+            Bet::Amount(amount) => amount,
+            Bet::All => balance,
+            Bet::Percentage(p) => (balance.max(0) * p as i64) / 100,
+        }
+    }
+}
+
+impl std::str::FromStr for Bet {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "all" {
+            return Ok(Bet::All);
+        }
+
+        if s.ends_with('%') {
+            let p = str::parse::<u32>(&s[..s.len() - 1])?;
+
+            if p > 100 {
+                bail!("percentage must be at most 100%");
+            }
+
+            return Ok(Bet::Percentage(p));
+        }
+
+        let amount = str::parse::<i64>(s)?;
+
+        if amount <= 0 {
+            bail!("bet must be positive");
+        }
+
+        Ok(Bet::Amount(amount))
+    }
+}
+
+impl fmt::Display for Bet {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Bet::Amount(amount) => amount.fmt(fmt),
+            Bet::All => "all".fmt(fmt),
+            Bet::Percentage(p) => write!(fmt, "{}%", p),
+        }
+    }
+}
+
+/// Settings and state shared by all games.
+#[derive(Clone)]
+struct Common {
+    enabled: settings::Var<bool>,
+    currency: injector::Var<Option<Currency>>,
+    min_bet: settings::Var<i64>,
+    max_bet: settings::Var<i64>,
+}
+
+impl Common {
+    /// Get the currency if the games are enabled.
+    async fn currency(&self, ctx: &command::Context) -> Result<Option<Currency>> {
+        if !self.enabled.load().await {
+            return Ok(None);
+        }
+
+        match self.currency.load().await {
+            Some(currency) => Ok(Some(currency)),
+            None => {
+                respond!(ctx, "No currency configured for stream, sorry :(");
+                Ok(None)
+            }
+        }
+    }
+
+    /// Resolve the bet of the given user and validate it against their
+    /// balance and the configured limits.
+    async fn bet(&self, currency: &Currency, channel: &str, user: &str, bet: Bet) -> Result<i64> {
+        let balance = currency
+            .balance_of(channel, user)
+            .await?
+            .unwrap_or_default()
+            .balance;
+
+        let amount = bet.resolve(balance);
+        let min_bet = self.min_bet.load().await;
+        let max_bet = self.max_bet.load().await;
+
+        if amount < min_bet.max(1) {
+            respond_bail!(
+                "You need to bet at least {} {}",
+                min_bet.max(1),
+                currency.name
+            );
+        }
+
+        if max_bet > 0 && amount > max_bet {
+            respond_bail!("You can bet at most {} {}", max_bet, currency.name);
+        }
+
+        if amount > balance {
+            respond_bail!(
+                "You don't have enough {currency} to bet {amount} (balance: {balance})",
+                currency = currency.name,
+                amount = amount,
+                balance = balance,
+            );
+        }
+
+        Ok(amount)
+    }
+}
+
+/// Per-user cooldowns for a single game.
+struct Cooldowns {
+    cooldown: settings::Var<utils::Duration>,
+    last: Mutex<HashMap<String, Instant>>,
+}
+
+impl Cooldowns {
+    /// Check the cooldown for the given user, and mark them as having played
+    /// if it's open.
+    async fn check(&self, user: &str) -> Result<()> {
+        let cooldown = self.cooldown.load().await.as_std();
+        let now = Instant::now();
+        let mut last = self.last.lock().await;
+
+        if let Some(last) = last.get(user) {
+            let elapsed = now.duration_since(*last);
+
+            if elapsed < cooldown {
+                respond_bail!(
+                    "You need to wait {} before playing again",
+                    utils::compact_duration(cooldown - elapsed)
+                );
+            }
+        }
+
+        last.insert(user.to_string(), now);
+        Ok(())
+    }
+}
+
+/// Handler for the !roulette command.
+pub struct Roulette {
+    common: Common,
+    cooldowns: Cooldowns,
+    win_chance: settings::Var<u32>,
+    payout: settings::Var<u32>,
+}
+
+#[async_trait]
+impl command::Handler for Roulette {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::GambleRoulette)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        use rand::Rng as _;
+
+        let currency = match self.common.currency(ctx).await? {
+            Some(currency) => currency,
+            None => return Ok(()),
+        };
+
+        let bet = ctx.next_parse("<amount|all|50%>")?;
+
+        let user = match ctx.user.real() {
+            Some(user) => user,
+            None => respond_bail!("Only real users can play roulette"),
+        };
+        let amount = self
+            .common
+            .bet(&currency, user.channel(), user.name(), bet)
+            .await?;
+        self.cooldowns.check(user.name()).await?;
+
+        let win_chance = self.win_chance.load().await.min(100);
+
+        if rand::thread_rng().gen_range(0, 100) < win_chance {
+            let won = (amount * self.payout.load().await as i64) / 100;
+
+            currency
+                .balance_add(user.channel(), user.name(), won, Reason::Gamble)
+                .await?;
+
+            respond!(
+                user,
+                "The wheel stops on your number! You won {won} {currency} PogChamp",
+                won = won,
+                currency = currency.name,
+            );
+        } else {
+            currency
+                .balance_add(user.channel(), user.name(), -amount, Reason::Gamble)
+                .await?;
+
+            respond!(
+                user,
+                "The house wins. You lost {amount} {currency} BibleThump",
+                amount = amount,
+                currency = currency.name,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the !slots command.
+pub struct Slots {
+    common: Common,
+    cooldowns: Cooldowns,
+    reels: settings::Var<HashSet<String>>,
+    pair_payout: settings::Var<u32>,
+    jackpot_payout: settings::Var<u32>,
+}
+
+#[async_trait]
+impl command::Handler for Slots {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::GambleSlots)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        use rand::seq::SliceRandom as _;
+
+        let currency = match self.common.currency(ctx).await? {
+            Some(currency) => currency,
+            None => return Ok(()),
+        };
+
+        let bet = match ctx.next_parse_optional::<Bet>()? {
+            Some(bet) => bet,
+            None => Bet::Amount(self.common.min_bet.load().await.max(1)),
+        };
+
+        let user = match ctx.user.real() {
+            Some(user) => user,
+            None => respond_bail!("Only real users can play the slots"),
+        };
+
+        let reels = self.reels.load().await.into_iter().collect::<Vec<_>>();
+
+        if reels.is_empty() {
+            respond_bail!("The slot machine doesn't have any reels configured");
+        }
+
+        let amount = self
+            .common
+            .bet(&currency, user.channel(), user.name(), bet)
+            .await?;
+        self.cooldowns.check(user.name()).await?;
+
+        let spin = {
+            let mut rng = rand::thread_rng();
+
+            (0..3)
+                .flat_map(|_| reels.choose(&mut rng).cloned())
+                .collect::<Vec<_>>()
+        };
+
+        let matching = spin
+            .iter()
+            .filter(|s| **s == spin[0])
+            .count()
+            .max(spin.iter().filter(|s| **s == spin[1]).count());
+
+        let payout = match matching {
+            3 => Some(self.jackpot_payout.load().await),
+            2 => Some(self.pair_payout.load().await),
+            _ => None,
+        };
+
+        let spin = spin.join(" | ");
+
+        match payout {
+            Some(payout) => {
+                let won = (amount * payout as i64) / 100;
+
+                currency
+                    .balance_add(user.channel(), user.name(), won, Reason::Gamble)
+                    .await?;
+
+                respond!(
+                    user,
+                    "[ {spin} ] You won {won} {currency}!",
+                    spin = spin,
+                    won = won,
+                    currency = currency.name,
+                );
+            }
+            None => {
+                currency
+                    .balance_add(user.channel(), user.name(), -amount, Reason::Gamble)
+                    .await?;
+
+                respond!(
+                    user,
+                    "[ {spin} ] No luck, you lost {amount} {currency}.",
+                    spin = spin,
+                    amount = amount,
+                    currency = currency.name,
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A duel that is waiting to be accepted.
+struct PendingDuel {
+    challenger: String,
+    amount: i64,
+    expires_at: Instant,
+}
+
+/// Handler for the !duel command.
+pub struct Duel {
+    common: Common,
+    cooldowns: Cooldowns,
+    timeout: settings::Var<utils::Duration>,
+    /// Pending duels, keyed by the challenged user.
+    pending: Mutex<HashMap<String, PendingDuel>>,
+}
+
+#[async_trait]
+impl command::Handler for Duel {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::GambleDuel)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        use rand::Rng as _;
+
+        let currency = match self.common.currency(ctx).await? {
+            Some(currency) => currency,
+            None => return Ok(()),
+        };
+
+        let sender = ctx.user.clone();
+
+        let user = match sender.real() {
+            Some(user) => user,
+            None => respond_bail!("Only real users can duel"),
+        };
+
+        match ctx.next().as_deref() {
+            Some("accept") => {
+                let duel = match self.pending.lock().await.remove(user.name()) {
+                    Some(duel) => duel,
+                    None => respond_bail!("No one has challenged you to a duel"),
+                };
+
+                if duel.expires_at < Instant::now() {
+                    respond_bail!("The duel from {} has expired", duel.challenger);
+                }
+
+                // NB: both stakes are taken before the duel is fought, so
+                // that no one can win what they can't afford to lose.
+                let result = currency
+                    .balance_transfer(
+                        user.channel(),
+                        &duel.challenger,
+                        ESCROW,
+                        duel.amount,
+                        false,
+                        Reason::Gamble,
+                    )
+                    .await;
+
+                match result {
+                    Ok(()) => (),
+                    Err(BalanceTransferError::NoBalance) => {
+                        respond_bail!(
+                            "{challenger} doesn't have {amount} {currency} anymore. The duel is off!",
+                            challenger = duel.challenger,
+                            amount = duel.amount,
+                            currency = currency.name,
+                        );
+                    }
+                    Err(BalanceTransferError::Other(e)) => return Err(e),
+                }
+
+                let result = currency
+                    .balance_transfer(
+                        user.channel(),
+                        user.name(),
+                        ESCROW,
+                        duel.amount,
+                        false,
+                        Reason::Gamble,
+                    )
+                    .await;
+
+                if let Err(e) = result {
+                    currency
+                        .balance_transfer(
+                            user.channel(),
+                            ESCROW,
+                            &duel.challenger,
+                            duel.amount,
+                            true,
+                            Reason::Gamble,
+                        )
+                        .await?;
+
+                    match e {
+                        BalanceTransferError::NoBalance => {
+                            respond_bail!(
+                                "You need {amount} {currency} to accept the duel. The duel is off!",
+                                amount = duel.amount,
+                                currency = currency.name,
+                            );
+                        }
+                        BalanceTransferError::Other(e) => return Err(e),
+                    }
+                }
+
+                let (winner, loser) = if rand::thread_rng().gen_bool(0.5) {
+                    (user.name().to_string(), duel.challenger)
+                } else {
+                    (duel.challenger, user.name().to_string())
+                };
+
+                currency
+                    .balance_transfer(
+                        user.channel(),
+                        ESCROW,
+                        &winner,
+                        duel.amount * 2,
+                        true,
+                        Reason::Gamble,
+                    )
+                    .await?;
+
+                ctx.privmsg(format!(
+                    "/me {winner} won the duel against {loser} and takes {amount} {currency}!",
+                    winner = winner,
+                    loser = loser,
+                    amount = duel.amount,
+                    currency = currency.name,
+                ))
+                .await;
+            }
+            Some("decline") => match self.pending.lock().await.remove(user.name()) {
+                Some(duel) => {
+                    respond!(ctx, "Declined the duel from {}", duel.challenger);
+                }
+                None => {
+                    respond!(ctx, "No one has challenged you to a duel");
+                }
+            },
+            Some(target) => {
+                let target = db::user_id(target);
+                let bet = ctx.next_parse("<user> <amount>")?;
+
+                if user.is(&target) {
+                    respond_bail!("You can't duel yourself");
+                }
+
+                let amount = self
+                    .common
+                    .bet(&currency, user.channel(), user.name(), bet)
+                    .await?;
+
+                let mut pending = self.pending.lock().await;
+
+                if let Some(duel) = pending.get(&target) {
+                    if duel.expires_at > Instant::now() {
+                        respond_bail!("{} already has a pending duel", target);
+                    }
+                }
+
+                self.cooldowns.check(user.name()).await?;
+
+                let timeout = self.timeout.load().await;
+
+                pending.insert(
+                    target.clone(),
+                    PendingDuel {
+                        challenger: user.name().to_string(),
+                        amount,
+                        expires_at: Instant::now() + timeout.as_std(),
+                    },
+                );
+
+                ctx.privmsg(format!(
+                    "/me {target}, {challenger} has challenged you to a duel for {amount} {currency}! \
+                     Type `!duel accept` within {timeout} to accept, or `!duel decline`.",
+                    target = target,
+                    challenger = user.display_name(),
+                    amount = amount,
+                    currency = currency.name,
+                    timeout = utils::compact_duration(timeout.as_std()),
+                ))
+                .await;
+            }
+            None => {
+                respond!(ctx, "Expected: <user> <amount>, accept, or decline");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "gamble"
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("gamble");
+
+        let common = Common {
+            enabled: settings.var("enabled", false).await?,
+            currency: injector.var().await?,
+            min_bet: settings.var("min-bet", 10).await?,
+            max_bet: settings.var("max-bet", 0).await?,
+        };
+
+        let default_reels = vec!["Kappa", "PogChamp", "LUL", "BibleThump", "SeemsGood"]
+            .into_iter()
+            .map(String::from)
+            .collect::<HashSet<_>>();
+
+        handlers.insert(
+            "roulette",
+            Roulette {
+                common: common.clone(),
+                cooldowns: Cooldowns {
+                    cooldown: settings
+                        .var("roulette/cooldown", utils::Duration::seconds(30))
+                        .await?,
+                    last: Default::default(),
+                },
+                win_chance: settings.var("roulette/win-chance%", 45).await?,
+                payout: settings.var("roulette/payout%", 100).await?,
+            },
+        );
+
+        handlers.insert(
+            "slots",
+            Slots {
+                common: common.clone(),
+                cooldowns: Cooldowns {
+                    cooldown: settings
+                        .var("slots/cooldown", utils::Duration::seconds(30))
+                        .await?,
+                    last: Default::default(),
+                },
+                reels: settings.var("slots/reels", default_reels).await?,
+                pair_payout: settings.var("slots/pair-payout%", 50).await?,
+                jackpot_payout: settings.var("slots/jackpot-payout%", 1000).await?,
+            },
+        );
+
+        handlers.insert(
+            "duel",
+            Duel {
+                common,
+                cooldowns: Cooldowns {
+                    cooldown: settings
+                        .var("duel/cooldown", utils::Duration::seconds(60))
+                        .await?,
+                    last: Default::default(),
+                },
+                timeout: settings
+                    .var("duel/timeout", utils::Duration::seconds(60))
+                    .await?,
+                pending: Default::default(),
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bet;
+
+    #[test]
+    fn test_parse_bet() {
+        assert_eq!(Bet::All, str::parse::<Bet>("all").unwrap());
+        assert_eq!(Bet::Percentage(50), str::parse::<Bet>("50%").unwrap());
+        assert_eq!(Bet::Amount(100), str::parse::<Bet>("100").unwrap());
+        assert!(str::parse::<Bet>("101%").is_err());
+        assert!(str::parse::<Bet>("-10").is_err());
+        assert!(str::parse::<Bet>("0").is_err());
+
+        assert_eq!(50, Bet::Percentage(50).resolve(101));
+        assert_eq!(101, Bet::All.resolve(101));
+        assert_eq!(10, Bet::Amount(10).resolve(101));
+    }
+}
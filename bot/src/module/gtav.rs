@@ -1012,7 +1012,12 @@ impl command::Handler for Handler {
             }
 
             currency
-                .balance_add(ctx.user.channel(), real.name(), -(cost as i64))
+                .balance_add(
+                    ctx.user.channel(),
+                    real.name(),
+                    -(cost as i64),
+                    currency::Reason::Gtav,
+                )
                 .await?;
         }
 
@@ -1067,6 +1072,11 @@ impl super::Module for Module {
         "gtav"
     }
 
+    /// ChaosMod runs on the streamer's computer.
+    fn extra_channels(&self) -> bool {
+        false
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
@@ -0,0 +1,494 @@
+use crate::auth;
+use crate::command;
+use crate::currency::{BalanceTransferError, Currency, Reason, HEIST_ESCROW as ESCROW};
+use crate::db;
+use crate::irc;
+use crate::module;
+use crate::module::gamble::Bet;
+use crate::prelude::*;
+use crate::template::Template;
+use crate::utils;
+use anyhow::Result;
+use std::time::Instant;
+use tokio::sync::Mutex;
+
+/// A single stake in a heist.
+#[derive(Debug, Clone)]
+struct Stake {
+    user: String,
+    amount: i64,
+}
+
+/// A heist which is open for people to join.
+struct Pending {
+    leader: String,
+    stakes: Vec<Stake>,
+}
+
+/// Variables available to heist templates.
+#[derive(Debug, serde::Serialize)]
+struct Vars<'a> {
+    name: &'a str,
+    currency: &'a str,
+    duration: String,
+    participants: usize,
+    survivors: String,
+    casualties: String,
+    payout: u32,
+}
+
+struct Heist {
+    enabled: settings::Var<bool>,
+    currency: injector::Var<Option<Currency>>,
+    stakes: injector::Var<Option<db::HeistStakes>>,
+    sender: irc::Sender,
+    min_stake: settings::Var<i64>,
+    max_stake: settings::Var<i64>,
+    join_duration: settings::Var<utils::Duration>,
+    cooldown: settings::Var<utils::Duration>,
+    survival: settings::Var<u32>,
+    payout: settings::Var<u32>,
+    group_bonus: settings::Var<u32>,
+    start_message: settings::Var<Template>,
+    success_message: settings::Var<Template>,
+    partial_message: settings::Var<Template>,
+    failure_message: settings::Var<Template>,
+    pending: Mutex<Option<Pending>>,
+    last_heist: Mutex<Option<Instant>>,
+}
+
+impl Heist {
+    /// Refund all stakes which were left in escrow, typically because the bot
+    /// was restarted in the middle of a heist.
+    async fn refund(&self, currency: &Currency) -> Result<()> {
+        if self.pending.lock().await.is_some() {
+            return Ok(());
+        }
+
+        let stakes = match self.stakes.load().await {
+            Some(stakes) => stakes,
+            None => return Ok(()),
+        };
+
+        let channel = self.sender.channel();
+        let escrow = stakes.list(channel).await?;
+
+        if escrow.is_empty() {
+            return Ok(());
+        }
+
+        for stake in &escrow {
+            log::info!(
+                "refunding {} to {} from aborted heist",
+                stake.amount,
+                stake.user
+            );
+
+            currency
+                .balance_transfer(
+                    channel,
+                    ESCROW,
+                    &stake.user,
+                    stake.amount,
+                    true,
+                    Reason::Gamble,
+                )
+                .await?;
+
+            stakes.remove(channel, &stake.user).await?;
+        }
+
+        self.sender
+            .privmsg(format!(
+                "/me the last heist was aborted, refunded {} {}!",
+                utils::human_list(&escrow.iter().map(|s| s.user.clone()).collect::<Vec<_>>())
+                    .unwrap_or_default(),
+                currency.name,
+            ))
+            .await;
+
+        Ok(())
+    }
+
+    /// Run the heist which is currently pending.
+    async fn run(&self) -> Result<()> {
+        use rand::Rng as _;
+
+        let pending = match self.pending.lock().await.take() {
+            Some(pending) => pending,
+            None => return Ok(()),
+        };
+
+        *self.last_heist.lock().await = Some(Instant::now());
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => {
+                log::warn!("currency was removed during heist, stakes will be refunded on restart");
+                return Ok(());
+            }
+        };
+
+        let stakes = match self.stakes.load().await {
+            Some(stakes) => stakes,
+            None => {
+                log::warn!("stakes were removed during heist, they will be refunded on restart");
+                return Ok(());
+            }
+        };
+
+        let channel = self.sender.channel();
+        let survival = self.survival.load().await.min(100);
+        let extra = pending.stakes.len().saturating_sub(1) as u32;
+        let payout = self.payout.load().await + self.group_bonus.load().await * extra;
+
+        let rolls = {
+            let mut rng = rand::thread_rng();
+
+            pending
+                .stakes
+                .iter()
+                .map(|_| rng.gen_range(0, 100) < survival)
+                .collect::<Vec<_>>()
+        };
+
+        let mut survivors = Vec::new();
+        let mut casualties = Vec::new();
+        // The net change to the escrow account once all stakes are settled.
+        let mut residue = 0;
+
+        // NB: every stake is settled with a single transfer and then removed
+        // from the database, so that a failure half-way through only refunds
+        // the stakes that haven't been settled yet.
+        for (stake, survived) in pending.stakes.iter().zip(rolls) {
+            if survived {
+                let won = (stake.amount * payout as i64) / 100;
+
+                currency
+                    .balance_transfer(
+                        channel,
+                        ESCROW,
+                        &stake.user,
+                        stake.amount + won,
+                        true,
+                        Reason::Gamble,
+                    )
+                    .await?;
+
+                residue -= won;
+                survivors.push(format!("{} (+{})", stake.user, won));
+            } else {
+                residue += stake.amount;
+                casualties.push(stake.user.clone());
+            }
+
+            stakes.remove(channel, &stake.user).await?;
+        }
+
+        // Clear out the lost stakes, and cover the winnings paid from escrow.
+        if residue != 0 {
+            currency
+                .balance_add(channel, ESCROW, -residue, Reason::Gamble)
+                .await?;
+        }
+
+        let template = if casualties.is_empty() {
+            self.success_message.load().await
+        } else if survivors.is_empty() {
+            self.failure_message.load().await
+        } else {
+            self.partial_message.load().await
+        };
+
+        let message = template.render_to_string(Vars {
+            name: &pending.leader,
+            currency: &currency.name,
+            duration: String::new(),
+            participants: pending.stakes.len(),
+            survivors: utils::human_list(&survivors).unwrap_or_default(),
+            casualties: utils::human_list(&casualties).unwrap_or_default(),
+            payout,
+        })?;
+
+        self.sender.privmsg(message).await;
+        Ok(())
+    }
+}
+
+/// Handler for the !heist command.
+pub struct Handler {
+    heist: Arc<Heist>,
+    tx: mpsc::UnboundedSender<()>,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Heist)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        let heist = &*self.heist;
+
+        if !heist.enabled.load().await {
+            return Ok(());
+        }
+
+        let currency = match heist.currency.load().await {
+            Some(currency) => currency,
+            None => respond_bail!("No currency configured for stream, sorry :("),
+        };
+
+        let bet = ctx.next_parse::<Bet, _>("<amount|all|50%>")?;
+
+        let user = match ctx.user.real() {
+            Some(user) => user,
+            None => respond_bail!("Only real users can join a heist"),
+        };
+
+        let stakes = match heist.stakes.load().await {
+            Some(stakes) => stakes,
+            None => return Ok(()),
+        };
+
+        let mut pending = heist.pending.lock().await;
+
+        if let Some(pending) = pending.as_ref() {
+            if pending.stakes.iter().any(|s| user.is(&s.user)) {
+                respond_bail!("You've already joined the heist!");
+            }
+        } else if let Some(last_heist) = *heist.last_heist.lock().await {
+            let cooldown = heist.cooldown.load().await.as_std();
+            let elapsed = last_heist.elapsed();
+
+            if elapsed < cooldown {
+                respond_bail!(
+                    "The crew is laying low, the next heist can start in {}",
+                    utils::compact_duration(cooldown - elapsed)
+                );
+            }
+        }
+
+        let balance = currency
+            .balance_of(user.channel(), user.name())
+            .await?
+            .unwrap_or_default()
+            .balance;
+
+        let amount = bet.resolve(balance);
+        let min_stake = heist.min_stake.load().await.max(1);
+        let max_stake = heist.max_stake.load().await;
+
+        if amount < min_stake {
+            respond_bail!("You need to stake at least {} {}", min_stake, currency.name);
+        }
+
+        if max_stake > 0 && amount > max_stake {
+            respond_bail!("You can stake at most {} {}", max_stake, currency.name);
+        }
+
+        let result = currency
+            .balance_transfer(
+                user.channel(),
+                user.name(),
+                ESCROW,
+                amount,
+                false,
+                Reason::Gamble,
+            )
+            .await;
+
+        match result {
+            Ok(()) => (),
+            Err(BalanceTransferError::NoBalance) => {
+                respond_bail!(
+                    "You don't have enough {currency} to stake {amount}",
+                    currency = currency.name,
+                    amount = amount,
+                );
+            }
+            Err(BalanceTransferError::Other(e)) => return Err(e),
+        }
+
+        if let Err(e) = stakes.insert(user.channel(), user.name(), amount).await {
+            currency
+                .balance_transfer(
+                    user.channel(),
+                    ESCROW,
+                    user.name(),
+                    amount,
+                    true,
+                    Reason::Gamble,
+                )
+                .await?;
+
+            return Err(e);
+        }
+
+        let stake = Stake {
+            user: user.name().to_string(),
+            amount,
+        };
+
+        match pending.as_mut() {
+            Some(pending) => {
+                pending.stakes.push(stake);
+
+                respond!(
+                    user,
+                    "You joined the heist with {amount} {currency}! The crew is now {count} strong.",
+                    amount = amount,
+                    currency = currency.name,
+                    count = pending.stakes.len(),
+                );
+            }
+            None => {
+                *pending = Some(Pending {
+                    leader: user.display_name().to_string(),
+                    stakes: vec![stake],
+                });
+
+                let duration = heist.join_duration.load().await;
+
+                let message = heist.start_message.load().await.render_to_string(Vars {
+                    name: user.display_name(),
+                    currency: &currency.name,
+                    duration: utils::compact_duration(duration.as_std()),
+                    participants: 1,
+                    survivors: String::new(),
+                    casualties: String::new(),
+                    payout: heist.payout.load().await,
+                })?;
+
+                ctx.privmsg(message).await;
+
+                if let Err(e) = self.tx.unbounded_send(()) {
+                    log::error!("failed to schedule heist: {}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "heist"
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            futures,
+            sender,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("heist");
+
+        let heist = Arc::new(Heist {
+            enabled: settings.var("enabled", false).await?,
+            currency: injector.var().await?,
+            stakes: injector.var().await?,
+            sender: sender.clone(),
+            min_stake: settings.var("min-stake", 10).await?,
+            max_stake: settings.var("max-stake", 0).await?,
+            join_duration: settings
+                .var("join-duration", utils::Duration::seconds(60))
+                .await?,
+            cooldown: settings
+                .var("cooldown", utils::Duration::seconds(10 * 60))
+                .await?,
+            survival: settings.var("survival%", 60).await?,
+            payout: settings.var("payout%", 50).await?,
+            group_bonus: settings.var("group-bonus%", 10).await?,
+            start_message: settings
+                .var(
+                    "start/message",
+                    Template::compile(
+                        "/me {{name}} is planning a heist! Type `!heist <amount>` within {{duration}} to join the crew.",
+                    )?,
+                )
+                .await?,
+            success_message: settings
+                .var(
+                    "success/message",
+                    Template::compile(
+                        "/me The heist was a complete success! Everyone made it out alive: {{survivors}}",
+                    )?,
+                )
+                .await?,
+            partial_message: settings
+                .var(
+                    "partial/message",
+                    Template::compile(
+                        "/me The heist is over. Survivors: {{survivors}}. Lost to the cops: {{casualties}}.",
+                    )?,
+                )
+                .await?,
+            failure_message: settings
+                .var(
+                    "failure/message",
+                    Template::compile(
+                        "/me The heist was a disaster, the whole crew got caught: {{casualties}}.",
+                    )?,
+                )
+                .await?,
+            pending: Mutex::new(None),
+            last_heist: Mutex::new(None),
+        });
+
+        let (tx, mut rx) = mpsc::unbounded();
+
+        handlers.insert(
+            "heist",
+            Handler {
+                heist: heist.clone(),
+                tx,
+            },
+        );
+
+        let (mut currency_stream, currency) = injector.stream::<Currency>().await;
+
+        let future = async move {
+            if let Some(currency) = currency {
+                if let Err(e) = heist.refund(&currency).await {
+                    log_error!(e, "failed to refund aborted heist");
+                }
+            }
+
+            let mut timer = None;
+
+            loop {
+                futures::select! {
+                    currency = currency_stream.select_next_some() => {
+                        if let Some(currency) = currency {
+                            if let Err(e) = heist.refund(&currency).await {
+                                log_error!(e, "failed to refund aborted heist");
+                            }
+                        }
+                    }
+                    _ = rx.select_next_some() => {
+                        let duration = heist.join_duration.load().await;
+                        timer = Some(tokio::time::delay_for(duration.as_std()));
+                    }
+                    _ = timer.current() => {
+                        if let Err(e) = heist.run().await {
+                            log_error!(e, "failed to run heist");
+                        }
+                    }
+                }
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
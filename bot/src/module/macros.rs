@@ -211,6 +211,66 @@ macro_rules! command_show {
     }};
 }
 
+/// Helper macro for constructing a command to edit one of the restrictions.
+macro_rules! command_restriction {
+    ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident, $field:ident, $parse:expr) => {{
+        $ctx.check_scope(crate::auth::Scope::$edit_scope).await?;
+
+        let name = $ctx.next_str("<name> [value|none]")?;
+
+        let thing = match $db.get_any($ctx.channel(), &name).await? {
+            Some(thing) => thing,
+            None => {
+                respond!($ctx, "No {} named `{}`.", $what, name);
+                return Ok(());
+            }
+        };
+
+        let value = match $ctx.next() {
+            Some(value) => value,
+            None => {
+                match thing.restrictions.$field.as_ref() {
+                    Some(value) => respond!(
+                        $ctx,
+                        "{} `{}` has {}: {}",
+                        $what,
+                        name,
+                        stringify!($field),
+                        value
+                    ),
+                    None => respond!($ctx, "{} `{}` has no {}", $what, name, stringify!($field)),
+                }
+
+                return Ok(());
+            }
+        };
+
+        let mut restrictions = thing.restrictions.clone();
+
+        restrictions.$field = match value.as_str() {
+            "none" => None,
+            value => match $parse(value) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    respond!($ctx, "Bad {}: {}", stringify!($field), e);
+                    return Ok(());
+                }
+            },
+        };
+
+        $db.edit_restrictions($ctx.channel(), &name, restrictions)
+            .await?;
+
+        respond!(
+            $ctx,
+            "Edited {} for {} `{}`",
+            stringify!($field),
+            $what,
+            name
+        );
+    }};
+}
+
 macro_rules! command_base {
     ($ctx:expr, $db:expr, $what:expr, $edit_scope:ident) => {{
         let arg = $ctx.next();
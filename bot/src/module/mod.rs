@@ -1,4 +1,5 @@
 use crate::api;
+use crate::bus;
 use crate::command;
 use crate::idle;
 use crate::injector;
@@ -15,15 +16,25 @@ pub mod admin;
 pub mod after_stream;
 pub mod alias_admin;
 pub mod auth;
+pub mod bad_word;
 pub mod clip;
 pub mod command_admin;
 pub mod countdown;
+pub mod counter;
 pub mod eight_ball;
+pub mod events;
+pub mod gamble;
 pub mod gtav;
+pub mod heist;
 pub mod help;
 pub mod misc;
 pub mod poll;
+pub mod prediction;
 pub mod promotions;
+pub mod queue;
+pub mod quotes;
+pub mod raffle;
+pub mod shop;
 pub mod song;
 pub mod speedrun;
 pub mod swearjar;
@@ -54,14 +65,20 @@ impl Handlers {
 pub struct HookContext<'a> {
     pub injector: &'a injector::Injector,
     pub handlers: &'a mut Handlers,
+    /// Hooks which peek at every message in the channel.
+    pub message_hooks: &'a mut command::MessageHooks,
     pub futures: &'a mut utils::Futures,
     pub stream_info: &'a stream_info::StreamInfo,
     pub idle: &'a idle::Idle,
     pub twitch: &'a api::Twitch,
+    /// Client for the account of the channel owner. In additional channels
+    /// this is the bot account.
     pub streamer_twitch: &'a api::Twitch,
     pub sender: &'a irc::Sender,
     pub settings: &'a settings::Settings,
     pub auth: &'a crate::auth::Auth,
+    pub global_bus: &'a Arc<bus::Bus<bus::Global>>,
+    pub queue_bus: &'a Arc<bus::Bus<bus::Queue>>,
 }
 
 #[async_trait::async_trait]
@@ -72,6 +89,14 @@ where
     /// Type of the module as a string to help with diagnostics.
     fn ty(&self) -> &'static str;
 
+    /// If the module can be set up in additional channels.
+    ///
+    /// Modules which are tied to the streamer account, like the player, are
+    /// only set up in the channel of the streamer.
+    fn extra_channels(&self) -> bool {
+        true
+    }
+
     /// Set up command handlers for this module.
     async fn hook(&self, _: HookContext<'_>) -> Result<(), anyhow::Error>;
 }
@@ -1,18 +1,21 @@
 use crate::auth;
+use crate::bus;
 use crate::command;
+use crate::currency::Currency;
+use crate::db;
 use crate::irc;
 use crate::module;
 use crate::prelude::*;
 use crate::utils;
 use anyhow::Error;
 use chrono::{DateTime, Utc};
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::time;
 use tokio::sync::Mutex;
 
 /// Handler for the !poll command.
 pub struct Poll {
-    enabled: settings::Var<bool>,
-    polls: Mutex<HashMap<command::HookId, ActivePoll>>,
+    state: Arc<State>,
 }
 
 #[async_trait]
@@ -22,15 +25,40 @@ impl command::Handler for Poll {
     }
 
     async fn handle(&self, ctx: &mut command::Context) -> Result<(), anyhow::Error> {
-        if !self.enabled.load().await {
+        if !self.state.enabled.load().await {
             return Ok(());
         }
 
+        let polls = match self.state.polls.load().await {
+            Some(polls) => polls,
+            None => return Ok(()),
+        };
+
         match ctx.next().as_deref() {
             Some("run") => {
-                let question = ctx.next_str("<question> <options...>")?;
+                let mut duration = None;
+                let mut weighted = false;
+
+                let question = loop {
+                    let arg = ctx
+                        .next_str("[--duration <duration>] [--weighted] <question> <options...>")?;
+
+                    match arg.as_str() {
+                        "--duration" => {
+                            duration = Some(ctx.next_parse::<utils::Duration, _>("<duration>")?);
+                        }
+                        "--weighted" => {
+                            weighted = true;
+                        }
+                        _ => break arg,
+                    }
+                };
+
+                if weighted && self.state.currency.load().await.is_none() {
+                    respond_bail!("No currency configured for stream, so votes can't be weighted");
+                }
 
-                let mut options = HashMap::new();
+                let mut options = Vec::new();
 
                 while let Some(option) = ctx.next() {
                     let (key, description) = match option.find('=') {
@@ -41,63 +69,69 @@ impl command::Handler for Poll {
                         None => (option, None),
                     };
 
-                    options.insert(key.to_lowercase(), description);
+                    options.push(PollOption {
+                        key: key.to_lowercase(),
+                        description,
+                    });
                 }
 
+                let closes_at = match duration {
+                    Some(duration) => {
+                        Some(Utc::now() + chrono::Duration::seconds(duration.num_seconds() as i64))
+                    }
+                    None => None,
+                };
+
+                let poll = polls
+                    .start(
+                        ctx.channel(),
+                        &question,
+                        &serde_json::to_string(&options)?,
+                        weighted,
+                        closes_at.map(|c| c.naive_utc()),
+                    )
+                    .await?;
+
                 let poll = ActivePoll {
-                    question: question.clone(),
-                    created_at: Utc::now(),
+                    poll,
                     options,
-                    inner: settings::Var::new(Inner {
-                        voted: Default::default(),
-                        votes: Default::default(),
-                    }),
+                    votes: Default::default(),
                 };
 
-                let hook_id = ctx.insert_hook(poll.clone()).await;
-                self.polls.lock().await.insert(hook_id, poll);
-                ctx.respond(format!("Started poll `{}` (id: {})", question, hook_id))
-                    .await;
+                let id = poll.poll.id;
+                self.state.global_bus.send(poll.to_bus(false)).await;
+                self.state.running.lock().await.insert(id, poll);
+
+                match duration {
+                    Some(duration) => respond!(
+                        ctx,
+                        "Started poll `{}` (id: {}), closing in {}",
+                        question,
+                        id,
+                        utils::compact_duration(duration.as_std())
+                    ),
+                    None => respond!(ctx, "Started poll `{}` (id: {})", question, id),
+                }
             }
             Some("close") => {
-                let mut polls = self.polls.lock().await;
-
                 let id = match ctx.next() {
-                    Some(id) => str::parse::<command::HookId>(&id)
-                        .map_err(|_| respond_err!("Bad id `{}`", id))?,
-                    None => {
-                        *polls
-                            .iter()
-                            .max_by_key(|e| e.1.created_at)
-                            .ok_or_else(|| respond_err!("No running polls"))?
-                            .0
+                    Some(id) => {
+                        str::parse::<i32>(&id).map_err(|_| respond_err!("Bad id `{}`", id))?
                     }
+                    None => *self
+                        .state
+                        .running
+                        .lock()
+                        .await
+                        .keys()
+                        .max()
+                        .ok_or_else(|| respond_err!("No running polls"))?,
                 };
 
-                let poll = polls
-                    .remove(&id)
-                    .ok_or_else(|| respond_err!("No poll with id `{}`!", id))?;
-
-                ctx.remove_hook(id).await;
-                let results = poll.close().await;
-
-                let total = results.iter().map(|(_, c)| c).sum::<u32>();
-
-                let mut formatted = Vec::new();
-
-                for (key, votes) in results {
-                    let p = utils::percentage(votes, total);
-
-                    let votes = match votes {
-                        0 => "no votes".to_string(),
-                        1 => "one vote".to_string(),
-                        n => format!("{} votes", n),
-                    };
-
-                    formatted.push(format!("{} = {} ({})", key, votes, p));
+                match self.state.close(id).await? {
+                    Some(message) => respond!(ctx, "{}", message),
+                    None => respond!(ctx, "No poll with id `{}`!", id),
                 }
-
-                respond!(ctx, "{} -> {}.", poll.question, formatted.join(", "));
             }
             _ => {
                 ctx.respond("Expected: run, close.").await;
@@ -108,60 +142,254 @@ impl command::Handler for Poll {
     }
 }
 
-struct Inner {
-    voted: HashSet<String>,
-    votes: HashMap<String, u32>,
+/// A single option of a poll.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PollOption {
+    key: String,
+    description: Option<String>,
 }
 
-#[derive(Clone)]
 struct ActivePoll {
-    question: String,
-    created_at: DateTime<Utc>,
-    options: HashMap<String, Option<String>>,
-    inner: settings::Var<Inner>,
+    poll: db::Poll,
+    options: Vec<PollOption>,
+    /// The total weight of votes cast for each option.
+    votes: HashMap<String, i64>,
 }
 
 impl ActivePoll {
-    /// Close the poll.
-    pub async fn close(&self) -> Vec<(String, u32)> {
-        let inner = self.inner.read().await;
+    /// Restore a poll from the database.
+    fn restore(poll: db::Poll, votes: Vec<db::PollVote>) -> Result<Self, Error> {
+        let options = serde_json::from_str(&poll.options)?;
+
+        let mut poll = ActivePoll {
+            poll,
+            options,
+            votes: Default::default(),
+        };
+
+        for vote in votes {
+            *poll.votes.entry(vote.option).or_default() += vote.weight;
+        }
+
+        Ok(poll)
+    }
+
+    /// When the poll closes, if ever.
+    fn closes_at(&self) -> Option<DateTime<Utc>> {
+        self.poll
+            .closes_at
+            .map(|c| DateTime::<Utc>::from_utc(c, Utc))
+    }
 
+    /// Get the results of the poll, sorted by most votes.
+    fn results(&self) -> Vec<(String, i64)> {
         let mut results = Vec::new();
 
-        for (o, description) in &self.options {
+        for o in &self.options {
             results.push((
-                description.clone().unwrap_or_else(|| o.to_string()),
-                inner.votes.get(o).cloned().unwrap_or_default(),
+                o.description.clone().unwrap_or_else(|| o.key.clone()),
+                self.votes.get(&o.key).cloned().unwrap_or_default(),
             ));
         }
 
         results.sort_by(|a, b| b.1.cmp(&a.1));
         results
     }
+
+    /// Format the results of the poll to post in chat.
+    fn format_results(&self) -> String {
+        let results = self.results();
+        let total = results.iter().map(|(_, c)| *c).sum::<i64>();
+
+        let mut formatted = Vec::new();
+
+        for (key, votes) in results {
+            let p = utils::percentage(votes, total);
+
+            let votes = match (self.poll.weighted, votes) {
+                (true, n) => format!("{} weight", n),
+                (false, 0) => "no votes".to_string(),
+                (false, 1) => "one vote".to_string(),
+                (false, n) => format!("{} votes", n),
+            };
+
+            formatted.push(format!("{} = {} ({})", key, votes, p));
+        }
+
+        format!("{} -> {}.", self.poll.question, formatted.join(", "))
+    }
+
+    /// Convert into a message for the overlay bus.
+    fn to_bus(&self, closed: bool) -> bus::Global {
+        bus::Global::Poll {
+            channel: self.poll.channel.clone(),
+            id: self.poll.id,
+            question: self.poll.question.clone(),
+            options: self
+                .options
+                .iter()
+                .map(|o| bus::PollOption {
+                    key: o.key.clone(),
+                    description: o.description.clone(),
+                    votes: self.votes.get(&o.key).cloned().unwrap_or_default(),
+                })
+                .collect(),
+            weighted: self.poll.weighted,
+            closes_at: self.closes_at(),
+            closed,
+        }
+    }
+}
+
+/// State shared between the command, the vote hook, and the timer.
+struct State {
+    enabled: settings::Var<bool>,
+    polls: injector::Var<Option<db::Polls>>,
+    currency: injector::Var<Option<Currency>>,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+    sender: irc::Sender,
+    running: Mutex<HashMap<i32, ActivePoll>>,
+}
+
+impl State {
+    /// Restore all polls which were running when the bot was stopped.
+    async fn restore(&self) -> Result<(), Error> {
+        let polls = match self.polls.load().await {
+            Some(polls) => polls,
+            None => return Ok(()),
+        };
+
+        let mut running = self.running.lock().await;
+
+        for poll in polls.running(self.sender.channel()).await? {
+            let votes = polls.votes(poll.id).await?;
+            let poll = ActivePoll::restore(poll, votes)?;
+            self.global_bus.send(poll.to_bus(false)).await;
+            running.insert(poll.poll.id, poll);
+        }
+
+        Ok(())
+    }
+
+    /// Close the given poll, returning its results if it was running.
+    async fn close(&self, id: i32) -> Result<Option<String>, Error> {
+        let poll = match self.running.lock().await.remove(&id) {
+            Some(poll) => poll,
+            None => return Ok(None),
+        };
+
+        if let Some(polls) = self.polls.load().await {
+            polls.close(id).await?;
+        }
+
+        self.global_bus.send(poll.to_bus(true)).await;
+        Ok(Some(poll.format_results()))
+    }
+
+    /// Close all polls whose time is up and post their results.
+    async fn close_expired(&self) -> Result<(), Error> {
+        let now = Utc::now();
+
+        let expired = self
+            .running
+            .lock()
+            .await
+            .values()
+            .filter(|p| p.closes_at().map(|c| c <= now).unwrap_or_default())
+            .map(|p| p.poll.id)
+            .collect::<Vec<_>>();
+
+        for id in expired {
+            if let Some(message) = self.close(id).await? {
+                self.sender.privmsg(message).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Message hook which counts votes for all running polls.
+struct Votes {
+    state: Arc<State>,
 }
 
 #[async_trait]
-impl command::MessageHook for ActivePoll {
+impl command::MessageHook for Votes {
     async fn peek(&self, user: &irc::User, m: &str) -> Result<(), Error> {
-        let mut inner = self.inner.write().await;
-
         let user = match user.real() {
             Some(user) => user,
             None => return Ok(()),
         };
 
-        if inner.voted.contains(user.name()) {
+        // NB: only hold on to the lock while matching the message against
+        // the running polls, not while talking to the database.
+        let candidates = self
+            .state
+            .running
+            .lock()
+            .await
+            .values()
+            .filter_map(|poll| {
+                let option = utils::TrimmedWords::new(m)
+                    .map(|w| w.to_lowercase())
+                    .find(|w| poll.options.iter().any(|o| o.key == *w))?;
+
+                Some((poll.poll.id, poll.poll.weighted, option))
+            })
+            .collect::<Vec<_>>();
+
+        if candidates.is_empty() {
             return Ok(());
         }
 
-        for word in utils::TrimmedWords::new(m) {
-            if self.options.get(&word.to_lowercase()).is_none() {
+        let polls = match self.state.polls.load().await {
+            Some(polls) => polls,
+            None => return Ok(()),
+        };
+
+        for (id, weighted, option) in candidates {
+            let weight = if weighted {
+                match self.state.currency.load().await {
+                    Some(currency) => currency
+                        .balance_of(user.channel(), user.name())
+                        .await?
+                        .map(|b| b.balance)
+                        .unwrap_or_default()
+                        .max(1),
+                    None => 1,
+                }
+            } else {
+                1
+            };
+
+            let counted = polls
+                .vote(db::PollVote {
+                    poll_id: id,
+                    user: user.name().to_string(),
+                    option: option.clone(),
+                    weight,
+                })
+                .await?;
+
+            if !counted {
                 continue;
             }
 
-            *inner.votes.entry(word.to_string()).or_default() += 1;
-            inner.voted.insert(user.name().to_string());
-            break;
+            let message = {
+                let mut running = self.state.running.lock().await;
+
+                // NB: the poll might have been closed while voting.
+                let poll = match running.get_mut(&id) {
+                    Some(poll) => poll,
+                    None => continue,
+                };
+
+                *poll.votes.entry(option).or_default() += weight;
+                poll.to_bus(false)
+            };
+
+            self.state.global_bus.send(message).await;
         }
 
         Ok(())
@@ -180,17 +408,53 @@ impl super::Module for Module {
     async fn hook(
         &self,
         module::HookContext {
-            handlers, settings, ..
+            injector,
+            handlers,
+            message_hooks,
+            futures,
+            sender,
+            settings,
+            global_bus,
+            ..
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
+        let state = Arc::new(State {
+            enabled: settings.var("poll/enabled", false).await?,
+            polls: injector.var().await?,
+            currency: injector.var().await?,
+            global_bus: global_bus.clone(),
+            sender: sender.clone(),
+            running: Mutex::new(Default::default()),
+        });
+
+        if let Err(e) = state.restore().await {
+            log_error!(e, "failed to restore running polls");
+        }
+
+        message_hooks.insert(Box::new(Votes {
+            state: state.clone(),
+        }));
+
         handlers.insert(
             "poll",
             Poll {
-                polls: Mutex::new(Default::default()),
-                enabled: settings.var("poll/enabled", false).await?,
+                state: state.clone(),
             },
         );
 
+        let future = async move {
+            let mut interval = tokio::time::interval(time::Duration::from_secs(1));
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = state.close_expired().await {
+                    log_error!(e, "failed to close expired polls");
+                }
+            }
+        };
+
+        futures.push(future.boxed());
         Ok(())
     }
 }
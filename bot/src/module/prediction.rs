@@ -0,0 +1,720 @@
+use crate::auth;
+use crate::bus;
+use crate::command;
+use crate::currency::{Currency, Reason};
+use crate::db;
+use crate::irc;
+use crate::module;
+use crate::module::gamble::Bet;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time;
+use tokio::sync::Mutex;
+
+/// Handler for the !bet command.
+pub struct Prediction {
+    state: Arc<State>,
+}
+
+#[async_trait]
+impl command::Handler for Prediction {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Bet)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.state.enabled.load().await {
+            return Ok(());
+        }
+
+        let predictions = match self.state.predictions.load().await {
+            Some(predictions) => predictions,
+            None => return Ok(()),
+        };
+
+        let currency = match self.state.currency.load().await {
+            Some(currency) => currency,
+            None => respond_bail!("No currency configured for stream, so bets can't be placed"),
+        };
+
+        match ctx.next().as_deref() {
+            Some("open") => {
+                ctx.check_scope(auth::Scope::BetAdmin).await?;
+
+                let mut window = self.state.window.load().await;
+
+                let question = loop {
+                    let arg = ctx.next_str("[--window <duration>] <question> <outcomes...>")?;
+
+                    match arg.as_str() {
+                        "--window" => {
+                            window = ctx.next_parse::<utils::Duration, _>("<duration>")?;
+                        }
+                        _ => break arg,
+                    }
+                };
+
+                let mut outcomes = Vec::new();
+
+                while let Some(outcome) = ctx.next() {
+                    let outcome = outcome.to_lowercase();
+
+                    if !outcomes.contains(&outcome) {
+                        outcomes.push(outcome);
+                    }
+                }
+
+                if outcomes.len() < 2 {
+                    respond_bail!("A bet needs at least two outcomes");
+                }
+
+                let mut current = self.state.current.lock().await;
+
+                if let Some(active) = current.as_ref() {
+                    respond_bail!(
+                        "Bet `{}` is still running, resolve or refund it first",
+                        active.prediction.question
+                    );
+                }
+
+                let locks_at = Utc::now() + chrono::Duration::seconds(window.num_seconds() as i64);
+
+                let prediction = predictions
+                    .open(
+                        ctx.channel(),
+                        &question,
+                        &serde_json::to_string(&outcomes)?,
+                        locks_at.naive_utc(),
+                    )
+                    .await?;
+
+                let active = Active {
+                    prediction,
+                    outcomes,
+                    stakes: Default::default(),
+                    locked: false,
+                };
+
+                self.state.global_bus.send(active.to_bus()).await;
+
+                ctx.privmsg(format!(
+                    "/me Bets are open for {window}: {question} Type `<outcome> <amount>` to bet {currency} on one of {outcomes}.",
+                    window = utils::compact_duration(window.as_std()),
+                    question = question,
+                    currency = currency.name,
+                    outcomes = active.outcomes.join(", "),
+                ))
+                .await;
+
+                *current = Some(active);
+            }
+            Some("lock") => {
+                ctx.check_scope(auth::Scope::BetAdmin).await?;
+
+                let mut current = self.state.current.lock().await;
+
+                let active = match current.as_mut() {
+                    Some(active) if !active.locked => active,
+                    _ => respond_bail!("No bet is accepting stakes"),
+                };
+
+                self.state.lock(&predictions, active).await?;
+
+                self.state.global_bus.send(active.to_bus()).await;
+                ctx.privmsg(format!("/me Bets are locked! {}", active.summary()))
+                    .await;
+            }
+            Some("resolve") => {
+                ctx.check_scope(auth::Scope::BetAdmin).await?;
+
+                let winner = ctx.next_str("<outcome>")?.to_lowercase();
+                let mut current = self.state.current.lock().await;
+
+                let active = match current.as_mut() {
+                    Some(active) if !active.outcomes.contains(&winner) => respond_bail!(
+                        "`{}` is not an outcome, expected one of: {}",
+                        winner,
+                        active.outcomes.join(", ")
+                    ),
+                    Some(active) => active,
+                    None => respond_bail!("No bet is running"),
+                };
+
+                let message = self
+                    .state
+                    .resolve(&predictions, &currency, active, &winner)
+                    .await?;
+
+                // NB: only forget the prediction once all stakes are settled,
+                // so that a failed resolve can be retried.
+                *current = None;
+                ctx.privmsg(message).await;
+            }
+            Some("refund") => {
+                ctx.check_scope(auth::Scope::BetAdmin).await?;
+
+                let mut current = self.state.current.lock().await;
+
+                let active = match current.as_mut() {
+                    Some(active) => active,
+                    None => respond_bail!("No bet is running"),
+                };
+
+                let message = self.state.refund(&predictions, &currency, active).await?;
+
+                *current = None;
+                ctx.privmsg(message).await;
+            }
+            Some("status") | None => {
+                let current = self.state.current.lock().await;
+
+                let active = match current.as_ref() {
+                    Some(active) => active,
+                    None => respond_bail!("No bet is running"),
+                };
+
+                let state = if active.locked {
+                    String::from("locked")
+                } else {
+                    let remaining = (active.locks_at() - Utc::now())
+                        .to_std()
+                        .unwrap_or_default();
+                    format!("locking in {}", utils::compact_duration(remaining))
+                };
+
+                respond!(
+                    ctx,
+                    "{question} ({state}) -> {summary}",
+                    question = active.prediction.question,
+                    state = state,
+                    summary = active.summary(),
+                );
+            }
+            Some(outcome) => {
+                let outcome = outcome.to_lowercase();
+                let bet = ctx.next_parse::<Bet, _>("<outcome> <amount>")?;
+
+                let user = ctx.user.clone();
+
+                let real = match user.real() {
+                    Some(real) => real,
+                    None => respond_bail!("Only real users can place bets"),
+                };
+
+                match self.state.stake(&currency, &real, &outcome, bet).await? {
+                    Some(message) => respond!(ctx, "{}", message),
+                    None => respond!(ctx, "No open bet with the outcome `{}`", outcome),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The prediction which is currently running.
+struct Active {
+    prediction: db::Prediction,
+    outcomes: Vec<String>,
+    /// Stakes placed, keyed by user.
+    stakes: HashMap<String, db::PredictionStake>,
+    /// If we have announced that the prediction is locked.
+    locked: bool,
+}
+
+impl Active {
+    /// Restore a prediction from the database.
+    fn restore(prediction: db::Prediction, stakes: Vec<db::PredictionStake>) -> Result<Self> {
+        let outcomes = serde_json::from_str(&prediction.outcomes)?;
+        let locked = prediction.locks_at <= Utc::now().naive_utc();
+
+        Ok(Active {
+            prediction,
+            outcomes,
+            stakes: stakes.into_iter().map(|s| (s.user.clone(), s)).collect(),
+            locked,
+        })
+    }
+
+    /// When the prediction stops accepting stakes.
+    fn locks_at(&self) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(self.prediction.locks_at, Utc)
+    }
+
+    /// Test if the prediction accepts stakes right now.
+    fn is_open(&self) -> bool {
+        !self.locked && Utc::now() < self.locks_at()
+    }
+
+    /// The total amount staked and number of users staking on each outcome.
+    fn totals(&self) -> Vec<(String, i64, usize)> {
+        self.outcomes
+            .iter()
+            .map(|o| {
+                let stakes = self.stakes.values().filter(|s| s.outcome == *o);
+                let (total, count) = stakes.fold((0, 0), |(t, c), s| (t + s.amount, c + 1));
+                (o.clone(), total, count)
+            })
+            .collect()
+    }
+
+    /// The total amount in the pool.
+    fn pool(&self) -> i64 {
+        self.stakes.values().map(|s| s.amount).sum()
+    }
+
+    /// Summarize the stakes placed on each outcome.
+    fn summary(&self) -> String {
+        let totals = self
+            .totals()
+            .into_iter()
+            .map(|(outcome, total, count)| format!("{} = {} ({} bets)", outcome, total, count))
+            .collect::<Vec<_>>();
+
+        format!("{}.", totals.join(", "))
+    }
+
+    /// Convert into a message for the overlay bus.
+    fn to_bus(&self) -> bus::Global {
+        self.to_bus_finished(db::PredictionState::Open, None)
+    }
+
+    /// Convert into a message for the overlay bus with the given state.
+    fn to_bus_finished(&self, state: db::PredictionState, winner: Option<&str>) -> bus::Global {
+        bus::Global::Prediction {
+            channel: self.prediction.channel.clone(),
+            id: self.prediction.id,
+            question: self.prediction.question.clone(),
+            outcomes: self
+                .totals()
+                .into_iter()
+                .map(|(key, total, stakers)| bus::PredictionOutcome {
+                    key,
+                    total,
+                    stakers,
+                })
+                .collect(),
+            locks_at: self.locks_at(),
+            locked: !self.is_open(),
+            state,
+            winner: winner.map(String::from),
+        }
+    }
+}
+
+/// State shared between the command, the stake hook, and the timer.
+struct State {
+    enabled: settings::Var<bool>,
+    window: settings::Var<utils::Duration>,
+    predictions: injector::Var<Option<db::Predictions>>,
+    currency: injector::Var<Option<Currency>>,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+    sender: irc::Sender,
+    current: Mutex<Option<Active>>,
+}
+
+impl State {
+    /// Restore the prediction which was open when the bot was stopped.
+    async fn restore(&self) -> Result<()> {
+        let predictions = match self.predictions.load().await {
+            Some(predictions) => predictions,
+            None => return Ok(()),
+        };
+
+        let prediction = match predictions.current(self.sender.channel()).await? {
+            Some(prediction) => prediction,
+            None => return Ok(()),
+        };
+
+        let stakes = predictions.stakes(prediction.id).await?;
+        let active = Active::restore(prediction, stakes)?;
+        self.global_bus.send(active.to_bus()).await;
+        *self.current.lock().await = Some(active);
+        Ok(())
+    }
+
+    /// Stop accepting stakes on the given prediction.
+    async fn lock(&self, predictions: &db::Predictions, active: &mut Active) -> Result<()> {
+        let now = Utc::now().naive_utc();
+        predictions.lock(active.prediction.id, now).await?;
+        active.prediction.locks_at = now;
+        active.locked = true;
+        Ok(())
+    }
+
+    /// Announce that the current prediction is locked once its window has
+    /// passed.
+    async fn lock_expired(&self) {
+        let mut current = self.current.lock().await;
+
+        let active = match current.as_mut() {
+            Some(active) if !active.locked && !active.is_open() => active,
+            _ => return,
+        };
+
+        active.locked = true;
+        self.global_bus.send(active.to_bus()).await;
+
+        self.sender
+            .privmsg(format!("/me Bets are locked! {}", active.summary()))
+            .await;
+    }
+
+    /// Place a stake on the current prediction.
+    ///
+    /// A user can only bet on one outcome, but may add to their stake while
+    /// the prediction is open.
+    ///
+    /// Returns the message to respond with, or `None` if there is no open
+    /// prediction with the given outcome.
+    async fn stake(
+        &self,
+        currency: &Currency,
+        user: &irc::RealUser<'_>,
+        outcome: &str,
+        bet: Bet,
+    ) -> Result<Option<String>> {
+        let predictions = match self.predictions.load().await {
+            Some(predictions) => predictions,
+            None => return Ok(None),
+        };
+
+        // NB: hold on to the lock to serialize stakes.
+        let mut current = self.current.lock().await;
+
+        let active = match current.as_mut() {
+            Some(active) if active.is_open() && active.outcomes.iter().any(|o| o == outcome) => {
+                active
+            }
+            _ => return Ok(None),
+        };
+
+        let previous = active
+            .stakes
+            .get(user.name())
+            .map(|s| (s.outcome.clone(), s.amount));
+
+        if let Some((previous, _)) = &previous {
+            if previous != outcome {
+                return Ok(Some(format!(
+                    "You already bet on `{}`, you can't bet on another outcome",
+                    previous
+                )));
+            }
+        }
+
+        let balance = currency
+            .balance_of(user.channel(), user.name())
+            .await?
+            .unwrap_or_default()
+            .balance;
+
+        let amount = bet.resolve(balance);
+
+        if amount <= 0 {
+            return Ok(Some(format!("You have no {} to bet", currency.name)));
+        }
+
+        if balance < amount {
+            return Ok(Some(format!(
+                "You need {amount} {currency} to bet that, you have {balance}",
+                amount = amount,
+                currency = currency.name,
+                balance = balance,
+            )));
+        }
+
+        currency
+            .balance_add(user.channel(), user.name(), -amount, Reason::Bet)
+            .await?;
+
+        let stake = db::PredictionStake {
+            prediction_id: active.prediction.id,
+            user: user.name().to_string(),
+            outcome: outcome.to_string(),
+            amount: previous.map(|(_, a)| a).unwrap_or_default() + amount,
+            settled: false,
+        };
+
+        if let Err(e) = predictions.stake(stake.clone()).await {
+            currency
+                .balance_add(user.channel(), user.name(), amount, Reason::Bet)
+                .await?;
+
+            return Err(e);
+        }
+
+        let total = stake.amount;
+        active.stakes.insert(stake.user.clone(), stake);
+        self.global_bus.send(active.to_bus()).await;
+
+        Ok(Some(format!(
+            "You have {total} {currency} riding on `{outcome}`. Good luck!",
+            total = total,
+            currency = currency.name,
+            outcome = outcome,
+        )))
+    }
+
+    /// Pay the given amount for a stake, unless it has already been settled.
+    async fn settle(
+        &self,
+        predictions: &db::Predictions,
+        currency: &Currency,
+        channel: &str,
+        stake: &mut db::PredictionStake,
+        amount: i64,
+    ) -> Result<()> {
+        if stake.settled {
+            return Ok(());
+        }
+
+        // NB: mark the stake as settled before paying it, and back out if the
+        // payment fails, so that a stake is never paid twice.
+        if predictions
+            .settle(stake.prediction_id, &stake.user, true)
+            .await?
+        {
+            let result = currency
+                .balance_add(channel, &stake.user, amount, Reason::Bet)
+                .await;
+
+            if let Err(e) = result {
+                predictions
+                    .settle(stake.prediction_id, &stake.user, false)
+                    .await?;
+                return Err(e);
+            }
+        }
+
+        stake.settled = true;
+        Ok(())
+    }
+
+    /// Resolve the prediction and pay out winners proportionally from the
+    /// pool.
+    ///
+    /// If nobody bet on the winning outcome, all stakes are refunded. Stakes
+    /// are settled one by one, so if this fails it can be retried without
+    /// paying anyone twice.
+    async fn resolve(
+        &self,
+        predictions: &db::Predictions,
+        currency: &Currency,
+        active: &mut Active,
+        winner: &str,
+    ) -> Result<String> {
+        if active.is_open() {
+            self.lock(predictions, active).await?;
+        }
+
+        let pool = active.pool();
+
+        let winning = active
+            .stakes
+            .values()
+            .filter(|s| s.outcome == winner)
+            .map(|s| s.amount)
+            .sum::<i64>();
+
+        if winning == 0 {
+            let message = self.refund(predictions, currency, active).await?;
+            return Ok(format!("Nobody bet on `{}`. {}", winner, message));
+        }
+
+        let mut winners = Vec::new();
+
+        for stake in active.stakes.values_mut().filter(|s| s.outcome == winner) {
+            // NB: widen to avoid overflowing large pools.
+            let payout = (stake.amount as i128 * pool as i128 / winning as i128) as i64;
+
+            self.settle(
+                predictions,
+                currency,
+                &active.prediction.channel,
+                stake,
+                payout,
+            )
+            .await?;
+
+            winners.push((stake.user.clone(), payout));
+        }
+
+        predictions
+            .finish(
+                active.prediction.id,
+                db::PredictionState::Resolved,
+                Some(winner),
+            )
+            .await?;
+
+        self.global_bus
+            .send(active.to_bus_finished(db::PredictionState::Resolved, Some(winner)))
+            .await;
+
+        winners.sort_by(|a, b| b.1.cmp(&a.1));
+
+        let top = winners
+            .iter()
+            .take(3)
+            .map(|(user, payout)| format!("{} ({})", user, payout))
+            .collect::<Vec<_>>();
+
+        Ok(format!(
+            "/me `{winner}` wins! {count} winners split {pool} {currency}. Top: {top}.",
+            winner = winner,
+            count = winners.len(),
+            pool = pool,
+            currency = currency.name,
+            top = utils::human_list(&top).unwrap_or_default(),
+        ))
+    }
+
+    /// Refund all stakes placed on the prediction.
+    async fn refund(
+        &self,
+        predictions: &db::Predictions,
+        currency: &Currency,
+        active: &mut Active,
+    ) -> Result<String> {
+        if active.is_open() {
+            self.lock(predictions, active).await?;
+        }
+
+        for stake in active.stakes.values_mut() {
+            let amount = stake.amount;
+
+            self.settle(
+                predictions,
+                currency,
+                &active.prediction.channel,
+                stake,
+                amount,
+            )
+            .await?;
+        }
+
+        predictions
+            .finish(active.prediction.id, db::PredictionState::Refunded, None)
+            .await?;
+
+        self.global_bus
+            .send(active.to_bus_finished(db::PredictionState::Refunded, None))
+            .await;
+
+        Ok(format!(
+            "/me Refunded {} bets on `{}`.",
+            active.stakes.len(),
+            active.prediction.question
+        ))
+    }
+}
+
+/// Message hook which accepts stakes typed as `<outcome> <amount>` in chat.
+struct Stakes {
+    state: Arc<State>,
+}
+
+#[async_trait]
+impl command::MessageHook for Stakes {
+    async fn peek(&self, user: &irc::User, m: &str) -> Result<()> {
+        let real = match user.real() {
+            Some(user) => user,
+            None => return Ok(()),
+        };
+
+        let mut words = utils::TrimmedWords::new(m);
+
+        let (outcome, bet) = match (words.next(), words.next(), words.next()) {
+            (Some(outcome), Some(bet), None) => match str::parse::<Bet>(bet) {
+                Ok(bet) => (outcome.to_lowercase(), bet),
+                Err(..) => return Ok(()),
+            },
+            _ => return Ok(()),
+        };
+
+        if !real.has_scope(auth::Scope::Bet).await {
+            return Ok(());
+        }
+
+        let currency = match self.state.currency.load().await {
+            Some(currency) => currency,
+            None => return Ok(()),
+        };
+
+        if let Some(message) = self.state.stake(&currency, &real, &outcome, bet).await? {
+            real.consume_scope(auth::Scope::Bet).await;
+            respond!(real, "{}", message);
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "prediction"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            message_hooks,
+            futures,
+            sender,
+            settings,
+            global_bus,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("bet");
+
+        let state = Arc::new(State {
+            enabled: settings.var("enabled", false).await?,
+            window: settings
+                .var("window", utils::Duration::seconds(120))
+                .await?,
+            predictions: injector.var().await?,
+            currency: injector.var().await?,
+            global_bus: global_bus.clone(),
+            sender: sender.clone(),
+            current: Mutex::new(None),
+        });
+
+        if let Err(e) = state.restore().await {
+            log_error!(e, "failed to restore open bet");
+        }
+
+        message_hooks.insert(Box::new(Stakes {
+            state: state.clone(),
+        }));
+
+        handlers.insert(
+            "bet",
+            Prediction {
+                state: state.clone(),
+            },
+        );
+
+        let future = async move {
+            let mut interval = tokio::time::interval(time::Duration::from_secs(1));
+
+            loop {
+                interval.tick().await;
+                state.lock_expired().await;
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
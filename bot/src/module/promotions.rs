@@ -4,6 +4,8 @@ use crate::db;
 use crate::irc;
 use crate::module;
 use crate::prelude::*;
+use crate::stream_info;
+use crate::template;
 use crate::utils;
 use chrono::Utc;
 
@@ -68,6 +70,7 @@ impl super::Module for Module {
             sender,
             settings,
             idle,
+            stream_info,
             ..
         }: module::HookContext<'_>,
     ) -> Result<(), anyhow::Error> {
@@ -91,6 +94,7 @@ impl super::Module for Module {
         let sender = sender.clone();
         let mut interval = tokio::time::interval(frequency.as_std()).fuse();
         let idle = idle.clone();
+        let stream_info = stream_info.clone();
 
         let future = async move {
             loop {
@@ -120,7 +124,7 @@ impl super::Module for Module {
                             let promotions = promotions.clone();
                             let sender = sender.clone();
 
-                            if let Err(e) = promote(promotions, sender).await {
+                            if let Err(e) = promote(promotions, sender, &stream_info).await {
                                 log::error!("failed to send promotion: {}", e);
                             }
                         }
@@ -135,11 +139,18 @@ impl super::Module for Module {
 }
 
 /// Run the next promotion.
-async fn promote(promotions: db::Promotions, sender: irc::Sender) -> Result<(), anyhow::Error> {
+async fn promote(
+    promotions: db::Promotions,
+    sender: irc::Sender,
+    stream_info: &stream_info::StreamInfo,
+) -> Result<(), anyhow::Error> {
     let channel = sender.channel();
 
     if let Some(p) = pick(promotions.list(channel).await) {
-        let text = p.render(&PromoData { channel })?;
+        let text = p.render(&PromoData {
+            channel,
+            stream: stream_info.template_vars(),
+        })?;
         promotions.bump_promoted_at(&*p).await?;
         sender.privmsg(text).await;
     }
@@ -150,6 +161,7 @@ async fn promote(promotions: db::Promotions, sender: irc::Sender) -> Result<(),
 #[derive(Debug, serde::Serialize)]
 struct PromoData<'a> {
     channel: &'a str,
+    stream: template::StreamVars,
 }
 
 /// Pick the best promo.
@@ -0,0 +1,377 @@
+use crate::auth;
+use crate::bus;
+use crate::command;
+use crate::currency::{Currency, Reason};
+use crate::db;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::Result;
+use chrono::Utc;
+use tokio::sync::Mutex;
+
+/// How many users to show with `!queue list`.
+const LIST_LIMIT: usize = 5;
+
+/// Handler for the !queue command.
+pub struct Queue {
+    state: Arc<State>,
+}
+
+#[async_trait]
+impl command::Handler for Queue {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Queue)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.state.enabled.load().await {
+            return Ok(());
+        }
+
+        let queue = match self.state.queue.load().await {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("join") => {
+                let user = user(ctx)?;
+
+                if !self.state.open.load().await {
+                    respond_bail!("The queue is closed");
+                }
+
+                let mut entries = self.state.entries.lock().await;
+
+                if let Some(position) = position(&entries, &user) {
+                    respond_bail!("You are already in the queue at position {}", position);
+                }
+
+                if let Some(max_size) = self.state.max_size.load().await {
+                    if entries.len() >= max_size as usize {
+                        respond_bail!("The queue is full, try again later");
+                    }
+                }
+
+                let priority = self.state.subscriber_priority.load().await
+                    && ctx
+                        .user
+                        .real()
+                        .map(|u| u.roles().contains(&auth::Role::Subscriber))
+                        .unwrap_or_default();
+
+                let entry = queue.join(ctx.channel(), &user, priority).await?;
+                entries.push(entry);
+                sort(&mut entries);
+                self.state.publish(&entries).await;
+
+                let position = position(&entries, &user).unwrap_or_default();
+                respond!(ctx, "You joined the queue at position {}", position);
+            }
+            Some("leave") => {
+                let user = user(ctx)?;
+                let mut entries = self.state.entries.lock().await;
+
+                let entry = match entries.iter().position(|e| e.user == user) {
+                    Some(index) => entries.remove(index),
+                    None => respond_bail!("You are not in the queue"),
+                };
+
+                queue.remove(vec![entry.id]).await?;
+                self.state.publish(&entries).await;
+                respond!(ctx, "You left the queue");
+            }
+            Some("position") => {
+                let user = user(ctx)?;
+                let entries = self.state.entries.lock().await;
+
+                match position(&entries, &user) {
+                    Some(position) => respond!(
+                        ctx,
+                        "You are at position {} of {} in the queue",
+                        position,
+                        entries.len()
+                    ),
+                    None => respond!(ctx, "You are not in the queue"),
+                }
+            }
+            Some("list") => {
+                let entries = self.state.entries.lock().await;
+
+                if entries.is_empty() {
+                    respond_bail!("The queue is empty");
+                }
+
+                let listed = entries
+                    .iter()
+                    .take(LIST_LIMIT)
+                    .enumerate()
+                    .map(|(i, e)| format!("{}. {}", i + 1, e.user))
+                    .collect::<Vec<_>>();
+
+                match entries.len().saturating_sub(LIST_LIMIT) {
+                    0 => respond!(ctx, "{}", listed.join(", ")),
+                    more => respond!(ctx, "{} (and {} more)", listed.join(", "), more),
+                }
+            }
+            Some("skip") => {
+                let user = user(ctx)?;
+
+                let cost = match self.state.skip_cost.load().await {
+                    Some(cost) if cost > 0 => cost,
+                    _ => respond_bail!("Skipping ahead in the queue is not enabled"),
+                };
+
+                let currency = match self.state.currency.load().await {
+                    Some(currency) => currency,
+                    None => respond_bail!("No currency configured for stream"),
+                };
+
+                let mut entries = self.state.entries.lock().await;
+
+                let entry = match entries.iter_mut().find(|e| e.user == user) {
+                    Some(entry) if entry.skipped_at.is_some() => {
+                        respond_bail!("You have already skipped ahead")
+                    }
+                    Some(entry) => entry,
+                    None => respond_bail!("You are not in the queue"),
+                };
+
+                let balance = currency
+                    .balance_of(ctx.channel(), &user)
+                    .await?
+                    .unwrap_or_default()
+                    .balance;
+
+                if balance < cost {
+                    respond_bail!(
+                        "You need {cost} {currency} to skip ahead, you have {balance}",
+                        cost = cost,
+                        currency = currency.name,
+                        balance = balance,
+                    );
+                }
+
+                currency
+                    .balance_add(ctx.channel(), &user, -cost, Reason::Queue)
+                    .await?;
+
+                let now = Utc::now().naive_utc();
+
+                if let Err(e) = queue.skip(entry.id, now).await {
+                    currency
+                        .balance_add(ctx.channel(), &user, cost, Reason::Queue)
+                        .await?;
+
+                    return Err(e);
+                }
+
+                entry.skipped_at = Some(now);
+
+                sort(&mut entries);
+                self.state.publish(&entries).await;
+
+                let position = position(&entries, &user).unwrap_or_default();
+                respond!(ctx, "You skipped ahead to position {}", position);
+            }
+            Some("next") => {
+                ctx.check_scope(auth::Scope::QueueAdmin).await?;
+
+                let count = ctx.next_parse_optional::<usize>()?.unwrap_or(1).max(1);
+                let mut entries = self.state.entries.lock().await;
+
+                if entries.is_empty() {
+                    respond_bail!("The queue is empty");
+                }
+
+                let count = count.min(entries.len());
+                let next = entries.drain(..count).collect::<Vec<_>>();
+
+                queue.remove(next.iter().map(|e| e.id).collect()).await?;
+                self.state.publish(&entries).await;
+
+                let users = next.into_iter().map(|e| e.user).collect::<Vec<_>>();
+
+                ctx.privmsg(format!(
+                    "/me Up next: {}!",
+                    utils::human_list(&users).unwrap_or_default()
+                ))
+                .await;
+            }
+            Some("clear") => {
+                ctx.check_scope(auth::Scope::QueueAdmin).await?;
+
+                let mut entries = self.state.entries.lock().await;
+                queue.clear(ctx.channel()).await?;
+                entries.clear();
+                self.state.publish(&entries).await;
+                respond!(ctx, "Cleared the queue");
+            }
+            Some("open") => {
+                ctx.check_scope(auth::Scope::QueueAdmin).await?;
+                self.state.settings.set("open", true).await?;
+                ctx.privmsg("/me The queue is open! Type `!queue join` to join.")
+                    .await;
+            }
+            Some("close") => {
+                ctx.check_scope(auth::Scope::QueueAdmin).await?;
+                self.state.settings.set("open", false).await?;
+                ctx.privmsg("/me The queue is closed.").await;
+            }
+            _ => {
+                respond!(
+                    ctx,
+                    "Expected: join, leave, position, list, skip, next, clear, open, or close."
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the name of the user running the command.
+fn user(ctx: &command::Context) -> Result<String> {
+    match ctx.user.name() {
+        Some(name) => Ok(name.to_string()),
+        None => respond_bail!("Only real users can use the queue"),
+    }
+}
+
+/// Get the 1-based position of the given user in the queue.
+fn position(entries: &[db::QueueEntry], user: &str) -> Option<usize> {
+    entries.iter().position(|e| e.user == user).map(|i| i + 1)
+}
+
+/// Sort entries in queue order.
+///
+/// Users who paid to skip ahead come first in the order they skipped,
+/// followed by users with priority, followed by everyone else in the order
+/// they joined.
+fn sort(entries: &mut Vec<db::QueueEntry>) {
+    entries.sort_by_key(|e| (e.skipped_at.is_none(), e.skipped_at, !e.priority, e.id));
+}
+
+/// State shared between the command and the overlay updater.
+struct State {
+    enabled: settings::Var<bool>,
+    open: settings::Var<bool>,
+    max_size: settings::Var<Option<u32>>,
+    subscriber_priority: settings::Var<bool>,
+    skip_cost: settings::Var<Option<i64>>,
+    settings: settings::Settings,
+    queue: injector::Var<Option<db::Queue>>,
+    currency: injector::Var<Option<Currency>>,
+    queue_bus: Arc<bus::Bus<bus::Queue>>,
+    /// Users in the queue, in queue order.
+    entries: Mutex<Vec<db::QueueEntry>>,
+}
+
+impl State {
+    /// Restore the queue from the database.
+    async fn restore(&self, channel: &str) -> Result<()> {
+        let queue = match self.queue.load().await {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+
+        let mut entries = self.entries.lock().await;
+        *entries = queue.entries(channel).await?;
+        sort(&mut entries);
+        self.publish(&entries).await;
+        Ok(())
+    }
+
+    /// Publish the current state of the queue to overlays.
+    async fn publish(&self, entries: &[db::QueueEntry]) {
+        let open = self.open.load().await;
+        let max_size = self.max_size.load().await;
+        self.send(open, max_size, entries).await;
+    }
+
+    /// Send the given state of the queue to overlays.
+    async fn send(&self, open: bool, max_size: Option<u32>, entries: &[db::QueueEntry]) {
+        self.queue_bus
+            .send(bus::Queue::QueueUpdate {
+                open,
+                max_size,
+                entries: entries.to_vec(),
+            })
+            .await;
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "queue"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            futures,
+            sender,
+            settings,
+            queue_bus,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("queue");
+
+        let state = Arc::new(State {
+            enabled: settings.var("enabled", false).await?,
+            open: settings.var("open", false).await?,
+            max_size: settings.optional("max-size").await?,
+            subscriber_priority: settings.var("subscriber-priority", false).await?,
+            skip_cost: settings.optional("skip-cost").await?,
+            settings: settings.clone(),
+            queue: injector.var().await?,
+            currency: injector.var().await?,
+            queue_bus: queue_bus.clone(),
+            entries: Mutex::new(Vec::new()),
+        });
+
+        handlers.insert(
+            "queue",
+            Queue {
+                state: state.clone(),
+            },
+        );
+
+        let (mut open_stream, mut open) = settings.stream("open").or_default().await?;
+        let (mut max_size_stream, mut max_size) = settings.stream("max-size").optional().await?;
+        let channel = sender.channel().to_string();
+
+        let future = async move {
+            if let Err(e) = state.restore(&channel).await {
+                log_error!(e, "failed to restore queue");
+            }
+
+            // NB: republish the queue whenever it is opened, closed, or resized
+            // so that overlays stay in sync with the settings.
+            loop {
+                futures::select! {
+                    update = open_stream.select_next_some() => {
+                        open = update;
+                    }
+                    update = max_size_stream.select_next_some() => {
+                        max_size = update;
+                    }
+                }
+
+                let entries = state.entries.lock().await;
+                state.send(open, max_size, &entries).await;
+            }
+        };
+
+        futures.push(future.boxed());
+        Ok(())
+    }
+}
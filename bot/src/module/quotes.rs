@@ -0,0 +1,167 @@
+use crate::auth;
+use crate::command;
+use crate::db;
+use crate::module;
+use crate::prelude::*;
+use crate::stream_info;
+use crate::utils;
+use anyhow::Result;
+
+/// How many quotes to show at most when searching.
+const SEARCH_LIMIT: i64 = 5;
+
+/// Handler for the !quote command.
+pub struct Quote {
+    enabled: settings::Var<bool>,
+    quotes: injector::Var<Option<db::Quotes>>,
+    stream_info: stream_info::StreamInfo,
+}
+
+#[async_trait]
+impl command::Handler for Quote {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Quote)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let quotes = match self.quotes.load().await {
+            Some(quotes) => quotes,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("add") => {
+                ctx.check_scope(auth::Scope::QuoteEdit).await?;
+
+                let quote = ctx.rest().trim().to_string();
+
+                if quote.is_empty() {
+                    respond_bail!("Expected: add <text>");
+                }
+
+                let added_by = match ctx.user.name() {
+                    Some(name) => name.to_string(),
+                    None => respond_bail!("Only real users can add quotes"),
+                };
+
+                let game = self.stream_info.data.read().game.clone();
+
+                let quote = quotes
+                    .add(ctx.channel(), &quote, &added_by, game.as_deref())
+                    .await?;
+
+                respond!(ctx, "Added quote #{}", quote.id);
+            }
+            Some("delete") => {
+                ctx.check_scope(auth::Scope::QuoteEdit).await?;
+
+                let id = ctx.next_parse::<i32, _>("<id>")?;
+
+                if quotes.delete(ctx.channel(), id).await? {
+                    respond!(ctx, "Deleted quote #{}", id);
+                } else {
+                    respond!(ctx, "No quote with id #{}", id);
+                }
+            }
+            Some("search") => {
+                let terms = utils::TrimmedWords::new(ctx.rest())
+                    .map(String::from)
+                    .collect::<Vec<_>>();
+
+                if terms.is_empty() {
+                    respond_bail!("Expected: search <terms>");
+                }
+
+                let found = quotes
+                    .search(ctx.channel(), &terms, SEARCH_LIMIT + 1)
+                    .await?;
+
+                match found.as_slice() {
+                    [] => respond!(ctx, "No quotes matching your search"),
+                    [quote] => respond!(ctx, "{}", format_quote(quote)),
+                    found => {
+                        let ids = found
+                            .iter()
+                            .take(SEARCH_LIMIT as usize)
+                            .map(|q| format!("#{}", q.id))
+                            .collect::<Vec<_>>();
+
+                        let more = if found.len() > SEARCH_LIMIT as usize {
+                            ", and more"
+                        } else {
+                            ""
+                        };
+
+                        respond!(
+                            ctx,
+                            "Found quotes {}{}. Use `!quote <id>` to show one.",
+                            ids.join(", "),
+                            more
+                        );
+                    }
+                }
+            }
+            Some("random") | None => match quotes.random(ctx.channel()).await? {
+                Some(quote) => respond!(ctx, "{}", format_quote(&quote)),
+                None => respond!(ctx, "There are no quotes yet"),
+            },
+            Some(id) => {
+                let id = str::parse::<i32>(id)
+                    .map_err(|_| respond_err!("Expected: <id>, random, search, add, or delete."))?;
+
+                match quotes.get(ctx.channel(), id).await? {
+                    Some(quote) => respond!(ctx, "{}", format_quote(&quote)),
+                    None => respond!(ctx, "No quote with id #{}", id),
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Format a quote to post in chat.
+fn format_quote(quote: &db::Quote) -> String {
+    let date = quote.created_at.format("%Y-%m-%d");
+
+    match &quote.game {
+        Some(game) => format!("#{}: {} ({}, {})", quote.id, quote.quote, game, date),
+        None => format!("#{}: {} ({})", quote.id, quote.quote, date),
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "quotes"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            stream_info,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "quote",
+            Quote {
+                enabled: settings.var("quotes/enabled", false).await?,
+                quotes: injector.var().await?,
+                stream_info: stream_info.clone(),
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,379 @@
+use crate::auth;
+use crate::command;
+use crate::currency::{Currency, Reason};
+use crate::db;
+use crate::irc;
+use crate::module;
+use crate::prelude::*;
+use crate::utils;
+use anyhow::Result;
+use tokio::sync::Mutex;
+
+/// State shared between the `!raffle` command and the entry hook.
+struct State {
+    enabled: settings::Var<bool>,
+    raffles: injector::Var<Option<db::Raffles>>,
+    currency: injector::Var<Option<Currency>>,
+    subscriber_luck: settings::Var<u32>,
+    /// The raffle which is currently running.
+    current: Mutex<Option<db::Raffle>>,
+}
+
+impl State {
+    /// Draw up to `count` winners from the given entries, excluding users
+    /// who have already won.
+    async fn draw(&self, raffles: &db::Raffles, id: i32, count: usize) -> Result<Vec<String>> {
+        use rand::Rng as _;
+
+        let luck = self.subscriber_luck.load().await as u64;
+
+        let mut entries = raffles
+            .entries(id)
+            .await?
+            .into_iter()
+            .filter(|e| !e.won && e.tickets > 0)
+            .map(|e| {
+                let weight = e.tickets as u64 * if e.subscriber { luck } else { 100 };
+                (e.user, weight)
+            })
+            .filter(|(_, weight)| *weight > 0)
+            .collect::<Vec<_>>();
+
+        let winners = {
+            let mut rng = rand::thread_rng();
+            let mut winners = Vec::new();
+
+            while winners.len() < count && !entries.is_empty() {
+                let total = entries.iter().map(|(_, w)| *w).sum::<u64>();
+                let mut n = rng.gen_range(0, total);
+
+                let index = entries
+                    .iter()
+                    .position(|(_, w)| match n.checked_sub(*w) {
+                        Some(rest) => {
+                            n = rest;
+                            false
+                        }
+                        None => true,
+                    })
+                    .unwrap_or_default();
+
+                winners.push(entries.swap_remove(index).0);
+            }
+
+            winners
+        };
+
+        for winner in &winners {
+            raffles.mark_winner(id, winner).await?;
+        }
+
+        Ok(winners)
+    }
+}
+
+/// Handler for the !raffle command.
+pub struct Raffle {
+    state: Arc<State>,
+}
+
+#[async_trait]
+impl command::Handler for Raffle {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Raffle)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.state.enabled.load().await {
+            return Ok(());
+        }
+
+        let raffles = match self.state.raffles.load().await {
+            Some(raffles) => raffles,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("open") => {
+                let keyword = ctx.next_str("<keyword> [ticket-cost] [max-tickets]")?;
+                let ticket_cost = ctx.next_parse_optional::<i64>()?.unwrap_or_default().max(0);
+                let max_tickets = ctx.next_parse_optional::<i32>()?.unwrap_or(1).max(1);
+
+                if ticket_cost > 0 && self.state.currency.load().await.is_none() {
+                    respond_bail!("No currency configured for stream, so tickets can't be sold");
+                }
+
+                let raffle = raffles
+                    .open(ctx.channel(), &keyword, ticket_cost, max_tickets)
+                    .await?;
+                *self.state.current.lock().await = Some(raffle);
+
+                if ticket_cost > 0 {
+                    ctx.privmsg(format!(
+                        "/me A raffle is open! Type `{keyword} [tickets]` to enter, \
+                         tickets cost {cost} each (max {max}).",
+                        keyword = keyword,
+                        cost = ticket_cost,
+                        max = max_tickets,
+                    ))
+                    .await;
+                } else {
+                    ctx.privmsg(format!(
+                        "/me A raffle is open! Type `{}` to enter.",
+                        keyword
+                    ))
+                    .await;
+                }
+            }
+            Some("close") => {
+                let mut current = self.state.current.lock().await;
+
+                let raffle = match current.as_mut() {
+                    Some(raffle) if raffle.open => raffle,
+                    _ => respond_bail!("No raffle is accepting entries"),
+                };
+
+                raffles.close(raffle.id).await?;
+                raffle.open = false;
+                respond!(ctx, "No more entries are accepted to the raffle.");
+            }
+            Some(command @ "draw") | Some(command @ "redraw") => {
+                let count = ctx.next_parse_optional::<usize>()?.unwrap_or(1).max(1);
+                let mut current = self.state.current.lock().await;
+
+                let raffle = match current.as_mut() {
+                    Some(raffle) => raffle,
+                    None => respond_bail!("No raffle is running"),
+                };
+
+                if raffle.open {
+                    raffles.close(raffle.id).await?;
+                    raffle.open = false;
+                }
+
+                let winners = self.state.draw(&raffles, raffle.id, count).await?;
+
+                let winners = match utils::human_list(&winners) {
+                    Some(winners) => winners,
+                    None => respond_bail!("There are no entries left to draw from"),
+                };
+
+                if command == "redraw" {
+                    ctx.privmsg(format!("/me Redrawing... the new winner is {}!", winners))
+                        .await;
+                } else {
+                    ctx.privmsg(format!("/me The raffle winner is {}!", winners))
+                        .await;
+                }
+            }
+            Some("status") => {
+                let raffle = match self.state.current.lock().await.clone() {
+                    Some(raffle) => raffle,
+                    None => respond_bail!("No raffle is running"),
+                };
+
+                let entries = raffles.entries(raffle.id).await?;
+                let tickets = entries.iter().map(|e| e.tickets as i64).sum::<i64>();
+
+                let winners = entries
+                    .iter()
+                    .filter(|e| e.won)
+                    .map(|e| e.user.clone())
+                    .collect::<Vec<_>>();
+
+                respond!(
+                    ctx,
+                    "Raffle `{keyword}` is {state} with {count} entrants and {tickets} tickets. Winners: {winners}.",
+                    keyword = raffle.keyword,
+                    state = if raffle.open { "open" } else { "closed" },
+                    count = entries.len(),
+                    tickets = tickets,
+                    winners = utils::human_list(&winners).unwrap_or_else(|| String::from("none")),
+                );
+            }
+            Some("end") => {
+                let raffle = match self.state.current.lock().await.take() {
+                    Some(raffle) => raffle,
+                    None => respond_bail!("No raffle is running"),
+                };
+
+                raffles.end(raffle.id).await?;
+                respond!(ctx, "Ended the raffle `{}`.", raffle.keyword);
+            }
+            _ => {
+                respond!(ctx, "Expected: open, close, draw, redraw, status, or end.");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Message hook which enters users into the current raffle.
+struct Entry {
+    state: Arc<State>,
+}
+
+#[async_trait]
+impl command::MessageHook for Entry {
+    async fn peek(&self, user: &irc::User, m: &str) -> Result<()> {
+        if !self.state.enabled.load().await {
+            return Ok(());
+        }
+
+        let real = match user.real() {
+            Some(user) => user,
+            None => return Ok(()),
+        };
+
+        let mut words = utils::TrimmedWords::new(m);
+
+        let keyword = match words.next() {
+            Some(keyword) => keyword,
+            None => return Ok(()),
+        };
+
+        // NB: hold on to the lock to serialize entries.
+        let current = self.state.current.lock().await;
+
+        let raffle = match current.as_ref() {
+            Some(raffle) if raffle.open && raffle.keyword.eq_ignore_ascii_case(keyword) => raffle,
+            _ => return Ok(()),
+        };
+
+        let raffles = match self.state.raffles.load().await {
+            Some(raffles) => raffles,
+            None => return Ok(()),
+        };
+
+        let entry = raffles.entry(raffle.id, real.name()).await?;
+        let held = entry.as_ref().map(|e| e.tickets).unwrap_or_default();
+
+        // NB: free raffles only allow a single ticket per user.
+        let max_tickets = if raffle.ticket_cost > 0 {
+            raffle.max_tickets
+        } else {
+            1
+        };
+
+        let wanted = words
+            .next()
+            .and_then(|w| str::parse::<i32>(w).ok())
+            .unwrap_or(1)
+            .max(1);
+
+        let bought = wanted.min(max_tickets - held);
+
+        if bought <= 0 {
+            return Ok(());
+        }
+
+        let mut charged = None;
+
+        if raffle.ticket_cost > 0 {
+            let currency = match self.state.currency.load().await {
+                Some(currency) => currency,
+                None => return Ok(()),
+            };
+
+            let cost = raffle.ticket_cost * bought as i64;
+
+            let balance = currency
+                .balance_of(real.channel(), real.name())
+                .await?
+                .unwrap_or_default()
+                .balance;
+
+            if balance < cost {
+                respond!(
+                    real,
+                    "You need {cost} {currency} to buy {bought} raffle tickets, you have {balance}",
+                    cost = cost,
+                    currency = currency.name,
+                    bought = bought,
+                    balance = balance,
+                );
+                return Ok(());
+            }
+
+            currency
+                .balance_add(real.channel(), real.name(), -cost, Reason::Raffle)
+                .await?;
+
+            charged = Some((currency, cost));
+        }
+
+        let result = raffles
+            .enter(db::RaffleEntry {
+                raffle_id: raffle.id,
+                user: real.name().to_string(),
+                tickets: held + bought,
+                subscriber: real.roles().contains(&auth::Role::Subscriber),
+                won: entry.map(|e| e.won).unwrap_or_default(),
+            })
+            .await;
+
+        if let Err(e) = result {
+            if let Some((currency, cost)) = charged {
+                currency
+                    .balance_add(real.channel(), real.name(), cost, Reason::Raffle)
+                    .await?;
+            }
+
+            return Err(e);
+        }
+
+        if raffle.ticket_cost > 0 {
+            respond!(
+                real,
+                "You now hold {} raffle tickets. Good luck!",
+                held + bought
+            );
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "raffle"
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            message_hooks,
+            sender,
+            settings,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("raffle");
+
+        let state = Arc::new(State {
+            enabled: settings.var("enabled", false).await?,
+            raffles: injector.var().await?,
+            currency: injector.var().await?,
+            subscriber_luck: settings.var("subscriber-luck%", 100).await?,
+            current: Mutex::new(None),
+        });
+
+        // NB: restore the raffle which was running when the bot was stopped.
+        if let Some(raffles) = state.raffles.load().await {
+            *state.current.lock().await = raffles.current(sender.channel()).await?;
+        }
+
+        message_hooks.insert(Box::new(Entry {
+            state: state.clone(),
+        }));
+
+        handlers.insert("raffle", Raffle { state });
+        Ok(())
+    }
+}
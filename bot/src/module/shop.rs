@@ -0,0 +1,489 @@
+use crate::auth;
+use crate::bus;
+use crate::command;
+use crate::currency::{Currency, Reason};
+use crate::db;
+use crate::module;
+use crate::prelude::*;
+use crate::template::Template;
+use crate::utils;
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Resolve a pending redemption, refunding the user if it's rejected.
+///
+/// Returns `None` if there is no pending redemption with the given id.
+pub(crate) async fn resolve(
+    shop: &db::Shop,
+    currency: Option<&Currency>,
+    global_bus: &bus::Bus<bus::Global>,
+    channel: &str,
+    id: i32,
+    state: db::RedemptionState,
+) -> Result<Option<db::ShopRedemption>> {
+    let redemption = match (state, currency) {
+        (db::RedemptionState::Rejected, None) => {
+            bail!("no currency configured to refund the redemption with");
+        }
+        // NB: refund before the redemption is marked as rejected, and take the
+        // refund back if that fails, so the user never ends up without both
+        // their item and their currency.
+        (db::RedemptionState::Rejected, Some(currency)) => {
+            let pending = match shop.pending(channel, id).await? {
+                Some(pending) => pending,
+                None => return Ok(None),
+            };
+
+            currency
+                .balance_add(channel, &pending.user, pending.cost, Reason::Shop)
+                .await?;
+
+            match shop.resolve(channel, id, state).await {
+                Ok(Some(redemption)) => redemption,
+                result => {
+                    currency
+                        .balance_add(channel, &pending.user, -pending.cost, Reason::Shop)
+                        .await?;
+
+                    return result;
+                }
+            }
+        }
+        _ => match shop.resolve(channel, id, state).await? {
+            Some(redemption) => redemption,
+            None => return Ok(None),
+        },
+    };
+
+    global_bus
+        .send(bus::Global::ShopRedemption {
+            redemption: redemption.clone(),
+        })
+        .await;
+
+    Ok(Some(redemption))
+}
+
+/// Variables available to the response template of an item.
+#[derive(Debug, serde::Serialize)]
+struct Vars<'a> {
+    name: &'a str,
+    item: &'a str,
+    cost: i64,
+    currency: &'a str,
+    message: Option<&'a str>,
+}
+
+/// Handler for the !shop command.
+pub struct Shop {
+    enabled: settings::Var<bool>,
+    shop: injector::Var<Option<db::Shop>>,
+    currency: injector::Var<Option<Currency>>,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+}
+
+impl Shop {
+    /// Load an item, or bail with a helpful message.
+    async fn item(&self, shop: &db::Shop, channel: &str, name: &str) -> Result<db::ShopItem> {
+        match shop.item(channel, name).await? {
+            Some(item) => Ok(item),
+            None => respond_bail!("No item named `{}` in the shop", name),
+        }
+    }
+}
+
+#[async_trait]
+impl command::Handler for Shop {
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let shop = match self.shop.load().await {
+            Some(shop) => shop,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            None => {
+                let items = shop
+                    .items(ctx.channel())
+                    .await?
+                    .into_iter()
+                    .filter(|i| !i.disabled && i.stock.map(|s| s > 0).unwrap_or(true))
+                    .map(|i| format!("{} ({})", i.name, i.cost))
+                    .collect::<Vec<_>>();
+
+                match utils::human_list(&items) {
+                    Some(items) => respond!(ctx, "Redeem with `!redeem <item>`: {}", items),
+                    None => respond!(ctx, "The shop is empty :("),
+                }
+            }
+            Some("add") => {
+                ctx.check_scope(auth::Scope::ShopEdit).await?;
+
+                let name = ctx.next_str("<name> <cost> [response]")?;
+                let cost = ctx.next_parse::<i64, _>("<name> <cost> [response]")?;
+                let response = ctx.rest().trim().to_string();
+
+                let response = if response.is_empty() {
+                    None
+                } else {
+                    Template::compile(&response)?;
+                    Some(response)
+                };
+
+                shop.edit(db::ShopItem {
+                    channel: ctx.channel().to_string(),
+                    name: name.clone(),
+                    cost: cost.max(0),
+                    cooldown: None,
+                    stock: None,
+                    scope: None,
+                    response,
+                    manual: false,
+                    disabled: false,
+                })
+                .await?;
+
+                respond!(ctx, "Added `{}` to the shop", name);
+            }
+            Some("set") => {
+                ctx.check_scope(auth::Scope::ShopEdit).await?;
+
+                let name = ctx.next_str("<name> <field> <value>")?;
+                let field = ctx.next_str("<name> <field> <value>")?;
+                let value = ctx.rest().trim().to_string();
+                let mut item = self.item(&shop, ctx.channel(), &name).await?;
+                let none = value == "none";
+
+                match field.as_str() {
+                    "cost" => {
+                        item.cost = str::parse::<i64>(&value)
+                            .map_err(|_| respond_err!("Expected a number"))?
+                            .max(0);
+                    }
+                    "cooldown" if none => item.cooldown = None,
+                    "cooldown" => {
+                        let cooldown = str::parse::<utils::Duration>(&value)
+                            .map_err(|_| respond_err!("Expected a duration, like `5m`"))?;
+                        item.cooldown = Some(cooldown.num_seconds() as i32);
+                    }
+                    "stock" if none => item.stock = None,
+                    "stock" => {
+                        item.stock = Some(
+                            str::parse::<i32>(&value)
+                                .map_err(|_| respond_err!("Expected a number"))?
+                                .max(0),
+                        );
+                    }
+                    "scope" if none => item.scope = None,
+                    "scope" => {
+                        if str::parse::<auth::Scope>(&value)? == auth::Scope::Unknown {
+                            respond_bail!("No scope named `{}`", value);
+                        }
+
+                        item.scope = Some(value.clone());
+                    }
+                    "manual" => {
+                        item.manual = str::parse::<bool>(&value)
+                            .map_err(|_| respond_err!("Expected `true` or `false`"))?;
+                    }
+                    "response" if none => item.response = None,
+                    "response" => {
+                        Template::compile(&value)?;
+                        item.response = Some(value.clone());
+                    }
+                    _ => {
+                        respond_bail!(
+                            "Expected one of: cost, cooldown, stock, scope, manual, response"
+                        );
+                    }
+                }
+
+                shop.edit(item).await?;
+                respond!(ctx, "Updated {} of `{}`", field, name);
+            }
+            Some("delete") => {
+                ctx.check_scope(auth::Scope::ShopEdit).await?;
+
+                let name = ctx.next_str("<name>")?;
+
+                if shop.delete(ctx.channel(), &name).await? {
+                    respond!(ctx, "Deleted `{}` from the shop", name);
+                } else {
+                    respond!(ctx, "No item named `{}` in the shop", name);
+                }
+            }
+            Some(command @ "enable") | Some(command @ "disable") => {
+                ctx.check_scope(auth::Scope::ShopEdit).await?;
+
+                let name = ctx.next_str("<name>")?;
+                let mut item = self.item(&shop, ctx.channel(), &name).await?;
+                item.disabled = command == "disable";
+                shop.edit(item).await?;
+                respond!(ctx, "{}d `{}`", command, name);
+            }
+            Some("pending") => {
+                ctx.check_scope(auth::Scope::ShopFulfil).await?;
+
+                let pending = shop
+                    .redemptions(ctx.channel(), Some(db::RedemptionState::Pending), 5)
+                    .await?
+                    .into_iter()
+                    .map(|r| format!("#{} {} for {}", r.id, r.item, r.user))
+                    .collect::<Vec<_>>();
+
+                match utils::human_list(&pending) {
+                    Some(pending) => respond!(ctx, "Pending redemptions: {}", pending),
+                    None => respond!(ctx, "No pending redemptions"),
+                }
+            }
+            Some(command @ "fulfil") | Some(command @ "reject") => {
+                ctx.check_scope(auth::Scope::ShopFulfil).await?;
+
+                let id = ctx.next_parse::<i32, _>("<id>")?;
+
+                let state = if command == "reject" {
+                    db::RedemptionState::Rejected
+                } else {
+                    db::RedemptionState::Fulfilled
+                };
+
+                let currency = self.currency.load().await;
+
+                let redemption = resolve(
+                    &shop,
+                    currency.as_ref(),
+                    &self.global_bus,
+                    ctx.channel(),
+                    id,
+                    state,
+                )
+                .await?;
+
+                match redemption {
+                    Some(r) if state == db::RedemptionState::Rejected => {
+                        respond!(
+                            ctx,
+                            "Rejected #{} and refunded {} to {}",
+                            r.id,
+                            r.cost,
+                            r.user
+                        );
+                    }
+                    Some(r) => respond!(ctx, "Fulfilled #{} for {}", r.id, r.user),
+                    None => respond!(ctx, "No pending redemption with id #{}", id),
+                }
+            }
+            Some(..) => {
+                respond!(
+                    ctx,
+                    "Expected: add, set, delete, enable, disable, pending, fulfil, or reject"
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Handler for the !redeem command.
+pub struct Redeem {
+    enabled: settings::Var<bool>,
+    shop: injector::Var<Option<db::Shop>>,
+    currency: injector::Var<Option<Currency>>,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+    /// When each item was last redeemed.
+    cooldowns: Mutex<HashMap<String, Instant>>,
+}
+
+#[async_trait]
+impl command::Handler for Redeem {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::ShopRedeem)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let shop = match self.shop.load().await {
+            Some(shop) => shop,
+            None => return Ok(()),
+        };
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => respond_bail!("No currency configured for stream, sorry :("),
+        };
+
+        let name = ctx.next_str("<item> [message]")?;
+        let message = ctx.rest().trim().to_string();
+        let message = if message.is_empty() {
+            None
+        } else {
+            Some(message)
+        };
+
+        let item = match shop.item(ctx.channel(), &name).await? {
+            Some(item) if !item.disabled => item,
+            _ => respond_bail!("No item named `{}` in the shop", name),
+        };
+
+        if let Some(scope) = &item.scope {
+            ctx.check_scope(str::parse::<auth::Scope>(scope)?).await?;
+        }
+
+        if item.stock == Some(0) {
+            respond_bail!("`{}` is out of stock", item.name);
+        }
+
+        let user = match ctx.user.real() {
+            Some(user) => user,
+            None => respond_bail!("Only real users can redeem items"),
+        };
+
+        let mut cooldowns = self.cooldowns.lock().await;
+
+        if let (Some(cooldown), Some(last)) = (item.cooldown, cooldowns.get(&item.name)) {
+            let cooldown = Duration::from_secs(cooldown.max(0) as u64);
+            let elapsed = last.elapsed();
+
+            if elapsed < cooldown {
+                respond_bail!(
+                    "`{}` can be redeemed again in {}",
+                    item.name,
+                    utils::compact_duration(cooldown - elapsed)
+                );
+            }
+        }
+
+        let balance = currency
+            .balance_of(user.channel(), user.name())
+            .await?
+            .unwrap_or_default()
+            .balance;
+
+        if balance < item.cost {
+            respond_bail!(
+                "You need {cost} {currency} to redeem `{item}`, you have {balance}",
+                cost = item.cost,
+                currency = currency.name,
+                item = item.name,
+                balance = balance,
+            );
+        }
+
+        // NB: deduct before recording the redemption, and refund if it can't
+        // be recorded, so that nothing is ever redeemed for free.
+        currency
+            .balance_add(user.channel(), user.name(), -item.cost, Reason::Shop)
+            .await?;
+
+        let redemption = match shop.redeem(&item, user.name(), message.as_deref()).await {
+            Ok(redemption) => redemption,
+            Err(e) => {
+                log_error!(e, "failed to redeem item");
+
+                currency
+                    .balance_add(user.channel(), user.name(), item.cost, Reason::Shop)
+                    .await?;
+
+                respond_bail!("Failed to redeem `{}`, sorry :(", item.name);
+            }
+        };
+
+        cooldowns.insert(item.name.clone(), Instant::now());
+        drop(cooldowns);
+
+        self.global_bus
+            .send(bus::Global::ShopRedemption {
+                redemption: redemption.clone(),
+            })
+            .await;
+
+        match &item.response {
+            Some(response) => {
+                let response = Template::compile(response)?.render_to_string(Vars {
+                    name: user.display_name(),
+                    item: &item.name,
+                    cost: item.cost,
+                    currency: &currency.name,
+                    message: message.as_deref(),
+                })?;
+
+                ctx.privmsg(response).await;
+            }
+            None if item.manual => {
+                respond!(
+                    user,
+                    "Redeemed `{}` for {} {}, the streamer will get to it soon (#{})",
+                    item.name,
+                    item.cost,
+                    currency.name,
+                    redemption.id
+                );
+            }
+            None => {
+                respond!(
+                    user,
+                    "Redeemed `{}` for {} {}!",
+                    item.name,
+                    item.cost,
+                    currency.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Module;
+
+#[async_trait]
+impl super::Module for Module {
+    fn ty(&self) -> &'static str {
+        "shop"
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            global_bus,
+            ..
+        }: module::HookContext<'_>,
+    ) -> Result<()> {
+        let enabled = settings.var("shop/enabled", false).await?;
+
+        handlers.insert(
+            "shop",
+            Shop {
+                enabled: enabled.clone(),
+                shop: injector.var().await?,
+                currency: injector.var().await?,
+                global_bus: global_bus.clone(),
+            },
+        );
+
+        handlers.insert(
+            "redeem",
+            Redeem {
+                enabled,
+                shop: injector.var().await?,
+                currency: injector.var().await?,
+                global_bus: global_bus.clone(),
+                cooldowns: Mutex::new(Default::default()),
+            },
+        );
+
+        Ok(())
+    }
+}
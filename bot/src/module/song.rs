@@ -1,6 +1,6 @@
 use crate::auth::Scope;
 use crate::command;
-use crate::currency::Currency;
+use crate::currency::{Currency, Reason};
 use crate::irc;
 use crate::module;
 use crate::player;
@@ -86,14 +86,14 @@ impl Handler {
             }
         };
 
-        let (what, has_scope, enabled) = match track_id {
+        let (what, scope, enabled) = match track_id {
             TrackId::Spotify(..) => {
                 let enabled = spotify.enabled.load().await;
-                ("Spotify", user.has_scope(Scope::SongSpotify).await, enabled)
+                ("Spotify", Scope::SongSpotify, enabled)
             }
             TrackId::YouTube(..) => {
                 let enabled = youtube.enabled.load().await;
-                ("YouTube", user.has_scope(Scope::SongYouTube).await, enabled)
+                ("YouTube", Scope::SongYouTube, enabled)
             }
         };
 
@@ -106,7 +106,7 @@ impl Handler {
             return Ok(());
         }
 
-        if !has_scope {
+        if !user.has_scope(scope).await {
             respond!(
                 user,
                 "You are not allowed to do {what} requests, sorry :(",
@@ -279,6 +279,8 @@ impl Handler {
             }
         };
 
+        user.consume_scope(scope).await;
+
         let currency = match currency.as_ref() {
             Some(currency) if request_reward > 0 => currency,
             _ => {
@@ -298,7 +300,12 @@ impl Handler {
         };
 
         match currency
-            .balance_add(user.channel(), user.name(), request_reward as i64)
+            .balance_add(
+                user.channel(),
+                user.name(),
+                request_reward as i64,
+                Reason::SongRequest,
+            )
             .await
         {
             Ok(()) => {
@@ -735,6 +742,11 @@ impl module::Module for Module {
         "song"
     }
 
+    /// There is only one player, which belongs to the streamer.
+    fn extra_channels(&self) -> bool {
+        false
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
@@ -1,7 +1,7 @@
 use crate::api;
 use crate::auth::Scope;
 use crate::command;
-use crate::currency::Currency;
+use crate::currency::{Currency, Reason};
 use crate::module;
 use crate::prelude::*;
 use crate::utils::{Cooldown, Duration};
@@ -59,11 +59,16 @@ impl command::Handler for Handler {
         let total_reward = reward * u.len() as i64;
 
         currency
-            .balance_add(user.channel(), &user.streamer().name, -total_reward)
+            .balance_add(
+                user.channel(),
+                &user.streamer().name,
+                -total_reward,
+                Reason::Swearjar,
+            )
             .await?;
 
         currency
-            .balances_increment(user.channel(), u, reward, 0)
+            .balances_increment(user.channel(), u, reward, 0, Reason::Swearjar)
             .await?;
 
         user.sender().privmsg(format!(
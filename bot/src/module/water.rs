@@ -1,6 +1,6 @@
 use crate::auth;
 use crate::command;
-use crate::currency::Currency;
+use crate::currency::{Currency, Reason};
 use crate::module;
 use crate::prelude::*;
 use crate::stream_info;
@@ -98,7 +98,7 @@ impl command::Handler for Handler {
                 .await;
 
                 if let Err(e) = currency
-                    .balance_add(ctx.channel(), &reward.user, -reward.amount)
+                    .balance_add(ctx.channel(), &reward.user, -reward.amount, Reason::Water)
                     .await
                 {
                     log::error!("failed to undo water from database: {}", e);
@@ -139,7 +139,7 @@ impl command::Handler for Handler {
                 );
 
                 if let Err(e) = currency
-                    .balance_add(ctx.channel(), user.name(), amount)
+                    .balance_add(ctx.channel(), user.name(), amount, Reason::Water)
                     .await
                 {
                     log::error!("failed to appply water balance: {}", e);
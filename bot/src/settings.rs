@@ -22,6 +22,9 @@ use tokio::{
 pub use crate::injector::Var;
 
 const SEPARATOR: char = '/';
+/// Prefix of settings belonging to an additional channel, like
+/// `channel/<name>/chat/join-message`.
+const CHANNEL_PREFIX: &str = "channel/";
 
 type EventSender = mpsc::UnboundedSender<Event<serde_json::Value>>;
 type Subscriptions = Arc<RwLock<HashMap<String, Vec<EventSender>>>>;
@@ -254,12 +257,31 @@ impl Schema {
 
     /// Lookup the given type by key.
     pub fn lookup(&self, key: &str) -> Option<SchemaType> {
-        self.types.get(key).cloned()
+        self.get(key).cloned()
     }
 
     /// Test if schema contains the given key.
     pub fn contains(&self, key: &str) -> bool {
-        self.types.contains_key(key)
+        self.get(key).is_some()
+    }
+
+    /// Get the type of the given key.
+    ///
+    /// Settings of additional channels share the schema of the channel of the
+    /// streamer, so `channel/<name>/chat/join-message` has the same type as
+    /// `chat/join-message`.
+    fn get(&self, key: &str) -> Option<&SchemaType> {
+        if let Some(schema) = self.types.get(key) {
+            return Some(schema);
+        }
+
+        if !key.starts_with(CHANNEL_PREFIX) {
+            return None;
+        }
+
+        let rest = &key[CHANNEL_PREFIX.len()..];
+        let index = rest.find(SEPARATOR)?;
+        self.types.get(&rest[index + 1..])
     }
 }
 
@@ -415,7 +437,7 @@ impl Settings {
     /// Lookup the given schema.
     pub fn lookup(&self, key: &str) -> Option<&SchemaType> {
         let key = self.key(key);
-        self.inner.schema.types.get(key.as_ref())
+        self.inner.schema.get(key.as_ref())
     }
 
     /// Get a setting by prefix.
@@ -476,7 +498,7 @@ impl Settings {
     {
         let key = self.key(key);
 
-        let schema = match self.inner.schema.types.get(key.as_ref()) {
+        let schema = match self.inner.schema.get(key.as_ref()) {
             Some(schema) => schema,
             None => return Ok(None),
         };
@@ -609,6 +631,25 @@ impl Settings {
                     });
                 }
 
+                // settings of additional channels are only listed once they've
+                // been set.
+                for (key, value) in &values {
+                    if !key.starts_with(CHANNEL_PREFIX) {
+                        continue;
+                    }
+
+                    let schema = match inner.schema.get(key) {
+                        Some(schema) => schema,
+                        None => continue,
+                    };
+
+                    settings.push(Setting {
+                        schema: schema.clone(),
+                        key: key.to_string(),
+                        value: serde_json::from_str(value)?,
+                    });
+                }
+
                 Ok(settings)
             })
             .await
@@ -659,6 +700,11 @@ impl Settings {
         }
     }
 
+    /// Create settings scoped to the given additional channel.
+    pub fn channel(&self, name: &str) -> Settings {
+        self.scoped(&format!("{}{}", CHANNEL_PREFIX, name))
+    }
+
     /// Initialize the value from the database.
     pub fn stream<'a, T>(&'a self, key: &str) -> StreamBuilder<'_, T> {
         let key = self.key(key);
@@ -1333,3 +1379,17 @@ impl fmt::Display for Type {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Schema;
+
+    #[test]
+    fn test_load_static() {
+        let schema = Schema::load_static().expect("settings.yaml to load");
+        assert!(schema.contains("chat/join-message"));
+        assert!(schema.contains("channel/setbac/chat/join-message"));
+        assert!(!schema.contains("channel/setbac"));
+        assert!(!schema.contains("channel/setbac/chat/missing"));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::player;
 use crate::prelude::*;
-use crate::template::Template;
+use crate::stream_info;
+use crate::template::{StreamVars, Template};
 use crate::utils;
 use anyhow::Result;
 use std::fs::File;
@@ -9,6 +10,16 @@ use std::path::PathBuf;
 static DEFAULT_CURRENT_SONG_TEMPLATE: &str = "Song: {{name}}{{#if artists}} by {{artists}}{{/if}}{{#if paused}} (Paused){{/if}} ({{duration}})\n{{#if user~}}Request by: @{{user~}}{{/if}}";
 static DEFAULT_CURRENT_SONG_STOPPED_TEMPLATE: &str = "Not Playing";
 
+/// Variables available to song file templates.
+#[derive(serde::Serialize)]
+struct Vars<T> {
+    #[serde(flatten)]
+    song: T,
+    /// Information on the stream, used by the `uptime`, `game`, and `title`
+    /// helpers.
+    stream: StreamVars,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct SongFileBuilder {
     pub enabled: bool,
@@ -71,6 +82,8 @@ impl SongFile {
     ) -> Result<()> {
         let (mut song_stream, mut song) = injector.stream::<player::Song>().await;
         let (mut state_stream, mut state) = injector.stream::<player::State>().await;
+        let (mut stream_info_stream, mut stream_info) =
+            injector.stream::<stream_info::StreamInfo>().await;
         let (mut path_stream, path) = settings.stream("path").optional().await?;
 
         let (mut template_stream, template) = settings
@@ -137,23 +150,36 @@ impl SongFile {
                 update = state_stream.select_next_some() => {
                     state = update;
                 }
+                update = stream_info_stream.select_next_some() => {
+                    stream_info = update;
+                }
             }
 
             if let Some(song_file) = &mut song_file {
-                song_file.update_song(song.as_ref(), state).await;
+                let stream = stream_info
+                    .as_ref()
+                    .map(|s| s.template_vars())
+                    .unwrap_or_default();
+
+                song_file.update_song(song.as_ref(), state, stream).await;
             }
         }
     }
 
     /// Write current song. Log any errors.
-    async fn update_song(&self, song: Option<&player::Song>, state: Option<player::State>) {
+    async fn update_song(
+        &self,
+        song: Option<&player::Song>,
+        state: Option<player::State>,
+        stream: StreamVars,
+    ) {
         log::trace!("updating song: {:?} {:?}", song, state);
 
         let state = state.unwrap_or_default();
 
         let result = match song {
-            Some(song) => self.write(song, state),
-            None => self.blank(),
+            Some(song) => self.write(song, state, stream),
+            None => self.stopped(stream),
         };
 
         if let Err(e) = result {
@@ -184,11 +210,28 @@ impl SongFile {
         Ok(())
     }
 
+    /// Write the stopped template to a path.
+    fn stopped(&self, stream: StreamVars) -> Result<(), anyhow::Error> {
+        let stopped_template = match self.stopped_template.as_ref() {
+            Some(stopped_template) => stopped_template,
+            None => return self.blank(),
+        };
+
+        let mut f = self.create_or_truncate()?;
+        stopped_template.render(&mut f, Vars { song: (), stream })?;
+        Ok(())
+    }
+
     /// Write the current song to a path.
-    pub fn write(&self, song: &player::Song, state: player::State) -> Result<(), anyhow::Error> {
+    pub fn write(
+        &self,
+        song: &player::Song,
+        state: player::State,
+        stream: StreamVars,
+    ) -> Result<(), anyhow::Error> {
         let mut f = self.create_or_truncate()?;
-        let data = song.data(state)?;
-        self.template.render(&mut f, &data)?;
+        let song = song.data(state)?;
+        self.template.render(&mut f, Vars { song, stream })?;
         Ok(())
     }
 
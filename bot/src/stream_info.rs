@@ -1,6 +1,7 @@
 use crate::api;
 use crate::api::twitch;
 use crate::prelude::*;
+use crate::template;
 use anyhow::{anyhow, Result};
 use parking_lot::RwLock;
 use std::collections::HashSet;
@@ -29,6 +30,17 @@ pub struct StreamInfo {
 }
 
 impl StreamInfo {
+    /// Get information on the stream to provide to templates.
+    pub fn template_vars(&self) -> template::StreamVars {
+        let data = self.data.read();
+
+        template::StreamVars {
+            game: data.game.clone(),
+            title: data.title.clone(),
+            started_at: data.stream.as_ref().map(|s| s.started_at),
+        }
+    }
+
     /// Check if a name is a subscriber.
     pub fn is_subscriber(&self, name: &str) -> bool {
         self.data.read().subs_set.contains(name)
@@ -1,3 +1,5 @@
+use crate::utils;
+use chrono::{DateTime, Utc};
 use std::collections::HashSet;
 use std::fmt;
 use std::io;
@@ -7,10 +9,353 @@ lazy_static::lazy_static! {
     static ref REGISTRY: handlebars::Handlebars<'static> = {
         let mut reg = handlebars::Handlebars::new();
         reg.register_escape_fn(|s| s.to_string());
+        reg.register_helper("args", Box::new(args_helper));
+        reg.register_helper("balance", Box::new(balance_helper));
+        reg.register_helper("counter", Box::new(counter_helper));
+        reg.register_helper("game", Box::new(game_helper));
+        reg.register_helper("pluralize", Box::new(pluralize_helper));
+        reg.register_helper("randint", Box::new(randint_helper));
+        reg.register_helper("random", Box::new(random_helper));
+        reg.register_helper("time", Box::new(time_helper));
+        reg.register_helper("title", Box::new(title_helper));
+        reg.register_helper("touser", Box::new(touser_helper));
+        reg.register_helper("uptime", Box::new(uptime_helper));
+        reg.register_helper("urlencode", Box::new(urlencode_helper));
         reg
     };
 }
 
+/// Test if the given name is a helper available to templates.
+pub fn is_helper(name: &str) -> bool {
+    REGISTRY.get_helper(name).is_some()
+}
+
+/// Information on the stream provided to templates through the `stream`
+/// field, used by the `uptime`, `game`, and `title` helpers.
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct StreamVars {
+    pub game: Option<String>,
+    pub title: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+}
+
+/// Helper to access the value of a named counter, like `{{counter "deaths"}}`.
+///
+/// Counter values are provided by the caller through the `counters` field
+/// of the data being rendered, and missing counters render as `0`.
+fn counter_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    ctx: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let name = h
+        .param(0)
+        .and_then(|p| p.value().as_str())
+        .ok_or_else(|| handlebars::RenderError::new("expected name of counter"))?;
+
+    let value = ctx
+        .data()
+        .get("counters")
+        .and_then(|c| c.get(&name.to_lowercase()))
+        .and_then(|v| v.as_i64())
+        .unwrap_or_default();
+
+    out.write(&value.to_string())?;
+    Ok(())
+}
+
+/// Helper to pick a random value, like `{{random "heads" "tails"}}`.
+///
+/// A single list parameter picks a random element from the list.
+fn random_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    _: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    use rand::seq::SliceRandom as _;
+
+    let choices = match h.params().as_slice() {
+        [list] if list.value().is_array() => list
+            .value()
+            .as_array()
+            .map(|a| a.iter().collect::<Vec<_>>())
+            .unwrap_or_default(),
+        params => params.iter().map(|p| p.value()).collect::<Vec<_>>(),
+    };
+
+    if let Some(choice) = choices.choose(&mut rand::thread_rng()) {
+        out.write(&value_to_string(choice))?;
+    }
+
+    Ok(())
+}
+
+/// Helper to pick a random number in an inclusive range, like
+/// `{{randint 1 6}}`.
+fn randint_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    _: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    use rand::Rng as _;
+
+    let min = param_i64(h, 0, "expected minimum number")?;
+    let max = param_i64(h, 1, "expected maximum number")?;
+
+    if min > max {
+        return Err(handlebars::RenderError::new(
+            "minimum number must not be greater than maximum",
+        ));
+    }
+
+    let range = rand::distributions::Uniform::new_inclusive(min, max);
+    out.write(&rand::thread_rng().sample(range).to_string())?;
+    Ok(())
+}
+
+/// Helper to show for how long the stream has been live, like `{{uptime}}`.
+fn uptime_helper(
+    _: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    ctx: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let started_at =
+        stream_field(ctx, "started_at").and_then(|s| DateTime::parse_from_rfc3339(s).ok());
+
+    match started_at {
+        Some(started_at) => {
+            let uptime = Utc::now()
+                .signed_duration_since(started_at)
+                .to_std()
+                .unwrap_or_default();
+
+            out.write(&utils::compact_duration(uptime))?;
+        }
+        None => {
+            out.write("offline")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Helper to show the current game, like `{{game}}`.
+fn game_helper(
+    _: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    ctx: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    out.write(stream_field(ctx, "game").unwrap_or_default())?;
+    Ok(())
+}
+
+/// Helper to show the current stream title, like `{{title}}`.
+fn title_helper(
+    _: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    ctx: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    out.write(stream_field(ctx, "title").unwrap_or_default())?;
+    Ok(())
+}
+
+/// Helper to show the balance of a user, like `{{balance name}}`.
+///
+/// Balances are provided by the caller through the `balances` field of the
+/// data being rendered, and missing balances render as `0`. Callers can use
+/// [Template::helper_literals] to find the users named in the template.
+fn balance_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    ctx: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let user = h
+        .param(0)
+        .and_then(|p| p.value().as_str())
+        .ok_or_else(|| handlebars::RenderError::new("expected name of user"))?;
+
+    let user = user.trim_start_matches('@').to_lowercase();
+
+    let value = ctx
+        .data()
+        .get("balances")
+        .and_then(|b| b.get(&user))
+        .and_then(|v| v.as_i64())
+        .unwrap_or_default();
+
+    out.write(&value.to_string())?;
+    Ok(())
+}
+
+/// Helper to show who a command is directed at, like `{{touser}}`.
+///
+/// This is the first argument without a leading `@`, or the name of the
+/// user invoking the command if there are no arguments.
+fn touser_helper(
+    _: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    ctx: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let data = ctx.data();
+
+    let user = data
+        .get("args")
+        .and_then(|a| a.as_str())
+        .and_then(|a| a.split_whitespace().next())
+        .map(|a| a.trim_start_matches('@'))
+        .filter(|a| !a.is_empty())
+        .or_else(|| data.get("name").and_then(|n| n.as_str()))
+        .unwrap_or_default();
+
+    out.write(user)?;
+    Ok(())
+}
+
+/// Helper to show the arguments passed to a command, like `{{args}}`, or a
+/// single argument by its zero-based index, like `{{args 0}}`.
+fn args_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    ctx: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let args = ctx
+        .data()
+        .get("args")
+        .and_then(|a| a.as_str())
+        .unwrap_or_default();
+
+    match h.param(0).and_then(|p| p.value().as_u64()) {
+        Some(n) => out.write(args.split_whitespace().nth(n as usize).unwrap_or_default())?,
+        None => out.write(args.trim())?,
+    }
+
+    Ok(())
+}
+
+/// Helper to show the current time in a time zone, like
+/// `{{time "Europe/Stockholm"}}`, with an optional format like
+/// `{{time "UTC" "%H:%M:%S"}}`.
+fn time_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    _: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let tz = h
+        .param(0)
+        .and_then(|p| p.value().as_str())
+        .ok_or_else(|| handlebars::RenderError::new("expected time zone"))?;
+
+    let tz = str::parse::<chrono_tz::Tz>(tz)
+        .map_err(|e| handlebars::RenderError::new(format!("bad time zone: {}", e)))?;
+
+    let format = h
+        .param(1)
+        .and_then(|p| p.value().as_str())
+        .unwrap_or("%H:%M");
+
+    out.write(&Utc::now().with_timezone(&tz).format(format).to_string())?;
+    Ok(())
+}
+
+/// Helper to pick the singular or plural form of a word depending on a
+/// count, like `{{pluralize count "death"}}` or
+/// `{{pluralize count "goose" "geese"}}`.
+fn pluralize_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    _: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let count = param_i64(h, 0, "expected count")?;
+
+    let singular = h
+        .param(1)
+        .and_then(|p| p.value().as_str())
+        .ok_or_else(|| handlebars::RenderError::new("expected word"))?;
+
+    if count == 1 || count == -1 {
+        out.write(singular)?;
+        return Ok(());
+    }
+
+    match h.param(2).and_then(|p| p.value().as_str()) {
+        Some(plural) => out.write(plural)?,
+        None => out.write(&format!("{}s", singular))?,
+    }
+
+    Ok(())
+}
+
+/// Helper to percent-encode a value for use in a URL, like
+/// `{{urlencode args}}`.
+fn urlencode_helper(
+    h: &handlebars::Helper<'_, '_>,
+    _: &handlebars::Handlebars<'_>,
+    _: &handlebars::Context,
+    _: &mut handlebars::RenderContext<'_, '_>,
+    out: &mut dyn handlebars::Output,
+) -> handlebars::HelperResult {
+    let value = h
+        .param(0)
+        .map(|p| value_to_string(p.value()))
+        .unwrap_or_default();
+
+    let encoded = percent_encoding::utf8_percent_encode(&value, percent_encoding::NON_ALPHANUMERIC);
+    out.write(&encoded.to_string())?;
+    Ok(())
+}
+
+/// Get a field of the `stream` data provided by the caller.
+fn stream_field<'a>(ctx: &'a handlebars::Context, field: &str) -> Option<&'a str> {
+    ctx.data().get("stream")?.get(field)?.as_str()
+}
+
+/// Get a parameter as a number, parsing it if it is a string.
+fn param_i64(
+    h: &handlebars::Helper<'_, '_>,
+    index: usize,
+    error: &'static str,
+) -> Result<i64, handlebars::RenderError> {
+    let value = h.param(index).map(|p| p.value());
+
+    value
+        .and_then(|v| {
+            v.as_i64()
+                .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+        })
+        .ok_or_else(|| handlebars::RenderError::new(error))
+}
+
+/// Convert a value into the string to render for it.
+fn value_to_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Template {
     source: String,
@@ -161,6 +506,47 @@ impl Template {
         }
     }
 
+    /// Collect the string literals passed as the first argument to the given
+    /// helper, like `"setbac"` in `{{balance "setbac"}}`.
+    pub fn helper_literals(&self, helper: &str) -> HashSet<String> {
+        use handlebars::template::{Parameter, TemplateElement};
+        use std::collections::VecDeque;
+
+        let mut out = HashSet::new();
+        let mut queue = self.template.elements.iter().collect::<VecDeque<_>>();
+
+        while let Some(e) = queue.pop_front() {
+            let h = match e {
+                TemplateElement::Expression(h)
+                | TemplateElement::HTMLExpression(h)
+                | TemplateElement::HelperBlock(h) => h,
+                _ => continue,
+            };
+
+            if h.name.as_name() == Some(helper) {
+                if let Some(Parameter::Literal(value)) = h.params.first() {
+                    if let Some(value) = value.as_str() {
+                        out.insert(value.to_string());
+                    }
+                }
+            }
+
+            for p in h.params.iter().chain(h.hash.values()) {
+                if let Parameter::Subexpression(e) = p {
+                    queue.push_back(&*e.element);
+                }
+            }
+
+            // NB: blocks like `{{#if ..}}` keep their contents in nested
+            // templates.
+            for t in h.template.iter().chain(h.inverse.iter()) {
+                queue.extend(t.elements.iter());
+            }
+        }
+
+        out
+    }
+
     /// Access the source of the template.
     pub fn source(&self) -> &str {
         self.source.as_str()
@@ -248,6 +634,8 @@ impl Default for StringOutput {
 mod tests {
     use super::Template;
     use anyhow::Error;
+    use chrono::{Duration, Utc};
+    use serde_json::json;
     use std::collections::HashSet;
 
     #[test]
@@ -262,4 +650,155 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    pub fn test_helper_literals() -> Result<(), Error> {
+        let template = Template::compile(
+            "{{balance name}} {{balance \"@Other\"}} {{pluralize (balance \"third\") \"coin\"}}",
+        )?;
+
+        assert_eq!(
+            vec!["@Other", "third"]
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect::<HashSet<String>>(),
+            template.helper_literals("balance")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_helper_literals_in_blocks() -> Result<(), Error> {
+        let template = Template::compile(
+            "{{#if name}}{{balance \"first\"}}{{else}}{{balance \"second\"}}{{/if}} {{#each list}}{{pluralize (balance \"third\") \"coin\"}}{{/each}}",
+        )?;
+
+        assert_eq!(
+            vec!["first", "second", "third"]
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect::<HashSet<String>>(),
+            template.helper_literals("balance")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_counter_helper() -> Result<(), Error> {
+        let template =
+            Template::compile("Deaths: {{counter \"Deaths\"}}, wins: {{counter \"wins\"}}")?;
+        assert!(template.vars().contains("counter"));
+
+        let data = json!({"counters": {"deaths": 42}});
+        assert_eq!("Deaths: 42, wins: 0", template.render_to_string(&data)?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_random_helper() -> Result<(), Error> {
+        let template = Template::compile("{{random \"heads\" \"tails\"}}")?;
+        let output = template.render_to_string(&json!({}))?;
+        assert!(output == "heads" || output == "tails");
+
+        let template = Template::compile("{{random choices}}")?;
+        let data = json!({"choices": ["rock"]});
+        assert_eq!("rock", template.render_to_string(&data)?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_randint_helper() -> Result<(), Error> {
+        let template = Template::compile("{{randint 1 6}}")?;
+        let output = str::parse::<i64>(&template.render_to_string(&json!({}))?)?;
+        assert!(output >= 1 && output <= 6);
+
+        let template = Template::compile("{{randint 3 3}}")?;
+        assert_eq!("3", template.render_to_string(&json!({}))?);
+
+        let template = Template::compile("{{randint 6 1}}")?;
+        assert!(template.render_to_string(&json!({})).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_uptime_helper() -> Result<(), Error> {
+        let template = Template::compile("{{uptime}}")?;
+        assert_eq!("offline", template.render_to_string(&json!({}))?);
+
+        let started_at = Utc::now() - Duration::minutes(150);
+        let data = json!({"stream": {"started_at": started_at}});
+        assert_eq!("2h 30m", template.render_to_string(&data)?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_game_and_title_helpers() -> Result<(), Error> {
+        let template = Template::compile("{{title}} ({{game}})")?;
+        let data = json!({"stream": {"game": "Celeste", "title": "Any%"}});
+        assert_eq!("Any% (Celeste)", template.render_to_string(&data)?);
+        assert_eq!(" ()", template.render_to_string(&json!({}))?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_balance_helper() -> Result<(), Error> {
+        let template = Template::compile("{{balance name}} {{balance \"@Other\"}}")?;
+        let data = json!({"name": "SetBac", "balances": {"setbac": 42}});
+        assert_eq!("42 0", template.render_to_string(&data)?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_touser_helper() -> Result<(), Error> {
+        let template = Template::compile("Hello {{touser}}")?;
+
+        let data = json!({"name": "setbac", "args": "@friend and more"});
+        assert_eq!("Hello friend", template.render_to_string(&data)?);
+
+        let data = json!({"name": "setbac", "args": ""});
+        assert_eq!("Hello setbac", template.render_to_string(&data)?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_args_helper() -> Result<(), Error> {
+        let template = Template::compile("{{args}}|{{args 1}}|{{args 5}}")?;
+        let data = json!({"args": " foo bar baz "});
+        assert_eq!("foo bar baz|bar|", template.render_to_string(&data)?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_time_helper() -> Result<(), Error> {
+        let template = Template::compile("{{time \"UTC\" \"%Z\"}}")?;
+        assert_eq!("UTC", template.render_to_string(&json!({}))?);
+
+        let template = Template::compile("{{time \"Not/AZone\"}}")?;
+        assert!(template.render_to_string(&json!({})).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_pluralize_helper() -> Result<(), Error> {
+        let template = Template::compile("{{pluralize count \"death\"}}")?;
+        assert_eq!("death", template.render_to_string(&json!({"count": 1}))?);
+        assert_eq!("deaths", template.render_to_string(&json!({"count": 0}))?);
+
+        let template = Template::compile("{{pluralize count \"goose\" \"geese\"}}")?;
+        assert_eq!("geese", template.render_to_string(&json!({"count": "2"}))?);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_urlencode_helper() -> Result<(), Error> {
+        let template = Template::compile("https://example.com/?q={{urlencode args}}")?;
+        let data = json!({"args": "rock & roll"});
+        assert_eq!(
+            "https://example.com/?q=rock%20%26%20roll",
+            template.render_to_string(&data)?
+        );
+        Ok(())
+    }
 }
@@ -349,10 +349,10 @@ fn partition(duration: time::Duration) -> DurationParts {
 }
 
 #[derive(Clone, Copy)]
-pub struct Percentage(u32, u32);
+pub struct Percentage(i64, i64);
 
 /// Format the given part and whole as a percentage.
-pub fn percentage(part: u32, total: u32) -> Percentage {
+pub fn percentage(part: i64, total: i64) -> Percentage {
     Percentage(part, total)
 }
 
@@ -3,7 +3,7 @@ use crate::api;
 use crate::api::setbac::ConnectionMeta;
 use crate::auth;
 use crate::bus;
-use crate::currency::Currency;
+use crate::currency::{Currency, TopOrder};
 use crate::db;
 use crate::injector;
 use crate::message_log;
@@ -23,9 +23,15 @@ use warp::{body, filters, path, Filter as _};
 
 mod cache;
 mod chat;
+mod moderation;
+mod quotes;
 mod settings;
+mod shop;
 
-use self::{cache::Cache, chat::Chat, settings::Settings};
+use self::{
+    cache::Cache, chat::Chat, moderation::Moderation, quotes::Quotes, settings::Settings,
+    shop::Shop,
+};
 
 pub const URL: &str = "http://localhost:12345";
 
@@ -145,6 +151,7 @@ impl Aliases {
             .and(path!("aliases" / Fragment / Fragment / "disabled").and(path::end()))
             .and(body::json())
             .and_then({
+                let api = api.clone();
                 move |channel: Fragment, name: Fragment, body: DisabledBody| {
                     let api = api.clone();
                     async move {
@@ -155,7 +162,26 @@ impl Aliases {
                 }
             });
 
-        return list.or(delete).or(edit).or(edit_disabled).boxed();
+        let edit_restrictions = warp::post()
+            .and(path!("aliases" / Fragment / Fragment / "restrictions").and(path::end()))
+            .and(body::json())
+            .and_then({
+                move |channel: Fragment, name: Fragment, body: db::Restrictions| {
+                    let api = api.clone();
+                    async move {
+                        api.edit_restrictions(channel.as_str(), name.as_str(), body)
+                            .await
+                            .map_err(custom_reject)
+                    }
+                }
+            });
+
+        return list
+            .or(delete)
+            .or(edit)
+            .or(edit_disabled)
+            .or(edit_restrictions)
+            .boxed();
 
         #[derive(serde::Deserialize)]
         pub struct PutAlias {
@@ -206,6 +232,25 @@ impl Aliases {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Set the given alias's restrictions.
+    async fn edit_restrictions(
+        &self,
+        channel: &str,
+        name: &str,
+        restrictions: db::Restrictions,
+    ) -> Result<impl warp::Reply> {
+        if !self
+            .aliases()
+            .await?
+            .edit_restrictions(channel, name, restrictions)
+            .await?
+        {
+            bail!("no alias named `{}`", name);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
     /// Delete the given alias by key.
     async fn delete(&self, channel: &str, name: &str) -> Result<impl warp::Reply> {
         self.aliases().await?.delete(channel, name).await?;
@@ -267,21 +312,43 @@ impl Commands {
             .and(path!("commands" / Fragment / Fragment).and(path::end()))
             .and(body::json())
             .and_then({
+                let api = api.clone();
                 move |channel: Fragment, name: Fragment, body: PutCommand| {
                     let api = api.clone();
                     async move {
-                        api.edit(channel.as_str(), name.as_str(), body.template)
+                        api.edit(channel.as_str(), name.as_str(), body.template, body.args)
                             .await
                             .map_err(custom_reject)
                     }
                 }
             });
 
-        return list.or(delete).or(edit).or(edit_disabled).boxed();
+        let edit_restrictions = warp::post()
+            .and(path!("commands" / Fragment / Fragment / "restrictions").and(path::end()))
+            .and(body::json())
+            .and_then({
+                move |channel: Fragment, name: Fragment, body: db::Restrictions| {
+                    let api = api.clone();
+                    async move {
+                        api.edit_restrictions(channel.as_str(), name.as_str(), body)
+                            .await
+                            .map_err(custom_reject)
+                    }
+                }
+            });
+
+        return list
+            .or(delete)
+            .or(edit)
+            .or(edit_disabled)
+            .or(edit_restrictions)
+            .boxed();
 
         #[derive(serde::Deserialize)]
         pub struct PutCommand {
             template: template::Template,
+            #[serde(default)]
+            args: Option<String>,
         }
     }
 
@@ -305,8 +372,19 @@ impl Commands {
         channel: &str,
         name: &str,
         template: template::Template,
+        args: Option<String>,
     ) -> Result<impl warp::Reply> {
-        self.commands().await?.edit(channel, name, template).await?;
+        // NB: arguments are left alone unless specified, and cleared if empty.
+        let args = match args.as_deref().map(str::trim) {
+            Some("") => Some(None),
+            Some(args) => Some(Some(str::parse::<db::CommandArgs>(args)?)),
+            None => None,
+        };
+
+        self.commands()
+            .await?
+            .edit(channel, name, template, args)
+            .await?;
         Ok(warp::reply::json(&EMPTY))
     }
 
@@ -328,6 +406,25 @@ impl Commands {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Set the given command's restrictions.
+    async fn edit_restrictions(
+        &self,
+        channel: &str,
+        name: &str,
+        restrictions: db::Restrictions,
+    ) -> Result<impl warp::Reply> {
+        if !self
+            .commands()
+            .await?
+            .edit_restrictions(channel, name, restrictions)
+            .await?
+        {
+            bail!("no command named `{}`", name);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
     /// Delete the given command by key.
     async fn delete(&self, channel: &str, name: &str) -> Result<impl warp::Reply> {
         self.commands().await?.delete(channel, name).await?;
@@ -694,6 +791,30 @@ impl Auth {
                 }))
             .boxed();
 
+        let route = route
+            .or(warp::get()
+                .and(warp::path!("temporary-grants").and(path::end()))
+                .and_then({
+                    let api = api.clone();
+                    move || {
+                        let api = api.clone();
+                        async move { api.temporary_grants().await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::delete()
+                .and(warp::path!("temporary-grants" / i32).and(path::end()))
+                .and_then({
+                    let api = api.clone();
+                    move |id: i32| {
+                        let api = api.clone();
+                        async move { api.delete_temporary_grant(id).await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
         let route = route
             .or(warp::get()
                 .and(
@@ -757,6 +878,18 @@ impl Auth {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Get the list of all temporary grants.
+    async fn temporary_grants(&self) -> Result<impl warp::Reply> {
+        let grants = self.auth.list_temporary().await;
+        Ok(warp::reply::json(&grants))
+    }
+
+    /// Revoke a single temporary grant.
+    async fn delete_temporary_grant(&self, id: i32) -> Result<impl warp::Reply> {
+        self.auth.delete_temporary(id).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
     /// Insert a single scope assignment.
     async fn insert_grant(&self, scope: auth::Scope, role: auth::Role) -> Result<impl warp::Reply> {
         self.auth.insert(scope, role).await?;
@@ -787,6 +920,30 @@ struct Api {
     after_streams: injector::Var<Option<db::AfterStreams>>,
     currency: injector::Var<Option<Currency>>,
     latest: injector::Var<Option<api::github::Release>>,
+    /// The chat channel of the streamer.
+    channel: injector::Var<Option<String>>,
+}
+
+#[derive(serde::Deserialize)]
+pub struct TopQuery {
+    #[serde(default = "default_top_order")]
+    order: TopOrder,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+fn default_top_order() -> TopOrder {
+    TopOrder::Amount
+}
+
+#[derive(serde::Deserialize)]
+pub struct TransactionsQuery {
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    offset: i64,
+    #[serde(default)]
+    limit: Option<i64>,
 }
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
@@ -930,6 +1087,47 @@ impl Api {
         Ok(warp::reply::json(&balances))
     }
 
+    /// Get the top balances.
+    async fn top_balances(self, channel: &str, query: TopQuery) -> Result<impl warp::Reply, Error> {
+        let limit = query.limit.unwrap_or(10).min(100).max(1);
+
+        let currency = self.currency.read().await;
+        let currency = currency.as_ref().ok_or_else(|| Error::NotFound)?;
+
+        if query.order == TopOrder::WatchTime && !currency.has_watch_time() {
+            return Err(Error::BadRequest);
+        }
+
+        let balances = currency.top(channel, query.order, limit).await?;
+        Ok(warp::reply::json(&balances))
+    }
+
+    /// Get the top balances in the channel of the streamer.
+    async fn top_balances_current(self, query: TopQuery) -> Result<impl warp::Reply, Error> {
+        let channel = self.channel.load().await.ok_or_else(|| Error::NotFound)?;
+        self.top_balances(&channel, query).await
+    }
+
+    /// Get a page of currency transactions.
+    async fn currency_transactions(
+        self,
+        channel: &str,
+        query: TransactionsQuery,
+    ) -> Result<impl warp::Reply, Error> {
+        let limit = query.limit.unwrap_or(50).min(100).max(1);
+
+        let transactions = self
+            .currency
+            .read()
+            .await
+            .as_ref()
+            .ok_or_else(|| Error::NotFound)?
+            .transactions(channel, query.user.as_deref(), query.offset.max(0), limit)
+            .await?;
+
+        Ok(warp::reply::json(&transactions))
+    }
+
     /// Get version information.
     async fn version(&self) -> Result<impl warp::Reply, Error> {
         let info = Version {
@@ -983,6 +1181,7 @@ pub async fn setup(
     message_bus: Arc<bus::Bus<message_log::Event>>,
     global_bus: Arc<bus::Bus<bus::Global>>,
     youtube_bus: Arc<bus::Bus<bus::YouTube>>,
+    queue_bus: Arc<bus::Bus<bus::Queue>>,
     command_bus: Arc<bus::Bus<bus::Command>>,
     auth: auth::Auth,
     channel: injector::Var<Option<String>>,
@@ -993,11 +1192,14 @@ pub async fn setup(
     let player = injector::Var::new(None);
     let active_connections: Arc<RwLock<HashMap<String, ConnectionMeta>>> = Default::default();
 
+    let overlay_channel = channel.clone();
+
     let api = Api {
         player: player.clone(),
         after_streams: injector.var().await?,
         currency: injector.var().await?,
         latest,
+        channel: channel.clone(),
     };
 
     let api = {
@@ -1071,6 +1273,56 @@ pub async fn setup(
                 }))
             .boxed();
 
+        let route = route
+            .or(warp::get()
+                .and(path!("currency" / "transactions" / Fragment))
+                .and(warp::query::<TransactionsQuery>())
+                .and_then({
+                    let api = api.clone();
+                    move |channel: Fragment, query: TransactionsQuery| {
+                        let api = api.clone();
+
+                        async move {
+                            api.currency_transactions(channel.as_str(), query)
+                                .await
+                                .map_err(custom_reject)
+                        }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::get()
+                .and(path!("balances" / "top"))
+                .and(warp::query::<TopQuery>())
+                .and_then({
+                    let api = api.clone();
+                    move |query: TopQuery| {
+                        let api = api.clone();
+
+                        async move { api.top_balances_current(query).await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::get()
+                .and(path!("balances" / "top" / Fragment))
+                .and(warp::query::<TopQuery>())
+                .and_then({
+                    let api = api.clone();
+                    move |channel: Fragment, query: TopQuery| {
+                        let api = api.clone();
+
+                        async move {
+                            api.top_balances(channel.as_str(), query)
+                                .await
+                                .map_err(custom_reject)
+                        }
+                    }
+                }))
+            .boxed();
+
         let route = route
             .or(warp::get().and(warp::path("balances")).and_then({
                 move || {
@@ -1092,8 +1344,18 @@ pub async fn setup(
         let route = route.or(Commands::route(injector.var().await?));
         let route = route.or(Promotions::route(injector.var().await?));
         let route = route.or(Themes::route(injector.var().await?));
+        let route = route.or(Shop::route(
+            injector.var().await?,
+            injector.var().await?,
+            global_bus.clone(),
+        ));
+        let route = route.or(Quotes::route(injector.var().await?));
         let route = route.or(Settings::route(injector.var().await?));
         let route = route.or(Cache::route(injector.var().await?));
+        let route = route.or(Moderation::route(
+            injector.var().await?,
+            command_bus.clone(),
+        ));
         let route = route.or(Chat::route(command_bus, message_log));
 
         // TODO: move endpoint into abstraction thingie.
@@ -1117,20 +1379,25 @@ pub async fn setup(
 
     let ws_messages = warp::get()
         .and(warp::path!("ws" / "messages"))
-        .and(send_bus(message_bus).recover(recover));
+        .and(send_bus(message_bus, None).recover(recover));
 
     let ws_overlay = warp::get()
         .and(warp::path!("ws" / "overlay"))
-        .and(send_bus(global_bus).recover(recover));
+        .and(send_bus(global_bus, Some(overlay_channel)).recover(recover));
 
     let ws_youtube = warp::get()
         .and(warp::path!("ws" / "youtube"))
-        .and(send_bus(youtube_bus).recover(recover));
+        .and(send_bus(youtube_bus, None).recover(recover));
+
+    let ws_queue = warp::get()
+        .and(warp::path!("ws" / "queue"))
+        .and(send_bus(queue_bus, None).recover(recover));
 
     let routes = api.recover(recover);
     let routes = routes.or(ws_messages.recover(recover));
     let routes = routes.or(ws_overlay.recover(recover));
     let routes = routes.or(ws_youtube.recover(recover));
+    let routes = routes.or(ws_queue.recover(recover));
 
     let fallback = Asset::get("index.html");
 
@@ -1293,7 +1560,13 @@ pub struct ReceivedToken {
 }
 
 /// Connecting a bus to a websocket connection.
-fn send_bus<T>(bus: Arc<bus::Bus<T>>) -> filters::BoxedFilter<(impl warp::Reply,)>
+///
+/// If a channel is specified, messages belonging to other channels are not
+/// forwarded.
+fn send_bus<T>(
+    bus: Arc<bus::Bus<T>>,
+    channel: Option<injector::Var<Option<String>>>,
+) -> filters::BoxedFilter<(impl warp::Reply,)>
 where
     T: bus::Message,
 {
@@ -1301,9 +1574,10 @@ where
         .map({
             move |ws: warp::ws::Ws| {
                 let bus = bus.clone();
+                let channel = channel.clone();
 
                 ws.on_upgrade(move |websocket: warp::filters::ws::WebSocket| async {
-                    if let Err(e) = send_bus_forward(bus, websocket).await {
+                    if let Err(e) = send_bus_forward(bus, channel, websocket).await {
                         log::error!("websocket error: {}", e);
                     }
                 })
@@ -1315,6 +1589,7 @@ where
 /// Forward the bus message to the websocket.
 async fn send_bus_forward<T>(
     bus: Arc<bus::Bus<T>>,
+    channel: Option<injector::Var<Option<String>>>,
     websocket: warp::filters::ws::WebSocket,
 ) -> Result<()>
 where
@@ -1327,15 +1602,40 @@ where
 
     // send all cached messages.
     for m in bus.latest().await {
+        if is_other_channel(&m, channel.as_ref()).await {
+            continue;
+        }
+
         let m = filters::ws::Message::text(serde_json::to_string(&m)?);
         tx.send(m).await?;
     }
 
     while let Some(m) = rx.next().await {
         let m = m?;
+
+        if is_other_channel(&m, channel.as_ref()).await {
+            continue;
+        }
+
         let m = filters::ws::Message::text(serde_json::to_string(&m)?);
         tx.send(m).await?;
     }
 
     Ok(())
 }
+
+/// Test if the message belongs to a different channel than the given one.
+async fn is_other_channel<T>(m: &T, channel: Option<&injector::Var<Option<String>>>) -> bool
+where
+    T: bus::Message,
+{
+    let channel = match channel {
+        Some(channel) => channel.load().await,
+        None => return false,
+    };
+
+    match (m.channel(), channel) {
+        (Some(a), Some(b)) => a != b,
+        _ => false,
+    }
+}
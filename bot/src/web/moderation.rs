@@ -0,0 +1,120 @@
+use crate::bus;
+use crate::db;
+use crate::injector;
+use crate::web::{Fragment, EMPTY};
+use anyhow::{bail, Result};
+use std::sync::Arc;
+use tokio::sync::RwLockReadGuard;
+use warp::{filters, path, Filter as _};
+
+/// Moderation log endpoints.
+#[derive(Clone)]
+pub struct Moderation {
+    moderation_log: injector::Var<Option<db::ModerationLog>>,
+    bus: Arc<bus::Bus<bus::Command>>,
+}
+
+impl Moderation {
+    pub fn route(
+        moderation_log: injector::Var<Option<db::ModerationLog>>,
+        bus: Arc<bus::Bus<bus::Command>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Moderation {
+            moderation_log,
+            bus,
+        };
+
+        let list = warp::get()
+            .and(path!("moderation" / Fragment).and(path::end()))
+            .and(warp::query::<db::ModerationLogFilter>())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, filter: db::ModerationLogFilter| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_str(), filter)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let undo = warp::post()
+            .and(path!("moderation" / Fragment / i32 / "undo").and(path::end()))
+            .and_then({
+                move |channel: Fragment, id: i32| {
+                    let api = api.clone();
+                    async move {
+                        api.undo(channel.as_str(), id)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        list.or(undo).boxed()
+    }
+
+    /// Access underlying moderation log abstraction.
+    async fn moderation_log(&self) -> Result<RwLockReadGuard<'_, db::ModerationLog>> {
+        match RwLockReadGuard::try_map(self.moderation_log.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("moderation log not configured"),
+        }
+    }
+
+    /// List moderation actions matching the filter.
+    async fn list(
+        &self,
+        channel: &str,
+        filter: db::ModerationLogFilter,
+    ) -> Result<impl warp::Reply> {
+        let entries = self.moderation_log().await?.list(channel, filter).await?;
+        Ok(warp::reply::json(&entries))
+    }
+
+    /// Undo the timeout or ban recorded in the given entry.
+    async fn undo(&self, channel: &str, id: i32) -> Result<impl warp::Reply> {
+        let moderation_log = self.moderation_log().await?;
+
+        let entry = match moderation_log.get(channel, id).await? {
+            Some(entry) => entry,
+            None => bail!("no moderation action with id {}", id),
+        };
+
+        let (action, command) = if entry.action.starts_with("timeout") {
+            let command = bus::Command::Untimeout {
+                channel: entry.channel.clone(),
+                user: entry.user.clone(),
+            };
+
+            ("untimeout", command)
+        } else if entry.action == "ban" {
+            let command = bus::Command::Unban {
+                channel: entry.channel.clone(),
+                user: entry.user.clone(),
+            };
+
+            ("unban", command)
+        } else {
+            bail!("can't undo `{}`", entry.action);
+        };
+
+        self.bus.send(command).await;
+
+        moderation_log
+            .record(db::InsertModerationLogEntry {
+                channel: channel.to_string(),
+                action: action.to_string(),
+                user: entry.user,
+                message: None,
+                rule: Some(format!("undo #{}", entry.id)),
+                actor: Some(String::from("web")),
+            })
+            .await?;
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
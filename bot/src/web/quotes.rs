@@ -0,0 +1,141 @@
+use crate::db;
+use crate::injector;
+use crate::web::{Fragment, EMPTY};
+use anyhow::{bail, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::{body, filters, path, Filter as _};
+
+#[derive(serde::Deserialize)]
+struct QuoteBody {
+    quote: String,
+    #[serde(default)]
+    added_by: Option<String>,
+    #[serde(default)]
+    game: Option<String>,
+}
+
+/// Quotes endpoints.
+#[derive(Clone)]
+pub struct Quotes(injector::Var<Option<db::Quotes>>);
+
+impl Quotes {
+    pub fn route(
+        quotes: injector::Var<Option<db::Quotes>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Quotes(quotes);
+
+        let list = warp::get()
+            .and(path!("quotes" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let add = warp::post()
+            .and(path!("quotes" / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, body: QuoteBody| {
+                    let api = api.clone();
+                    async move {
+                        api.add(channel.as_str(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let edit = warp::put()
+            .and(path!("quotes" / Fragment / i32).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, id: i32, body: QuoteBody| {
+                    let api = api.clone();
+                    async move {
+                        api.edit(channel.as_str(), id, body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let delete = warp::delete()
+            .and(path!("quotes" / Fragment / i32).and(path::end()))
+            .and_then({
+                move |channel: Fragment, id: i32| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(channel.as_str(), id)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        list.or(add).or(edit).or(delete).boxed()
+    }
+
+    /// Access underlying quotes abstraction.
+    async fn quotes(&self) -> Result<RwLockReadGuard<'_, db::Quotes>> {
+        match RwLockReadGuard::try_map(self.0.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("quotes not configured"),
+        }
+    }
+
+    /// List all quotes.
+    async fn list(&self, channel: &str) -> Result<impl warp::Reply> {
+        let quotes = self.quotes().await?.list(channel).await?;
+        Ok(warp::reply::json(&quotes))
+    }
+
+    /// Add a new quote.
+    async fn add(&self, channel: &str, body: QuoteBody) -> Result<impl warp::Reply> {
+        let added_by = body.added_by.as_deref().unwrap_or("web");
+
+        let quote = self
+            .quotes()
+            .await?
+            .add(channel, &body.quote, added_by, body.game.as_deref())
+            .await?;
+
+        Ok(warp::reply::json(&quote))
+    }
+
+    /// Edit the given quote.
+    async fn edit(&self, channel: &str, id: i32, body: QuoteBody) -> Result<impl warp::Reply> {
+        let edited = self
+            .quotes()
+            .await?
+            .edit(channel, id, &body.quote, body.game.as_deref())
+            .await?;
+
+        if !edited {
+            bail!("no quote with id {}", id);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete the given quote.
+    async fn delete(&self, channel: &str, id: i32) -> Result<impl warp::Reply> {
+        if !self.quotes().await?.delete(channel, id).await? {
+            bail!("no quote with id {}", id);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
@@ -0,0 +1,162 @@
+use crate::bus;
+use crate::currency::Currency;
+use crate::db;
+use crate::injector;
+use crate::module;
+use crate::web::Fragment;
+use anyhow::{bail, Result};
+use std::sync::Arc;
+use tokio::sync::RwLockReadGuard;
+use warp::{filters, path, Filter as _};
+
+#[derive(serde::Deserialize)]
+struct RedemptionsQuery {
+    #[serde(default)]
+    state: Option<db::RedemptionState>,
+    #[serde(default)]
+    limit: Option<i64>,
+}
+
+/// Shop endpoints.
+#[derive(Clone)]
+pub struct Shop {
+    shop: injector::Var<Option<db::Shop>>,
+    currency: injector::Var<Option<Currency>>,
+    global_bus: Arc<bus::Bus<bus::Global>>,
+}
+
+impl Shop {
+    pub fn route(
+        shop: injector::Var<Option<db::Shop>>,
+        currency: injector::Var<Option<Currency>>,
+        global_bus: Arc<bus::Bus<bus::Global>>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Shop {
+            shop,
+            currency,
+            global_bus,
+        };
+
+        let items = warp::get()
+            .and(path!("shop" / Fragment / "items").and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.items(channel.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let redemptions = warp::get()
+            .and(path!("shop" / Fragment / "redemptions").and(path::end()))
+            .and(warp::query::<RedemptionsQuery>())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, query: RedemptionsQuery| {
+                    let api = api.clone();
+
+                    async move {
+                        api.redemptions(channel.as_str(), query)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let fulfil = warp::post()
+            .and(path!("shop" / Fragment / "redemptions" / i32 / "fulfil").and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, id: i32| {
+                    let api = api.clone();
+
+                    async move {
+                        api.resolve(channel.as_str(), id, db::RedemptionState::Fulfilled)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let reject = warp::post()
+            .and(path!("shop" / Fragment / "redemptions" / i32 / "reject").and(path::end()))
+            .and_then({
+                move |channel: Fragment, id: i32| {
+                    let api = api.clone();
+
+                    async move {
+                        api.resolve(channel.as_str(), id, db::RedemptionState::Rejected)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        items.or(redemptions).or(fulfil).or(reject).boxed()
+    }
+
+    /// Access underlying shop abstraction.
+    async fn shop(&self) -> Result<RwLockReadGuard<'_, db::Shop>> {
+        match RwLockReadGuard::try_map(self.shop.read().await, |c| c.as_ref()) {
+            Ok(out) => Ok(out),
+            Err(_) => bail!("shop not configured"),
+        }
+    }
+
+    /// List all items in the shop.
+    async fn items(&self, channel: &str) -> Result<impl warp::Reply> {
+        let items = self.shop().await?.items(channel).await?;
+        Ok(warp::reply::json(&items))
+    }
+
+    /// List redemptions, optionally filtered by state.
+    async fn redemptions(
+        &self,
+        channel: &str,
+        query: RedemptionsQuery,
+    ) -> Result<impl warp::Reply> {
+        let limit = query.limit.unwrap_or(50).min(100).max(1);
+
+        let redemptions = self
+            .shop()
+            .await?
+            .redemptions(channel, query.state, limit)
+            .await?;
+
+        Ok(warp::reply::json(&redemptions))
+    }
+
+    /// Fulfil or reject a pending redemption.
+    async fn resolve(
+        &self,
+        channel: &str,
+        id: i32,
+        state: db::RedemptionState,
+    ) -> Result<impl warp::Reply> {
+        let shop = self.shop().await?.clone();
+        let currency = self.currency.load().await;
+
+        let redemption = module::shop::resolve(
+            &shop,
+            currency.as_ref(),
+            &self.global_bus,
+            channel,
+            id,
+            state,
+        )
+        .await?;
+
+        match redemption {
+            Some(redemption) => Ok(warp::reply::json(&redemption)),
+            None => bail!("no pending redemption with id {}", id),
+        }
+    }
+}